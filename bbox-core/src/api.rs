@@ -3,6 +3,8 @@ use crate::ogcapi::*;
 #[derive(Clone, Default)]
 pub struct OgcApiInventory {
     pub landing_page_links: Vec<ApiLink>,
+    /// Additional landing page members like STAC catalog fields
+    pub landing_page_members: serde_json::Map<String, serde_json::Value>,
     pub conformance_classes: Vec<String>,
     pub collections: Vec<CoreCollection>,
 }
@@ -1,11 +1,29 @@
+use actix_session::SessionExt;
+use actix_web::HttpRequest;
+
 #[cfg(feature = "oidc")]
 pub mod oidc;
 
+#[derive(Clone, Debug)]
 pub struct Identity {
     pub username: String,
     pub groups: Vec<String>,
 }
 
+impl Identity {
+    /// Identity of the user logged in with the session of `req`
+    pub fn from_request(req: &HttpRequest) -> Option<Identity> {
+        let session = req.get_session();
+        let username = session.get::<String>("username").ok()??;
+        let groups = session
+            .get::<Vec<String>>("groups")
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        Some(Identity { username, groups })
+    }
+}
+
 #[cfg(not(feature = "oidc"))]
 pub mod oidc {
     use super::Identity;
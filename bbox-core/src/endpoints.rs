@@ -97,6 +97,8 @@ async fn index(ogcapi: web::Data<OgcApiInventory>, req: HttpRequest) -> HttpResp
         title: Some("BBOX OGC API".to_string()),
         description: Some("BBOX OGC API landing page".to_string()),
         links,
+        conforms_to: ogcapi.conformance_classes.to_vec(),
+        foreign_members: ogcapi.landing_page_members.clone(),
     };
     HttpResponse::Ok().json(landing_page)
 }
@@ -9,6 +9,12 @@ pub struct CoreLandingPage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub links: Vec<ApiLink>,
+    /// Conformance classes (required by STAC API)
+    #[serde(rename = "conformsTo", skip_serializing_if = "Vec::is_empty")]
+    pub conforms_to: Vec<String>,
+    /// Additional members like STAC catalog fields
+    #[serde(flatten)]
+    pub foreign_members: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -60,6 +66,11 @@ pub struct CoreCollection {
     pub item_type: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub crs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_crs: Option<String>,
+    /// Additional members like STAC collection fields
+    #[serde(flatten)]
+    pub foreign_members: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -117,6 +128,9 @@ pub struct CoreFeature {
     pub id: Option<String>, // string or integer
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub links: Vec<ApiLink>,
+    /// GeoJSON foreign members like STAC `assets`
+    #[serde(flatten)]
+    pub foreign_members: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -142,9 +156,60 @@ pub struct QueryableProperty {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+/// /collections/{collectionId}/schema
+/// <https://docs.ogc.org/DRAFTS/23-058r1.html#rc_schemas>
+pub struct FeatureSchema {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    #[serde(rename = "$id")]
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String, // object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Properties in column order
+    #[serde(serialize_with = "serialize_ordered_map")]
+    pub properties: Vec<(String, SchemaProperty)>,
+    /// Properties which are not nullable
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
+/// <https://docs.ogc.org/DRAFTS/23-058r1.html#_properties>
+pub struct SchemaProperty {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema type (`string`, `integer`, `number`, `boolean`, `array`, `object`)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// E.g. `date-time` or `geometry-point`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
+    #[serde(rename = "readOnly", skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// E.g. `id`, `primary-geometry` or `primary-instant`
+    #[serde(rename = "x-ogc-role", skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+}
+
+fn serialize_ordered_map<S: serde::Serializer>(
+    entries: &[(String, SchemaProperty)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(entries.iter().map(|(key, value)| (key, value)))
+}
+
 #[derive(Clone, Debug, Serialize)]
 /// <https://docs.ogc.org/DRAFTS/19-079r1.html#queryables>
 pub enum QueryableType {
@@ -1,6 +1,6 @@
 use crate::config::DsPostgisCfg;
 use log::info;
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::postgres::{PgListener, PgPool, PgPoolOptions};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -29,4 +29,10 @@ impl PgDatasource {
             .await?;
         Ok(PgDatasource { pool })
     }
+    /// Listener for notifications on `channel`, connected with the pool options
+    pub async fn listen(&self, channel: &str) -> Result<PgListener> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(channel).await?;
+        Ok(listener)
+    }
 }
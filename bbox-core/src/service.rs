@@ -39,6 +39,10 @@ pub trait OgcApiService: Clone + Send {
     fn landing_page_links(&self, _api_base: &str) -> Vec<ApiLink> {
         Vec::new()
     }
+    /// Additional landing page members
+    fn landing_page_members(&self) -> serde_json::Map<String, serde_json::Value> {
+        serde_json::Map::new()
+    }
     fn conformance_classes(&self) -> Vec<String> {
         Vec::new()
     }
@@ -108,6 +112,9 @@ impl CoreService {
         self.ogcapi
             .landing_page_links
             .extend(svc.landing_page_links(api_base));
+        self.ogcapi
+            .landing_page_members
+            .extend(svc.landing_page_members());
         self.ogcapi
             .conformance_classes
             .extend(svc.conformance_classes());
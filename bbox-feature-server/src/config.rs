@@ -1,11 +1,15 @@
+use bbox_core::auth::Identity;
 use bbox_core::config::{from_config_root_or_exit, ConfigError, DsPostgisCfg, NamedDatasourceCfg};
 use bbox_core::service::ServiceConfig;
 use clap::ArgMatches;
 use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Deserialize, Default, Debug)]
 #[serde(default)]
 pub struct FeatureServiceCfg {
+    #[serde(rename = "featureserver")]
+    pub server: FeatureServerCfg,
     #[serde(rename = "datasource")]
     pub datasources: Vec<NamedDatasourceCfg>,
     #[serde(rename = "collections")]
@@ -14,6 +18,25 @@ pub struct FeatureServiceCfg {
     pub collections: Vec<ConfiguredCollectionCfg>,
 }
 
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureServerCfg {
+    /// Maximal number of features returned by an items request. 0: unlimited (Default: 0)
+    pub max_limit: u32,
+}
+
+impl FeatureServerCfg {
+    /// Effective limit of an items request (`limit=0` requests all features up to `max_limit`)
+    pub fn limit(&self, limit: Option<u32>) -> Option<u32> {
+        match limit {
+            Some(limit) if self.max_limit > 0 && (limit == 0 || limit > self.max_limit) => {
+                Some(self.max_limit)
+            }
+            limit => limit,
+        }
+    }
+}
+
 /// Collections with auto-detection
 #[derive(Deserialize, Default, Debug)]
 #[serde(default, deny_unknown_fields)]
@@ -34,11 +57,60 @@ pub struct ConfiguredCollectionCfg {
     pub name: String,
     pub title: Option<String>,
     pub description: Option<String>,
-    // extent: Option<CoreExtent>
+    /// Static extent (Default: computed from data)
+    pub extent: Option<ExtentCfg>,
+    /// Access rules (Default: public read access)
+    pub access: Option<AccessCfg>,
     #[serde(flatten)]
     pub source: CollectionSourceCfg,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExtentCfg {
+    /// Spatial extent `[minx, miny, maxx, maxy]` in WGS84
+    pub bbox: Option<[f64; 4]>,
+    /// Temporal extent `[start, end]` as RFC 3339 timestamps, `".."` for an open end
+    pub interval: Option<[String; 2]>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AccessCfg {
+    /// Read access without authentication
+    #[serde(default)]
+    pub anonymous: bool,
+    /// Groups with read access (Default: all authenticated users)
+    #[serde(default)]
+    pub read: Vec<String>,
+    /// Groups with read and write access
+    #[serde(default)]
+    pub write: Vec<String>,
+    /// SQL condition restricting readable rows, with `{username}` as placeholder for the user name
+    pub row_filter: Option<String>,
+}
+
+impl AccessCfg {
+    pub fn can_read(&self, identity: Option<&Identity>) -> bool {
+        match identity {
+            None => self.anonymous,
+            Some(identity) => {
+                self.anonymous
+                    || self.read.is_empty()
+                    || member_of(identity, &self.read)
+                    || member_of(identity, &self.write)
+            }
+        }
+    }
+    pub fn can_write(&self, identity: Option<&Identity>) -> bool {
+        matches!(identity, Some(identity) if member_of(identity, &self.write))
+    }
+}
+
+fn member_of(identity: &Identity, groups: &[String]) -> bool {
+    identity.groups.iter().any(|group| groups.contains(group))
+}
+
 /// Collections with configuration
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
@@ -47,6 +119,18 @@ pub enum CollectionSourceCfg {
     Postgis(PostgisCollectionCfg),
     #[serde(rename = "gpkg")]
     Gpkg(GpkgCollectionCfg),
+    #[serde(rename = "fgb")]
+    Fgb(FgbCollectionCfg),
+    #[serde(rename = "geoparquet")]
+    GeoParquet(GeoParquetCollectionCfg),
+    #[serde(rename = "geojson")]
+    GeoJson(FileCollectionCfg),
+    #[serde(rename = "shp")]
+    Shapefile(FileCollectionCfg),
+    #[serde(rename = "stac")]
+    Stac(StacCollectionCfg),
+    #[serde(rename = "pgstac")]
+    Pgstac(PgstacCollectionCfg),
 }
 
 #[derive(Deserialize, Default, Clone, Debug)]
@@ -60,9 +144,20 @@ pub struct PostgisCollectionCfg {
     pub table_name: Option<String>,
     /// Custom SQL query
     pub sql: Option<String>,
+    /// Request parameters bound to `:name` placeholders in `sql`
+    #[serde(default, rename = "parameter")]
+    pub parameters: Vec<SqlParameterCfg>,
     pub fid_field: Option<String>,
     pub geometry_field: Option<String>,
-    //pub field_list: Option<Vec<String>>,
+    /// Fields included in output, in this order (Default: all fields)
+    #[serde(default)]
+    pub include_fields: Vec<String>,
+    /// Fields excluded from output
+    #[serde(default)]
+    pub exclude_fields: Vec<String>,
+    /// Output configuration of individual fields
+    #[serde(default, rename = "field")]
+    pub fields: Vec<FieldCfg>,
     /// Field used for temporal filter expressions
     pub temporal_field: Option<String>,
     /// Field used for temporal end filter expressions
@@ -70,6 +165,100 @@ pub struct PostgisCollectionCfg {
     /// Fields which can be used in filter expressions
     #[serde(default)]
     pub queryable_fields: Vec<String>,
+    /// Text fields searched with the `q` parameter
+    #[serde(default)]
+    pub search_fields: Vec<String>,
+    /// Enable create, replace, update and delete requests (requires `table_name`)
+    #[serde(default)]
+    pub transactions: bool,
+    /// Additional output CRS as EPSG codes (WGS84 and storage CRS are supported by default)
+    #[serde(default)]
+    pub crs: Vec<i32>,
+    /// Paging of items with `offset` or with cursor tokens in `next` links (Default: offset)
+    #[serde(default)]
+    pub pagination: PaginationCfg,
+    /// Computation of `numberMatched` in items responses (Default: exact)
+    #[serde(default)]
+    pub number_matched: NumberMatchedCfg,
+    /// Feature change notifications with LISTEN/NOTIFY
+    pub changes: Option<ChangesCfg>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ChangesCfg {
+    /// Notification channel (Default: `bbox_<collection name>`)
+    pub channel: Option<String>,
+    /// Install a trigger on `table_name` sending notifications on the channel
+    #[serde(default)]
+    pub create_trigger: bool,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCfg {
+    /// Column name
+    pub name: String,
+    /// Property name in output (Default: column name)
+    pub alias: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Round numeric values to the given number of decimal places
+    pub decimals: Option<u32>,
+}
+
+impl FieldCfg {
+    /// Property name in output
+    pub fn property_name(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SqlParameterCfg {
+    /// Placeholder name, used as request parameter
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub type_: SqlParameterType,
+    /// Value used when the request parameter is missing
+    pub default: Option<String>,
+    /// Reject requests without this parameter
+    #[serde(default)]
+    pub required: bool,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlParameterType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Datetime,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PaginationCfg {
+    #[default]
+    Offset,
+    /// Keyset pagination with sort key values of the last feature
+    Cursor,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberMatchedCfg {
+    /// Count of all matching features
+    #[default]
+    Exact,
+    /// Row estimate of table statistics for unfiltered requests
+    Estimated,
+    /// No `numberMatched`
+    None,
 }
 
 #[derive(Deserialize, Default, Debug)]
@@ -80,9 +269,104 @@ pub struct GpkgCollectionCfg {
     pub table_name: Option<String>,
     /// Custom SQL query
     pub sql: Option<String>,
+    /// Request parameters bound to `:name` placeholders in `sql`
+    #[serde(default, rename = "parameter")]
+    pub parameters: Vec<SqlParameterCfg>,
     pub fid_field: Option<String>,
     pub geometry_field: Option<String>,
-    //pub field_list: Option<Vec<String>>,
+    /// Fields included in output, in this order (Default: all fields)
+    #[serde(default)]
+    pub include_fields: Vec<String>,
+    /// Fields excluded from output
+    #[serde(default)]
+    pub exclude_fields: Vec<String>,
+    /// Output configuration of individual fields
+    #[serde(default, rename = "field")]
+    pub fields: Vec<FieldCfg>,
+    /// Field used for temporal filter expressions
+    pub temporal_field: Option<String>,
+    /// Field used for temporal end filter expressions
+    pub temporal_end_field: Option<String>,
+    /// Fields which can be used in filter expressions (Default: all columns)
+    #[serde(default)]
+    pub queryable_fields: Vec<String>,
+    /// Text fields searched with the `q` parameter (requires `table_name`)
+    #[serde(default)]
+    pub search_fields: Vec<String>,
+    /// Enable create, replace, update and delete requests (requires `table_name`)
+    #[serde(default)]
+    pub transactions: bool,
+    /// Additional output CRS as EPSG codes (requires feature `projtransform`)
+    #[serde(default)]
+    pub crs: Vec<i32>,
+}
+
+/// FlatGeobuf file (requires feature `flatgeobuf`)
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FgbCollectionCfg {
+    /// Path of `.fgb` file, relative to the configuration file
+    pub path: PathBuf,
+    /// Property used as feature id
+    pub fid_field: Option<String>,
+    /// Fields which can be used in filter expressions (Default: all columns)
+    #[serde(default)]
+    pub queryable_fields: Vec<String>,
+    /// Additional output CRS as EPSG codes (requires feature `projtransform`)
+    #[serde(default)]
+    pub crs: Vec<i32>,
+}
+
+/// GeoParquet file (requires feature `geoparquet`)
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GeoParquetCollectionCfg {
+    /// Path of `.parquet` file, relative to the configuration file
+    pub path: PathBuf,
+    /// Column used as feature id
+    pub fid_field: Option<String>,
+    /// Fields which can be used in filter expressions (Default: all columns)
+    #[serde(default)]
+    pub queryable_fields: Vec<String>,
+    /// Additional output CRS as EPSG codes (requires feature `projtransform`)
+    #[serde(default)]
+    pub crs: Vec<i32>,
+}
+
+/// GeoJSON file or Shapefile loaded into memory
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FileCollectionCfg {
+    /// Path of `.geojson` or `.shp` file, relative to the configuration file
+    pub path: PathBuf,
+    /// Property used as feature id (Default: feature number)
+    pub fid_field: Option<String>,
+    /// Fields which can be used in filter expressions (Default: all columns)
+    #[serde(default)]
+    pub queryable_fields: Vec<String>,
+    /// EPSG code of the data (Default: 4326 for GeoJSON, WGS84 `.prj` for Shapefiles)
+    pub srid: Option<i32>,
+    /// Additional output CRS as EPSG codes (requires feature `projtransform`)
+    #[serde(default)]
+    pub crs: Vec<i32>,
+}
+
+/// Static STAC collection loaded into memory
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StacCollectionCfg {
+    /// Directory with `collection.json` and STAC item files, relative to the configuration file
+    pub path: PathBuf,
+}
+
+/// STAC collection of a pgstac database
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PgstacCollectionCfg {
+    /// Name of datasource.postgis config (Default: first with matching type)
+    pub datasource: Option<String>,
+    /// pgstac collection id (Default: collection name)
+    pub collection: Option<String>,
 }
 
 impl ServiceConfig for FeatureServiceCfg {
@@ -0,0 +1,134 @@
+//! CQL2-JSON parser.
+
+use super::{parse_date, parse_instant, parse_timestamp, Error, Expr, Geometry, Result};
+use serde_json::Value;
+
+pub fn parse(src: &str) -> Result<Expr> {
+    let value: Value = serde_json::from_str(src).map_err(|e| Error::Syntax(e.to_string()))?;
+    expr(&value)
+}
+
+fn expr(value: &Value) -> Result<Expr> {
+    match value {
+        Value::Bool(b) => Ok(Expr::Bool(*b)),
+        Value::String(s) => Ok(Expr::Str(s.clone())),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(Expr::Int(i))
+            } else {
+                n.as_f64()
+                    .map(Expr::Float)
+                    .ok_or(Error::InvalidValue(n.to_string()))
+            }
+        }
+        Value::Array(list) => Ok(Expr::Array(
+            list.iter().map(expr).collect::<Result<Vec<_>>>()?,
+        )),
+        Value::Object(obj) => {
+            if let Some(op) = obj.get("op") {
+                let op = op
+                    .as_str()
+                    .ok_or(Error::Syntax("`op` must be a string".to_string()))?;
+                let args = match obj.get("args") {
+                    Some(Value::Array(args)) => {
+                        args.iter().map(expr).collect::<Result<Vec<_>>>()?
+                    }
+                    Some(_) => return Err(Error::InvalidArguments(op.to_string())),
+                    None => Vec::new(),
+                };
+                Ok(Expr::op(op, args))
+            } else if let Some(prop) = obj.get("property") {
+                prop.as_str()
+                    .map(|p| Expr::Property(p.to_string()))
+                    .ok_or(Error::Syntax("`property` must be a string".to_string()))
+            } else if let Some(ts) = obj.get("timestamp") {
+                parse_timestamp(ts.as_str().unwrap_or_default())
+            } else if let Some(date) = obj.get("date") {
+                parse_date(date.as_str().unwrap_or_default())
+            } else if let Some(interval) = obj.get("interval") {
+                let bounds = interval
+                    .as_array()
+                    .filter(|bounds| bounds.len() == 2)
+                    .ok_or(Error::InvalidArguments("interval".to_string()))?;
+                Ok(Expr::Interval(
+                    Box::new(interval_bound(&bounds[0])?),
+                    Box::new(interval_bound(&bounds[1])?),
+                ))
+            } else if let Some(bbox) = obj.get("bbox") {
+                let coords = bbox
+                    .as_array()
+                    .map(|coords| coords.iter().filter_map(Value::as_f64).collect::<Vec<_>>())
+                    .filter(|coords| coords.len() == 4 || coords.len() == 6)
+                    .ok_or(Error::InvalidArguments("bbox".to_string()))?;
+                Ok(Expr::BBox(coords))
+            } else if obj.contains_key("type")
+                && (obj.contains_key("coordinates") || obj.contains_key("geometries"))
+            {
+                Ok(Expr::Geometry(Geometry::GeoJson(value.clone())))
+            } else {
+                Err(Error::Syntax(format!("unexpected object {value}")))
+            }
+        }
+        Value::Null => Err(Error::Syntax("unexpected null".to_string())),
+    }
+}
+
+fn interval_bound(value: &Value) -> Result<Expr> {
+    match value {
+        Value::String(s) => parse_instant(s),
+        Value::Object(_) => match expr(value)? {
+            e @ (Expr::Timestamp(_) | Expr::Date(_) | Expr::Property(_)) => Ok(e),
+            _ => Err(Error::InvalidArguments("interval".to_string())),
+        },
+        _ => Err(Error::InvalidArguments("interval".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_expressions() {
+        assert_eq!(
+            parse(r#"{"op":"in","args":[{"property":"name"},["Rhein","Aare"]]}"#).unwrap(),
+            Expr::op(
+                "in",
+                vec![
+                    Expr::Property("name".to_string()),
+                    Expr::Array(vec![
+                        Expr::Str("Rhein".to_string()),
+                        Expr::Str("Aare".to_string())
+                    ])
+                ]
+            )
+        );
+        assert_eq!(
+            parse(r#"{"op":"isNull","args":[{"property":"name"}]}"#).unwrap(),
+            Expr::op("isnull", vec![Expr::Property("name".to_string())])
+        );
+        let geom = serde_json::json!({"type": "Point", "coordinates": [7.5, 47.1]});
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op":"s_within","args":[{{"property":"geom"}},{geom}]}}"#
+            ))
+            .unwrap(),
+            Expr::op(
+                "s_within",
+                vec![
+                    Expr::Property("geom".to_string()),
+                    Expr::Geometry(Geometry::GeoJson(geom))
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn json_errors() {
+        assert!(parse("name = 'Rhein'").is_err());
+        assert!(parse(r#"{"op":"=","args":{"property":"name"}}"#).is_err());
+        assert!(parse(r#"{"foo":"bar"}"#).is_err());
+        assert!(parse(r#"{"timestamp":"yesterday"}"#).is_err());
+        assert!(parse(r#"{"interval":["2021-01-01"]}"#).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! CQL2 filter expressions (OGC API Features Part 3).
+//!
+//! CQL2-Text and CQL2-JSON are both parsed into the same [`Expr`] tree,
+//! which mirrors the `{"op": ..., "args": [...]}` structure of CQL2-JSON.
+
+mod json;
+mod sql;
+mod text;
+
+pub use sql::{to_sql, Dialect, SpatialIndex, SqlContext, SqlPart, SqlValue};
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("syntax error - {0}")]
+    Syntax(String),
+    #[error("unsupported filter language `{0}`")]
+    UnsupportedLanguage(String),
+    #[error("unsupported operator `{0}` - {1}")]
+    UnsupportedOperator(String, String),
+    #[error("invalid arguments for operator `{0}`")]
+    InvalidArguments(String),
+    #[error("property `{0}` is not queryable")]
+    UnknownProperty(String),
+    #[error("invalid value `{0}`")]
+    InvalidValue(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// CQL2 expression tree
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// Operation with lowercase operator name (e.g. `and`, `=`, `like`, `s_intersects`)
+    Op {
+        op: String,
+        args: Vec<Expr>,
+    },
+    Property(String),
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Timestamp(DateTime<FixedOffset>),
+    Date(NaiveDate),
+    /// Interval with start and end. Open ends are represented as `Str("..")`.
+    Interval(Box<Expr>, Box<Expr>),
+    Geometry(Geometry),
+    /// Bounding box with 4 or 6 values
+    BBox(Vec<f64>),
+    Array(Vec<Expr>),
+}
+
+/// Geometry literal
+#[derive(Clone, Debug, PartialEq)]
+pub enum Geometry {
+    /// WKT geometry from CQL2-Text
+    Wkt(String),
+    /// GeoJSON geometry from CQL2-JSON
+    GeoJson(serde_json::Value),
+}
+
+/// Parse CQL2-Text expression
+pub fn parse_text(filter: &str) -> Result<Expr> {
+    text::parse(filter)
+}
+
+/// Parse CQL2-JSON expression
+pub fn parse_json(filter: &str) -> Result<Expr> {
+    json::parse(filter)
+}
+
+impl Expr {
+    pub(crate) fn op(op: &str, args: Vec<Expr>) -> Expr {
+        Expr::Op {
+            op: op.to_lowercase(),
+            args,
+        }
+    }
+}
+
+pub(crate) fn parse_timestamp(value: &str) -> Result<Expr> {
+    DateTime::parse_from_rfc3339(value)
+        .map(Expr::Timestamp)
+        .map_err(|_| Error::InvalidValue(value.to_string()))
+}
+
+pub(crate) fn parse_date(value: &str) -> Result<Expr> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(Expr::Date)
+        .map_err(|_| Error::InvalidValue(value.to_string()))
+}
+
+/// Interval boundary from a string literal
+pub(crate) fn parse_instant(value: &str) -> Result<Expr> {
+    if value == ".." {
+        Ok(Expr::Str(value.to_string()))
+    } else if value.len() == 10 {
+        parse_date(value)
+    } else {
+        parse_timestamp(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_and_json_equivalence() {
+        let text = parse_text("name = 'Rhein' AND scalerank < 5").unwrap();
+        let json = parse_json(
+            r#"{"op":"and","args":[
+                {"op":"=","args":[{"property":"name"},"Rhein"]},
+                {"op":"<","args":[{"property":"scalerank"},5]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(text, json);
+
+        let text = parse_text("S_INTERSECTS(geom, BBOX(5.6,45.8,10.9,47.6))").unwrap();
+        let json = parse_json(
+            r#"{"op":"s_intersects","args":[{"property":"geom"},{"bbox":[5.6,45.8,10.9,47.6]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(text, json);
+
+        let text = parse_text("T_INTERSECTS(ts, INTERVAL('2021-01-01T00:00:00Z', '..'))").unwrap();
+        let json = parse_json(
+            r#"{"op":"t_intersects","args":[{"property":"ts"},{"interval":["2021-01-01T00:00:00Z",".."]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(text, json);
+    }
+}
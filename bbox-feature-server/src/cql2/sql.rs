@@ -0,0 +1,788 @@
+//! Translation of CQL2 expressions into SQL with bind parameters.
+
+use super::{parse_instant, Error, Expr, Geometry, Result};
+use crate::datasource::GeometryTransform;
+use bbox_core::ogcapi::QueryableType;
+use chrono::{DateTime, FixedOffset, NaiveDate, SecondsFormat};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+    Postgis,
+    Gpkg,
+}
+
+/// Collection information needed for SQL generation
+pub struct SqlContext<'a> {
+    pub dialect: Dialect,
+    pub geometry_column: &'a str,
+    /// Properties which can be used in filter expressions
+    pub queryables: &'a HashMap<String, QueryableType>,
+    /// SRID of geometry literals in the filter expression
+    pub filter_srid: i32,
+    /// SRID of the geometry column, if known
+    pub storage_srid: Option<i32>,
+    /// R-tree index used for spatial operators in dialects without spatial SQL functions
+    pub spatial_index: Option<SpatialIndex<'a>>,
+}
+
+/// R-tree spatial index with feature envelopes
+pub struct SpatialIndex<'a> {
+    /// R-tree table with columns `id`, `minx`, `maxx`, `miny`, `maxy`
+    pub rtree: &'a str,
+    /// Column of the features matching the R-tree `id`
+    pub id_column: &'a str,
+    /// Transformation of literal envelopes into the storage CRS, if it differs from the filter CRS
+    pub transform: Option<&'a GeometryTransform>,
+}
+
+/// Value passed as bind parameter
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Timestamp(DateTime<FixedOffset>),
+    Date(NaiveDate),
+}
+
+/// SQL fragment or bind parameter, to be pushed into a query builder
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlPart {
+    Sql(String),
+    Bind(SqlValue),
+}
+
+/// Translate a boolean CQL2 expression into a SQL condition
+pub fn to_sql(expr: &Expr, ctx: &SqlContext) -> Result<Vec<SqlPart>> {
+    let mut writer = SqlWriter {
+        ctx,
+        parts: Vec::new(),
+    };
+    writer.predicate(expr)?;
+    Ok(writer.parts)
+}
+
+/// Temporal interval boundary
+#[derive(Clone, Copy)]
+enum Bound<'e> {
+    NegInf,
+    Value(&'e Expr),
+    PosInf,
+}
+
+impl Bound<'_> {
+    fn rank(&self) -> i8 {
+        match self {
+            Bound::NegInf => -1,
+            Bound::Value(_) => 0,
+            Bound::PosInf => 1,
+        }
+    }
+}
+
+struct SqlWriter<'a, 'c> {
+    ctx: &'c SqlContext<'a>,
+    parts: Vec<SqlPart>,
+}
+
+impl SqlWriter<'_, '_> {
+    fn sql(&mut self, sql: &str) {
+        if let Some(SqlPart::Sql(last)) = self.parts.last_mut() {
+            last.push_str(sql);
+        } else {
+            self.parts.push(SqlPart::Sql(sql.to_string()));
+        }
+    }
+
+    fn bind(&mut self, value: SqlValue) {
+        self.parts.push(SqlPart::Bind(value));
+    }
+
+    fn predicate(&mut self, expr: &Expr) -> Result<()> {
+        let Expr::Op { op, args } = expr else {
+            return match expr {
+                Expr::Bool(true) => {
+                    self.sql("1=1");
+                    Ok(())
+                }
+                Expr::Bool(false) => {
+                    self.sql("1=0");
+                    Ok(())
+                }
+                Expr::Property(_) => self.scalar(expr, Some(&QueryableType::Bool)),
+                _ => Err(Error::Syntax(format!("{expr:?} is not a predicate"))),
+            };
+        };
+        let invalid_args = || Error::InvalidArguments(op.clone());
+        match (op.as_str(), args.as_slice()) {
+            ("and" | "or", [_, ..]) => {
+                let sep = if op == "and" { " AND " } else { " OR " };
+                self.sql("(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.sql(sep);
+                    }
+                    self.predicate(arg)?;
+                }
+                self.sql(")");
+            }
+            ("not", [arg]) => {
+                self.sql("NOT (");
+                self.predicate(arg)?;
+                self.sql(")");
+            }
+            ("=" | "<>" | "<" | ">" | "<=" | ">=", [left, right]) => {
+                if self.is_temporal(left) || self.is_temporal(right) {
+                    self.instant(left)?;
+                    self.sql(&format!(" {op} "));
+                    self.instant(right)?;
+                } else {
+                    let hint = self.type_hint(left).or(self.type_hint(right)).cloned();
+                    self.scalar(left, hint.as_ref())?;
+                    self.sql(&format!(" {op} "));
+                    self.scalar(right, hint.as_ref())?;
+                }
+            }
+            ("like", [value, pattern]) => {
+                self.text(value)?;
+                self.sql(" LIKE ");
+                self.text(pattern)?;
+            }
+            ("between", [value, low, high]) => {
+                let temporal = self.is_temporal(value);
+                let hint = self.type_hint(value).cloned();
+                for (i, arg) in [value, low, high].into_iter().enumerate() {
+                    match i {
+                        1 => self.sql(" BETWEEN "),
+                        2 => self.sql(" AND "),
+                        _ => {}
+                    }
+                    if temporal {
+                        self.instant(arg)?;
+                    } else {
+                        self.scalar(arg, hint.as_ref())?;
+                    }
+                }
+            }
+            ("in", [value, Expr::Array(list)]) if !list.is_empty() => {
+                let hint = self.type_hint(value).cloned();
+                self.scalar(value, hint.as_ref())?;
+                self.sql(" IN (");
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        self.sql(",");
+                    }
+                    self.scalar(item, hint.as_ref())?;
+                }
+                self.sql(")");
+            }
+            ("isnull", [value]) => {
+                if self.is_geometry(value) {
+                    self.geometry(value)?;
+                } else {
+                    self.scalar(value, None)?;
+                }
+                self.sql(" IS NULL");
+            }
+            (spatial, [left, right]) if spatial.starts_with("s_") => {
+                self.spatial(spatial, left, right)?;
+            }
+            (temporal, [left, right]) if temporal.starts_with("t_") => {
+                self.temporal(temporal, left, right)?;
+            }
+            ("and" | "or" | "not" | "=" | "<>" | "<" | ">" | "<=" | ">=", _)
+            | ("like" | "between" | "in" | "isnull", _) => return Err(invalid_args()),
+            _ => {
+                return Err(Error::UnsupportedOperator(
+                    op.clone(),
+                    "unknown operator".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Quoted column name of a queryable property
+    fn property(&mut self, name: &str) -> Result<()> {
+        if !self.ctx.queryables.contains_key(name) {
+            return Err(Error::UnknownProperty(name.to_string()));
+        }
+        self.sql(&quote_ident(name));
+        Ok(())
+    }
+
+    fn type_hint(&self, expr: &Expr) -> Option<&QueryableType> {
+        match expr {
+            Expr::Property(name) => self.ctx.queryables.get(name),
+            _ => None,
+        }
+    }
+
+    fn is_temporal(&self, expr: &Expr) -> bool {
+        matches!(expr, Expr::Timestamp(_) | Expr::Date(_))
+            || matches!(self.type_hint(expr), Some(QueryableType::Datetime))
+    }
+
+    fn is_geometry(&self, expr: &Expr) -> bool {
+        matches!(expr, Expr::Property(name) if name == self.ctx.geometry_column || name == "geometry")
+    }
+
+    /// Scalar value, with literals converted to the type of the compared property
+    fn scalar(&mut self, expr: &Expr, hint: Option<&QueryableType>) -> Result<()> {
+        let value = match expr {
+            Expr::Property(name) => return self.property(name),
+            Expr::Op { op, args } if op == "casei" => {
+                let [arg] = args.as_slice() else {
+                    return Err(Error::InvalidArguments(op.clone()));
+                };
+                self.sql("LOWER(");
+                self.scalar(arg, hint)?;
+                self.sql(")");
+                return Ok(());
+            }
+            Expr::Timestamp(_) | Expr::Date(_) => return self.instant(expr),
+            Expr::Str(s) => match hint {
+                Some(QueryableType::Integer) => {
+                    SqlValue::Int(s.parse().map_err(|_| Error::InvalidValue(s.to_string()))?)
+                }
+                Some(QueryableType::Number) => {
+                    SqlValue::Float(s.parse().map_err(|_| Error::InvalidValue(s.to_string()))?)
+                }
+                Some(QueryableType::Bool) => {
+                    SqlValue::Bool(s.parse().map_err(|_| Error::InvalidValue(s.to_string()))?)
+                }
+                _ => SqlValue::Text(s.clone()),
+            },
+            Expr::Int(i) => match hint {
+                Some(QueryableType::String) => SqlValue::Text(i.to_string()),
+                Some(QueryableType::Number) => SqlValue::Float(*i as f64),
+                _ => SqlValue::Int(*i),
+            },
+            Expr::Float(f) => match hint {
+                Some(QueryableType::String) => SqlValue::Text(f.to_string()),
+                _ => SqlValue::Float(*f),
+            },
+            Expr::Bool(b) => SqlValue::Bool(*b),
+            _ => return Err(Error::InvalidValue(format!("{expr:?}"))),
+        };
+        self.bind(value);
+        Ok(())
+    }
+
+    /// Value used in a text comparison
+    fn text(&mut self, expr: &Expr) -> Result<()> {
+        match self.type_hint(expr) {
+            Some(QueryableType::String) | None => self.scalar(expr, Some(&QueryableType::String)),
+            Some(_) => {
+                self.sql("CAST(");
+                self.scalar(expr, None)?;
+                self.sql(" AS TEXT)");
+                Ok(())
+            }
+        }
+    }
+
+    /// Timestamp or date value
+    fn instant(&mut self, expr: &Expr) -> Result<()> {
+        let gpkg = self.ctx.dialect == Dialect::Gpkg;
+        match expr {
+            Expr::Property(name) => {
+                if gpkg {
+                    // GeoPackage stores ISO 8601 strings
+                    self.sql("julianday(");
+                    self.property(name)?;
+                    self.sql(")");
+                } else {
+                    self.property(name)?;
+                }
+            }
+            Expr::Timestamp(ts) => {
+                if gpkg {
+                    self.sql("julianday(");
+                    self.bind(SqlValue::Text(
+                        ts.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    ));
+                    self.sql(")");
+                } else {
+                    self.bind(SqlValue::Timestamp(*ts));
+                }
+            }
+            Expr::Date(date) => {
+                if gpkg {
+                    self.sql("julianday(");
+                    self.bind(SqlValue::Text(date.format("%Y-%m-%d").to_string()));
+                    self.sql(")");
+                } else {
+                    self.bind(SqlValue::Date(*date));
+                }
+            }
+            Expr::Str(s) if s != ".." => self.instant(&parse_instant(s)?)?,
+            _ => return Err(Error::InvalidValue(format!("{expr:?}"))),
+        }
+        Ok(())
+    }
+
+    fn bounds<'e>(&self, expr: &'e Expr) -> Result<(Bound<'e>, Bound<'e>)> {
+        match expr {
+            Expr::Interval(start, end) => {
+                let start = match start.as_ref() {
+                    Expr::Str(s) if s == ".." => Bound::NegInf,
+                    e => Bound::Value(e),
+                };
+                let end = match end.as_ref() {
+                    Expr::Str(s) if s == ".." => Bound::PosInf,
+                    e => Bound::Value(e),
+                };
+                Ok((start, end))
+            }
+            Expr::Property(_) | Expr::Timestamp(_) | Expr::Date(_) | Expr::Str(_) => {
+                Ok((Bound::Value(expr), Bound::Value(expr)))
+            }
+            _ => Err(Error::InvalidValue(format!("{expr:?}"))),
+        }
+    }
+
+    fn temporal(&mut self, op: &str, left: &Expr, right: &Expr) -> Result<()> {
+        let (a0, a1) = self.bounds(left)?;
+        let (b0, b1) = self.bounds(right)?;
+        if op == "t_disjoint" {
+            self.sql("NOT (");
+            self.temporal("t_intersects", left, right)?;
+            self.sql(")");
+            return Ok(());
+        }
+        let conditions = match op {
+            "t_after" => vec![(a0, ">", b1)],
+            "t_before" => vec![(a1, "<", b0)],
+            "t_contains" => vec![(a0, "<", b0), (a1, ">", b1)],
+            "t_during" => vec![(a0, ">", b0), (a1, "<", b1)],
+            "t_equals" => vec![(a0, "=", b0), (a1, "=", b1)],
+            "t_finishedby" => vec![(a0, "<", b0), (a1, "=", b1)],
+            "t_finishes" => vec![(a0, ">", b0), (a1, "=", b1)],
+            "t_intersects" => vec![(a0, "<=", b1), (a1, ">=", b0)],
+            "t_meets" => vec![(a1, "=", b0)],
+            "t_metby" => vec![(a0, "=", b1)],
+            "t_overlappedby" => vec![(a0, ">", b0), (a0, "<", b1), (a1, ">", b1)],
+            "t_overlaps" => vec![(a0, "<", b0), (a1, ">", b0), (a1, "<", b1)],
+            "t_startedby" => vec![(a0, "=", b0), (a1, ">", b1)],
+            "t_starts" => vec![(a0, "=", b0), (a1, "<", b1)],
+            _ => {
+                return Err(Error::UnsupportedOperator(
+                    op.to_string(),
+                    "unknown temporal operator".to_string(),
+                ))
+            }
+        };
+        // Conditions with open interval ends are constant
+        let mut sql_conditions = Vec::new();
+        for (l, cmp, r) in conditions {
+            match (l, r) {
+                (Bound::Value(l), Bound::Value(r)) => sql_conditions.push((l, cmp, r)),
+                _ => {
+                    let (l, r) = (l.rank(), r.rank());
+                    let holds = match cmp {
+                        "<" => l < r,
+                        ">" => l > r,
+                        "<=" => l <= r,
+                        ">=" => l >= r,
+                        _ => l == r && l != 0,
+                    };
+                    if !holds {
+                        self.sql("1=0");
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        if sql_conditions.is_empty() {
+            self.sql("1=1");
+            return Ok(());
+        }
+        self.sql("(");
+        for (i, (l, cmp, r)) in sql_conditions.into_iter().enumerate() {
+            if i > 0 {
+                self.sql(" AND ");
+            }
+            self.instant(l)?;
+            self.sql(&format!(" {cmp} "));
+            self.instant(r)?;
+        }
+        self.sql(")");
+        Ok(())
+    }
+
+    fn spatial(&mut self, op: &str, left: &Expr, right: &Expr) -> Result<()> {
+        let func = match op {
+            "s_contains" => "ST_Contains",
+            "s_crosses" => "ST_Crosses",
+            "s_disjoint" => "ST_Disjoint",
+            "s_equals" => "ST_Equals",
+            "s_intersects" => "ST_Intersects",
+            "s_overlaps" => "ST_Overlaps",
+            "s_touches" => "ST_Touches",
+            "s_within" => "ST_Within",
+            _ => {
+                return Err(Error::UnsupportedOperator(
+                    op.to_string(),
+                    "unknown spatial operator".to_string(),
+                ))
+            }
+        };
+        if self.ctx.dialect == Dialect::Gpkg {
+            return self.envelope_predicate(op, left, right);
+        }
+        self.sql(&format!("{func}("));
+        self.geometry(left)?;
+        self.sql(",");
+        self.geometry(right)?;
+        self.sql(")");
+        Ok(())
+    }
+
+    /// Spatial predicate on feature envelopes in the R-tree index.
+    /// Results are approximations for geometries not equal to their envelope.
+    fn envelope_predicate(&mut self, op: &str, left: &Expr, right: &Expr) -> Result<()> {
+        let unsupported =
+            |reason: &str| Error::UnsupportedOperator(op.to_string(), reason.to_string());
+        let Some(index) = &self.ctx.spatial_index else {
+            return Err(unsupported(
+                "spatial operators require a table with R-tree spatial index",
+            ));
+        };
+        // Operator with geometry column as first argument
+        let (op, literal) = match (self.is_geometry(left), self.is_geometry(right)) {
+            (true, false) => (op, right),
+            (false, true) => match op {
+                "s_within" => ("s_contains", left),
+                "s_contains" => ("s_within", left),
+                _ => (op, left),
+            },
+            _ => return Err(Error::InvalidArguments(op.to_string())),
+        };
+        let mut envelope = envelope(literal)?;
+        if let Some(transform) = index.transform {
+            envelope = transform
+                .transform_bbox(envelope)
+                .map_err(|e| Error::InvalidValue(e.to_string()))?;
+        } else if matches!(self.ctx.storage_srid, Some(srid) if srid > 0 && srid != self.ctx.filter_srid)
+        {
+            return Err(unsupported(
+                "filter CRS can't be transformed into the storage CRS",
+            ));
+        }
+        let [minx, miny, maxx, maxy] = envelope;
+        let conditions =
+            match op {
+                "s_intersects" => [
+                    ("minx <= ", maxx),
+                    ("maxx >= ", minx),
+                    ("miny <= ", maxy),
+                    ("maxy >= ", miny),
+                ],
+                "s_within" => [
+                    ("minx >= ", minx),
+                    ("maxx <= ", maxx),
+                    ("miny >= ", miny),
+                    ("maxy <= ", maxy),
+                ],
+                "s_contains" => [
+                    ("minx <= ", minx),
+                    ("maxx >= ", maxx),
+                    ("miny <= ", miny),
+                    ("maxy >= ", maxy),
+                ],
+                _ => return Err(unsupported(
+                    "only S_INTERSECTS, S_WITHIN and S_CONTAINS are supported for this datasource",
+                )),
+            };
+        self.sql(&format!(
+            "{} IN (SELECT id FROM {} WHERE ",
+            quote_ident(index.id_column),
+            quote_ident(index.rtree)
+        ));
+        for (i, (condition, value)) in conditions.into_iter().enumerate() {
+            if i > 0 {
+                self.sql(" AND ");
+            }
+            self.sql(condition);
+            self.bind(SqlValue::Float(value));
+        }
+        self.sql(")");
+        Ok(())
+    }
+
+    /// Geometry column or literal in storage SRS
+    fn geometry(&mut self, expr: &Expr) -> Result<()> {
+        let srid = self.ctx.filter_srid;
+        match expr {
+            Expr::Property(name) => {
+                if !self.is_geometry(expr) {
+                    return Err(Error::UnknownProperty(name.to_string()));
+                }
+                self.sql(&quote_ident(self.ctx.geometry_column));
+                return Ok(());
+            }
+            Expr::Geometry(_) | Expr::BBox(_) => {}
+            _ => return Err(Error::InvalidValue(format!("{expr:?}"))),
+        }
+        let transform = match self.ctx.storage_srid {
+            Some(storage_srid) if storage_srid > 0 && storage_srid != srid => {
+                self.sql("ST_Transform(");
+                Some(format!(",{storage_srid})"))
+            }
+            Some(0) => {
+                self.sql("ST_SetSRID(");
+                Some(",0)".to_string())
+            }
+            _ => None,
+        };
+        match expr {
+            Expr::Geometry(Geometry::Wkt(wkt)) => {
+                self.sql("ST_GeomFromText(");
+                self.bind(SqlValue::Text(wkt.clone()));
+                self.sql(&format!(",{srid})"));
+            }
+            Expr::Geometry(Geometry::GeoJson(json)) => {
+                self.sql("ST_SetSRID(ST_GeomFromGeoJSON(");
+                self.bind(SqlValue::Text(json.to_string()));
+                self.sql(&format!("),{srid})"));
+            }
+            Expr::BBox(bbox) => {
+                // Ignore vertical axis
+                let coords = if bbox.len() == 6 {
+                    [bbox[0], bbox[1], bbox[3], bbox[4]]
+                } else {
+                    [bbox[0], bbox[1], bbox[2], bbox[3]]
+                };
+                self.sql("ST_MakeEnvelope(");
+                for coord in coords {
+                    self.bind(SqlValue::Float(coord));
+                    self.sql(",");
+                }
+                self.sql(&format!("{srid})"));
+            }
+            _ => unreachable!(),
+        }
+        if let Some(suffix) = transform {
+            self.sql(&suffix);
+        }
+        Ok(())
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Envelope (minx, miny, maxx, maxy) of a geometry or bbox literal
+fn envelope(expr: &Expr) -> Result<[f64; 4]> {
+    let mut envelope = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    let mut extend = |x: f64, y: f64| {
+        envelope = [
+            envelope[0].min(x),
+            envelope[1].min(y),
+            envelope[2].max(x),
+            envelope[3].max(y),
+        ];
+    };
+    match expr {
+        Expr::BBox(bbox) if bbox.len() == 6 => {
+            extend(bbox[0], bbox[1]);
+            extend(bbox[3], bbox[4]);
+        }
+        Expr::BBox(bbox) if bbox.len() == 4 => {
+            extend(bbox[0], bbox[1]);
+            extend(bbox[2], bbox[3]);
+        }
+        Expr::Geometry(Geometry::Wkt(wkt)) => {
+            // Positions are separated by parentheses and commas, x and y are the first numbers
+            for position in wkt.split(['(', ')', ',']) {
+                let mut coords = position
+                    .split_whitespace()
+                    .filter_map(|token| token.parse::<f64>().ok());
+                if let (Some(x), Some(y)) = (coords.next(), coords.next()) {
+                    extend(x, y);
+                }
+            }
+        }
+        Expr::Geometry(Geometry::GeoJson(json)) => {
+            fn positions(value: &serde_json::Value, extend: &mut impl FnMut(f64, f64)) {
+                let Some(values) = value.as_array() else {
+                    return;
+                };
+                if let [x, y, ..] = values.as_slice() {
+                    if let (Some(x), Some(y)) = (x.as_f64(), y.as_f64()) {
+                        extend(x, y);
+                        return;
+                    }
+                }
+                for value in values {
+                    positions(value, extend);
+                }
+            }
+            positions(&json["coordinates"], &mut extend);
+            for geometry in json["geometries"].as_array().into_iter().flatten() {
+                positions(&geometry["coordinates"], &mut extend);
+            }
+        }
+        _ => return Err(Error::InvalidValue(format!("{expr:?}"))),
+    }
+    if envelope[0] > envelope[2] {
+        return Err(Error::InvalidValue(format!("{expr:?} is empty")));
+    }
+    Ok(envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cql2::parse_text;
+
+    fn queryables() -> HashMap<String, QueryableType> {
+        HashMap::from([
+            ("name".to_string(), QueryableType::String),
+            ("scalerank".to_string(), QueryableType::Integer),
+            ("ts".to_string(), QueryableType::Datetime),
+        ])
+    }
+
+    fn to_sql_string(filter: &str, dialect: Dialect) -> Result<(String, Vec<SqlValue>)> {
+        let queryables = queryables();
+        let ctx = SqlContext {
+            dialect,
+            geometry_column: "geom",
+            queryables: &queryables,
+            filter_srid: 4326,
+            // GeoPackage without transformation of geometry literals
+            storage_srid: Some(if dialect == Dialect::Postgis { 3857 } else { 4326 }),
+            spatial_index: Some(SpatialIndex {
+                rtree: "rtree_places_geom",
+                id_column: "fid",
+                transform: None,
+            }),
+        };
+        let parts = to_sql(&parse_text(filter)?, &ctx)?;
+        let mut sql = String::new();
+        let mut binds = Vec::new();
+        for part in parts {
+            match part {
+                SqlPart::Sql(s) => sql.push_str(&s),
+                SqlPart::Bind(v) => {
+                    sql.push('?');
+                    binds.push(v);
+                }
+            }
+        }
+        Ok((sql, binds))
+    }
+
+    #[test]
+    fn comparison_sql() {
+        let (sql, binds) =
+            to_sql_string("name = 'Rhein' AND scalerank <= '4'", Dialect::Postgis).unwrap();
+        assert_eq!(sql, r#"("name" = ? AND "scalerank" <= ?)"#);
+        assert_eq!(
+            binds,
+            vec![SqlValue::Text("Rhein".to_string()), SqlValue::Int(4)]
+        );
+
+        let (sql, _) =
+            to_sql_string("NOT scalerank IN (1,2) OR name IS NULL", Dialect::Gpkg).unwrap();
+        assert_eq!(sql, r#"(NOT ("scalerank" IN (?,?)) OR "name" IS NULL)"#);
+
+        let (sql, _) = to_sql_string("scalerank LIKE '1%'", Dialect::Postgis).unwrap();
+        assert_eq!(sql, r#"CAST("scalerank" AS TEXT) LIKE ?"#);
+
+        assert!(matches!(
+            to_sql_string("scalerank = 'high'", Dialect::Postgis),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(
+            to_sql_string("pop_max > 1000", Dialect::Postgis),
+            Err(Error::UnknownProperty(_))
+        ));
+    }
+
+    #[test]
+    fn temporal_sql() {
+        let (sql, binds) = to_sql_string(
+            "T_INTERSECTS(ts, INTERVAL('2021-01-01', '..'))",
+            Dialect::Postgis,
+        )
+        .unwrap();
+        assert_eq!(sql, r#"("ts" >= ?)"#);
+        assert_eq!(binds.len(), 1);
+
+        let (sql, _) = to_sql_string(
+            "T_BEFORE(ts, TIMESTAMP('2021-01-01T00:00:00Z'))",
+            Dialect::Gpkg,
+        )
+        .unwrap();
+        assert_eq!(sql, r#"(julianday("ts") < julianday(?))"#);
+
+        let (sql, _) =
+            to_sql_string("T_AFTER(ts, INTERVAL('..', '..'))", Dialect::Postgis).unwrap();
+        assert_eq!(sql, "1=0");
+    }
+
+    #[test]
+    fn spatial_sql() {
+        let (sql, _) =
+            to_sql_string("S_INTERSECTS(geom, POINT(7.5 47.1))", Dialect::Postgis).unwrap();
+        assert_eq!(
+            sql,
+            r#"ST_Intersects("geom",ST_Transform(ST_GeomFromText(?,4326),3857))"#
+        );
+        let (sql, binds) = to_sql_string(
+            "S_WITHIN(geometry, BBOX(5.6,45.8,10.9,47.6))",
+            Dialect::Postgis,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            r#"ST_Within("geom",ST_Transform(ST_MakeEnvelope(?,?,?,?,4326),3857))"#
+        );
+        assert_eq!(binds.len(), 4);
+        assert!(to_sql_string("S_INTERSECTS(name, POINT(7.5 47.1))", Dialect::Postgis).is_err());
+
+        // Envelope test with R-tree index
+        let (sql, binds) = to_sql_string(
+            "S_INTERSECTS(geom, LINESTRING(7.5 47.1, 8.0 46.9))",
+            Dialect::Gpkg,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            r#""fid" IN (SELECT id FROM "rtree_places_geom" WHERE minx <= ? AND maxx >= ? AND miny <= ? AND maxy >= ?)"#
+        );
+        assert_eq!(binds, [8.0, 7.5, 47.1, 46.9].map(SqlValue::Float).to_vec());
+        let (sql, binds) = to_sql_string(
+            "S_WITHIN(BBOX(5.6,45.8,10.9,47.6), geometry)",
+            Dialect::Gpkg,
+        )
+        .unwrap();
+        assert!(sql.contains("minx <= ? AND maxx >= ? AND miny <= ? AND maxy >= ?"));
+        assert_eq!(binds, [5.6, 10.9, 45.8, 47.6].map(SqlValue::Float).to_vec());
+        assert!(to_sql_string("S_TOUCHES(geom, POINT(7.5 47.1))", Dialect::Gpkg).is_err());
+        assert!(to_sql_string("S_INTERSECTS(geom, POINT EMPTY)", Dialect::Gpkg).is_err());
+    }
+
+    #[test]
+    fn geometry_envelope() {
+        let expr = Expr::Geometry(Geometry::Wkt(
+            "MULTIPOLYGON Z (((0 0 5, 4 0 5, 4 3 5, 0 0 5)), ((-1 2 0, 1 2 0, 1 6 0, -1 2 0)))"
+                .to_string(),
+        ));
+        assert_eq!(envelope(&expr).unwrap(), [-1.0, 0.0, 4.0, 6.0]);
+        let expr = Expr::Geometry(Geometry::GeoJson(serde_json::json!({
+            "type": "GeometryCollection",
+            "geometries": [
+                {"type": "Point", "coordinates": [7.5, 47.1]},
+                {"type": "LineString", "coordinates": [[6.0, 46.0], [7.0, 48.0]]}
+            ]
+        })));
+        assert_eq!(envelope(&expr).unwrap(), [6.0, 46.0, 7.5, 48.0]);
+    }
+}
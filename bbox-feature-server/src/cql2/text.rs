@@ -0,0 +1,625 @@
+//! CQL2-Text parser.
+
+use super::{parse_date, parse_instant, parse_timestamp, Error, Expr, Geometry, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    QuotedIdent(String),
+    Str(String),
+    Number(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// Token with byte offsets into the source
+#[derive(Debug)]
+struct Spanned {
+    token: Token,
+    start: usize,
+    end: usize,
+}
+
+const WKT_TYPES: [&str; 7] = [
+    "POINT",
+    "LINESTRING",
+    "POLYGON",
+    "MULTIPOINT",
+    "MULTILINESTRING",
+    "MULTIPOLYGON",
+    "GEOMETRYCOLLECTION",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Spanned>> {
+    let chars: Vec<(usize, char)> = src.char_indices().collect();
+    let byte_pos = |i: usize| chars.get(i).map(|c| c.0).unwrap_or(src.len());
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let start = i;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            '=' => {
+                i += 1;
+                Token::Op("=")
+            }
+            '<' | '>' => {
+                let next = chars.get(i + 1).map(|c| c.1);
+                i += 1;
+                match (c, next) {
+                    ('<', Some('>')) => {
+                        i += 1;
+                        Token::Op("<>")
+                    }
+                    ('<', Some('=')) => {
+                        i += 1;
+                        Token::Op("<=")
+                    }
+                    ('>', Some('=')) => {
+                        i += 1;
+                        Token::Op(">=")
+                    }
+                    ('<', _) => Token::Op("<"),
+                    _ => Token::Op(">"),
+                }
+            }
+            '\'' | '"' => {
+                // Quotes are escaped by doubling them
+                let quote = c;
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i).map(|c| c.1) {
+                        Some(ch) if ch == quote => {
+                            if chars.get(i + 1).map(|c| c.1) == Some(quote) {
+                                value.push(quote);
+                                i += 2;
+                            } else {
+                                i += 1;
+                                break;
+                            }
+                        }
+                        Some(ch) => {
+                            value.push(ch);
+                            i += 1;
+                        }
+                        None => return Err(Error::Syntax("unterminated literal".to_string())),
+                    }
+                }
+                if quote == '"' {
+                    Token::QuotedIdent(value)
+                } else {
+                    Token::Str(value)
+                }
+            }
+            c if c.is_ascii_digit()
+                || ((c == '-' || c == '+' || c == '.')
+                    && chars
+                        .get(i + 1)
+                        .map(|c| c.1.is_ascii_digit() || c.1 == '.')
+                        .unwrap_or(false)) =>
+            {
+                let mut value = String::from(c);
+                i += 1;
+                while let Some(&(_, ch)) = chars.get(i) {
+                    let exp_sign = (ch == '-' || ch == '+')
+                        && matches!(value.chars().last(), Some('e') | Some('E'));
+                    if ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E' || exp_sign {
+                        value.push(ch);
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Number(value)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut value = String::new();
+                while let Some(&(_, ch)) = chars.get(i) {
+                    if ch.is_alphanumeric() || ch == '_' || ch == ':' || ch == '.' {
+                        value.push(ch);
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(value)
+            }
+            c => return Err(Error::Syntax(format!("unexpected character `{c}`"))),
+        };
+        tokens.push(Spanned {
+            token,
+            start: byte_pos(start),
+            end: byte_pos(i),
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+pub fn parse(src: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        src,
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    let expr = parser.boolean_expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(Error::Syntax(format!("unexpected token {token:?}")));
+    }
+    Ok(expr)
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn peek_next(&self) -> Option<&Token> {
+        self.tokens.get(self.pos + 1).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|t| t.token.clone())
+            .ok_or(Error::Syntax("unexpected end of expression".to_string()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(Error::Syntax(format!(
+                "expected {expected:?}, found {token:?}"
+            )))
+        }
+    }
+
+    /// Check for (case insensitive) keyword
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn boolean_expr(&mut self) -> Result<Expr> {
+        let mut args = vec![self.boolean_term()?];
+        while self.keyword("OR") {
+            args.push(self.boolean_term()?);
+        }
+        Ok(if args.len() == 1 {
+            args.remove(0)
+        } else {
+            Expr::op("or", args)
+        })
+    }
+
+    fn boolean_term(&mut self) -> Result<Expr> {
+        let mut args = vec![self.boolean_factor()?];
+        while self.keyword("AND") {
+            args.push(self.boolean_factor()?);
+        }
+        Ok(if args.len() == 1 {
+            args.remove(0)
+        } else {
+            Expr::op("and", args)
+        })
+    }
+
+    fn boolean_factor(&mut self) -> Result<Expr> {
+        if self.keyword("NOT") {
+            Ok(Expr::op("not", vec![self.boolean_factor()?]))
+        } else {
+            self.boolean_primary()
+        }
+    }
+
+    fn boolean_primary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let expr = self.boolean_expr()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        if let (Some(Token::Ident(name)), Some(Token::LParen)) = (self.peek(), self.peek_next()) {
+            let name = name.to_lowercase();
+            // Spatial, temporal and array predicates
+            if name.starts_with("s_") || name.starts_with("t_") || name.starts_with("a_") {
+                self.pos += 2;
+                let args = self.operand_list()?;
+                return Ok(Expr::op(&name, args));
+            }
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr> {
+        let left = self.operand()?;
+        if let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.operand()?;
+            return Ok(Expr::op(op, vec![left, right]));
+        }
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            if !self.keyword("NULL") {
+                return Err(Error::Syntax("expected NULL".to_string()));
+            }
+            let expr = Expr::op("isNull", vec![left]);
+            return Ok(negate(expr, negated));
+        }
+        let negated = self.keyword("NOT");
+        let expr = if self.keyword("LIKE") {
+            Expr::op("like", vec![left, self.operand()?])
+        } else if self.keyword("BETWEEN") {
+            let low = self.operand()?;
+            if !self.keyword("AND") {
+                return Err(Error::Syntax("expected AND in BETWEEN".to_string()));
+            }
+            let high = self.operand()?;
+            Expr::op("between", vec![left, low, high])
+        } else if self.keyword("IN") {
+            self.expect(Token::LParen)?;
+            let list = self.operand_list()?;
+            Expr::op("in", vec![left, Expr::Array(list)])
+        } else if negated {
+            return Err(Error::Syntax(
+                "expected LIKE, BETWEEN or IN after NOT".to_string(),
+            ));
+        } else if let Expr::Bool(_) = left {
+            // Boolean literal as predicate (e.g. `TRUE`)
+            return Ok(left);
+        } else {
+            return Err(Error::Syntax(format!("expected predicate after {left:?}")));
+        };
+        Ok(negate(expr, negated))
+    }
+
+    /// Comma separated operands up to closing parenthesis
+    fn operand_list(&mut self) -> Result<Vec<Expr>> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::RParen) {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.operand()?);
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RParen => break,
+                token => return Err(Error::Syntax(format!("unexpected token {token:?}"))),
+            }
+        }
+        Ok(args)
+    }
+
+    fn string_arg(&mut self) -> Result<String> {
+        self.expect(Token::LParen)?;
+        let Token::Str(value) = self.next()? else {
+            return Err(Error::Syntax("expected string literal".to_string()));
+        };
+        self.expect(Token::RParen)?;
+        Ok(value)
+    }
+
+    fn operand(&mut self) -> Result<Expr> {
+        let start = self.pos;
+        match self.next()? {
+            Token::Str(value) => Ok(Expr::Str(value)),
+            Token::Number(value) => number(&value),
+            Token::QuotedIdent(name) => Ok(Expr::Property(name)),
+            Token::LParen => {
+                // Array literal
+                let list = self.operand_list()?;
+                Ok(Expr::Array(list))
+            }
+            Token::Ident(ident) => {
+                let keyword = ident.to_uppercase();
+                let is_call = self.peek() == Some(&Token::LParen);
+                match keyword.as_str() {
+                    "TRUE" => Ok(Expr::Bool(true)),
+                    "FALSE" => Ok(Expr::Bool(false)),
+                    "TIMESTAMP" if is_call => parse_timestamp(&self.string_arg()?),
+                    "DATE" if is_call => parse_date(&self.string_arg()?),
+                    "INTERVAL" if is_call => {
+                        self.pos += 1;
+                        let args = self.operand_list()?;
+                        let [start, end]: [Expr; 2] = args
+                            .try_into()
+                            .map_err(|_| Error::InvalidArguments("interval".to_string()))?;
+                        Ok(Expr::Interval(
+                            Box::new(interval_bound(start)?),
+                            Box::new(interval_bound(end)?),
+                        ))
+                    }
+                    "BBOX" if is_call => {
+                        self.pos += 1;
+                        let coords = self
+                            .operand_list()?
+                            .into_iter()
+                            .map(|v| match v {
+                                Expr::Int(i) => Ok(i as f64),
+                                Expr::Float(f) => Ok(f),
+                                _ => Err(Error::InvalidArguments("bbox".to_string())),
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        if coords.len() != 4 && coords.len() != 6 {
+                            return Err(Error::InvalidArguments("bbox".to_string()));
+                        }
+                        Ok(Expr::BBox(coords))
+                    }
+                    wkt if WKT_TYPES.contains(&wkt) => self.wkt(start),
+                    _ if is_call => {
+                        self.pos += 1;
+                        let args = self.operand_list()?;
+                        Ok(Expr::op(&ident, args))
+                    }
+                    _ => Ok(Expr::Property(ident)),
+                }
+            }
+            token => Err(Error::Syntax(format!("unexpected token {token:?}"))),
+        }
+    }
+
+    /// Geometry literal starting at token `start`, up to the matching parenthesis
+    fn wkt(&mut self, start: usize) -> Result<Expr> {
+        // Dimension markers like `POINT Z (...)`
+        while matches!(self.peek(), Some(Token::Ident(id)) if ["Z", "M", "ZM"].contains(&id.to_uppercase().as_str()))
+        {
+            self.pos += 1;
+        }
+        if !self.keyword("EMPTY") {
+            self.expect(Token::LParen)?;
+            let mut depth = 1;
+            while depth > 0 {
+                match self.next()? {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        let end = self.tokens[self.pos - 1].end;
+        let wkt = &self.src[self.tokens[start].start..end];
+        Ok(Expr::Geometry(Geometry::Wkt(wkt.to_string())))
+    }
+}
+
+fn negate(expr: Expr, negated: bool) -> Expr {
+    if negated {
+        Expr::op("not", vec![expr])
+    } else {
+        expr
+    }
+}
+
+fn number(value: &str) -> Result<Expr> {
+    if let Ok(i) = value.parse::<i64>() {
+        Ok(Expr::Int(i))
+    } else {
+        value
+            .parse::<f64>()
+            .map(Expr::Float)
+            .map_err(|_| Error::InvalidValue(value.to_string()))
+    }
+}
+
+fn interval_bound(expr: Expr) -> Result<Expr> {
+    match expr {
+        Expr::Str(value) => parse_instant(&value),
+        Expr::Timestamp(_) | Expr::Date(_) | Expr::Property(_) => Ok(expr),
+        _ => Err(Error::InvalidArguments("interval".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop(name: &str) -> Expr {
+        Expr::Property(name.to_string())
+    }
+
+    #[test]
+    fn comparison() {
+        assert_eq!(
+            parse("name = 'Rhein'").unwrap(),
+            Expr::op("=", vec![prop("name"), Expr::Str("Rhein".to_string())])
+        );
+        assert_eq!(
+            parse("\"pop max\" >= -1.5e3").unwrap(),
+            Expr::op(">=", vec![prop("pop max"), Expr::Float(-1500.0)])
+        );
+        assert_eq!(
+            parse("name <> 'O''Neil'").unwrap(),
+            Expr::op("<>", vec![prop("name"), Expr::Str("O'Neil".to_string())])
+        );
+    }
+
+    #[test]
+    fn logical_precedence() {
+        assert_eq!(
+            parse("a = 1 OR b = 2 AND NOT c = 3").unwrap(),
+            Expr::op(
+                "or",
+                vec![
+                    Expr::op("=", vec![prop("a"), Expr::Int(1)]),
+                    Expr::op(
+                        "and",
+                        vec![
+                            Expr::op("=", vec![prop("b"), Expr::Int(2)]),
+                            Expr::op("not", vec![Expr::op("=", vec![prop("c"), Expr::Int(3)])]),
+                        ]
+                    ),
+                ]
+            )
+        );
+        assert_eq!(
+            parse("(a = 1 or b = 2) and c = 3").unwrap(),
+            Expr::op(
+                "and",
+                vec![
+                    Expr::op(
+                        "or",
+                        vec![
+                            Expr::op("=", vec![prop("a"), Expr::Int(1)]),
+                            Expr::op("=", vec![prop("b"), Expr::Int(2)]),
+                        ]
+                    ),
+                    Expr::op("=", vec![prop("c"), Expr::Int(3)]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn advanced_comparison() {
+        assert_eq!(
+            parse("name NOT LIKE 'Rh%'").unwrap(),
+            Expr::op(
+                "not",
+                vec![Expr::op(
+                    "like",
+                    vec![prop("name"), Expr::Str("Rh%".to_string())]
+                )]
+            )
+        );
+        assert_eq!(
+            parse("scalerank BETWEEN 1 AND 4").unwrap(),
+            Expr::op(
+                "between",
+                vec![prop("scalerank"), Expr::Int(1), Expr::Int(4)]
+            )
+        );
+        assert_eq!(
+            parse("name IN ('Rhein', 'Aare')").unwrap(),
+            Expr::op(
+                "in",
+                vec![
+                    prop("name"),
+                    Expr::Array(vec![
+                        Expr::Str("Rhein".to_string()),
+                        Expr::Str("Aare".to_string())
+                    ])
+                ]
+            )
+        );
+        assert_eq!(
+            parse("name IS NOT NULL").unwrap(),
+            Expr::op("not", vec![Expr::op("isNull", vec![prop("name")])])
+        );
+    }
+
+    #[test]
+    fn spatial() {
+        assert_eq!(
+            parse("S_INTERSECTS(geom, POINT(7.5 47.1))").unwrap(),
+            Expr::op(
+                "s_intersects",
+                vec![
+                    prop("geom"),
+                    Expr::Geometry(Geometry::Wkt("POINT(7.5 47.1)".to_string()))
+                ]
+            )
+        );
+        assert_eq!(
+            parse("s_within(geom, POLYGON((0 0, 1 0, 1 1, 0 0))) and a=1").unwrap(),
+            Expr::op(
+                "and",
+                vec![
+                    Expr::op(
+                        "s_within",
+                        vec![
+                            prop("geom"),
+                            Expr::Geometry(Geometry::Wkt(
+                                "POLYGON((0 0, 1 0, 1 1, 0 0))".to_string()
+                            ))
+                        ]
+                    ),
+                    Expr::op("=", vec![prop("a"), Expr::Int(1)]),
+                ]
+            )
+        );
+        assert_eq!(
+            parse("S_INTERSECTS(geom, POINT Z (7.5 47.1 400))").unwrap(),
+            Expr::op(
+                "s_intersects",
+                vec![
+                    prop("geom"),
+                    Expr::Geometry(Geometry::Wkt("POINT Z (7.5 47.1 400)".to_string()))
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn temporal() {
+        let ts = chrono::DateTime::parse_from_rfc3339("2021-05-09T00:00:00Z").unwrap();
+        assert_eq!(
+            parse("ts > TIMESTAMP('2021-05-09T00:00:00Z')").unwrap(),
+            Expr::op(">", vec![prop("ts"), Expr::Timestamp(ts)])
+        );
+        assert_eq!(
+            parse("T_BEFORE(ts, DATE('2021-05-09'))").unwrap(),
+            Expr::op(
+                "t_before",
+                vec![
+                    prop("ts"),
+                    Expr::Date(chrono::NaiveDate::from_ymd_opt(2021, 5, 9).unwrap())
+                ]
+            )
+        );
+        // `date` is a property name when not used as function
+        assert_eq!(
+            parse("date = '2024-11-08'").unwrap(),
+            Expr::op("=", vec![prop("date"), Expr::Str("2024-11-08".to_string())])
+        );
+        assert!(parse("ts > TIMESTAMP('yesterday')").is_err());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(parse("name = ").is_err());
+        assert!(parse("name = 'x").is_err());
+        assert!(parse("name 'x'").is_err());
+        assert!(parse("(a = 1").is_err());
+        assert!(parse("a = 1 b = 2").is_err());
+        assert!(parse("a BETWEEN 1 4").is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! FlatGeobuf feature source.
+
+use crate::config::{CollectionSourceCfg, ConfiguredCollectionCfg};
+use crate::datasource::gpkg::geojson_bbox;
+use crate::datasource::{
+    blocking, column_queryables, datasource_error, filter_bbox, geometry_transform, item_links,
+    not_queryable, selected_columns, source_collection, spatial_extent, CollectionSource,
+    FeatureSender, GeometryTransform, ItemsResult,
+};
+use crate::error::{Error, Result};
+use crate::filter_params::FilterParams;
+use crate::inventory::FeatureCollection;
+use async_trait::async_trait;
+use bbox_core::config::app_dir;
+use bbox_core::ogcapi::*;
+use flatgeobuf::{ColumnType, FallibleStreamingIterator, FgbFeature, FgbReader};
+use futures::executor::block_on;
+use futures::SinkExt;
+use geozero::{FeatureProperties, ToJson};
+use log::{debug, error, info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+pub struct FgbCollectionSource {
+    path: PathBuf,
+    srid: Option<i32>,
+    /// Property used as feature id
+    fid_column: Option<String>,
+    /// Property columns in file order
+    columns: Vec<String>,
+    column_types: HashMap<String, QueryableType>,
+    /// Queryable columns
+    other_columns: HashMap<String, QueryableType>,
+    /// File contains a packed R-tree index
+    indexed: bool,
+    features_count: u64,
+}
+
+/// Collection of an explicit `[[collection]]` configuration
+pub fn setup_collection(
+    cfg: &ConfiguredCollectionCfg,
+    base_url: &str,
+) -> Result<FeatureCollection> {
+    info!("Setup FlatGeobuf Collection `{}`", &cfg.name);
+    let CollectionSourceCfg::Fgb(ref srccfg) = cfg.source else {
+        panic!();
+    };
+    let path = app_dir(&srccfg.path);
+    let (mut source, extent) = FgbCollectionSource::open(&path)?;
+    source.fid_column = srccfg.fid_field.clone();
+    if !srccfg.queryable_fields.is_empty() {
+        source
+            .other_columns
+            .retain(|col, _| srccfg.queryable_fields.contains(col));
+    }
+    Ok(source.into_collection(
+        &cfg.name,
+        cfg.title.clone(),
+        cfg.description.clone(),
+        extent,
+        &srccfg.crs,
+        base_url,
+    ))
+}
+
+/// Collection of a file found by directory autoscan
+pub fn file_collection(path: &Path, base_url: &str) -> Result<FeatureCollection> {
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (source, extent) = FgbCollectionSource::open(path)?;
+    Ok(source.into_collection(&id, Some(id.clone()), None, extent, &[], base_url))
+}
+
+impl FgbCollectionSource {
+    fn open(path: &Path) -> Result<(Self, Option<CoreExtent>)> {
+        info!("Opening `{}`", path.display());
+        let mut file = BufReader::new(File::open(path).map_err(datasource_error)?);
+        let fgb = FgbReader::open(&mut file).map_err(datasource_error)?;
+        let header = fgb.header();
+        let srid = header.crs().map(|crs| crs.code()).filter(|code| *code > 0);
+        let mut columns = Vec::new();
+        let mut column_types = HashMap::new();
+        for column in header.columns().into_iter().flatten() {
+            let queryable_type = match column.type_() {
+                ColumnType::Bool => QueryableType::Bool,
+                ColumnType::Byte
+                | ColumnType::UByte
+                | ColumnType::Short
+                | ColumnType::UShort
+                | ColumnType::Int
+                | ColumnType::UInt
+                | ColumnType::Long
+                | ColumnType::ULong => QueryableType::Integer,
+                ColumnType::Float | ColumnType::Double => QueryableType::Number,
+                ColumnType::DateTime => QueryableType::Datetime,
+                ColumnType::String | ColumnType::Json => QueryableType::String,
+                _ => {
+                    debug!("Skipping column `{}` with unsupported type", column.name());
+                    continue;
+                }
+            };
+            columns.push(column.name().to_string());
+            column_types.insert(column.name().to_string(), queryable_type);
+        }
+        let extent = header.envelope().and_then(|env| {
+            let env: Vec<f64> = env.iter().collect();
+            let [minx, miny, maxx, maxy, ..] = env[..] else {
+                return None;
+            };
+            let spatial = spatial_extent([minx, miny, maxx, maxy], srid)?;
+            Some(CoreExtent {
+                spatial: Some(spatial),
+                temporal: None,
+            })
+        });
+        let source = FgbCollectionSource {
+            path: path.to_path_buf(),
+            srid,
+            fid_column: None,
+            columns,
+            other_columns: column_types.clone(),
+            column_types,
+            indexed: header.index_node_size() > 0,
+            features_count: header.features_count(),
+        };
+        Ok((source, extent))
+    }
+
+    fn into_collection(
+        self,
+        id: &str,
+        title: Option<String>,
+        description: Option<String>,
+        extent: Option<CoreExtent>,
+        additional_crs: &[i32],
+        base_url: &str,
+    ) -> FeatureCollection {
+        let (srid, queryables) = (self.srid, !self.other_columns.is_empty());
+        source_collection(
+            Box::new(self),
+            id,
+            title,
+            description,
+            extent,
+            srid,
+            additional_crs,
+            queryables,
+            base_url,
+        )
+    }
+
+    /// Feature properties converted to JSON values of the column types
+    fn feature_properties(&self, feature: &FgbFeature) -> Result<serde_json::Map<String, Value>> {
+        let mut properties = serde_json::Map::new();
+        for (key, value) in feature.properties().map_err(datasource_error)? {
+            let value = match self.column_types.get(&key) {
+                Some(QueryableType::Integer) => value.parse::<i64>().map(Value::from).ok(),
+                Some(QueryableType::Number) => value.parse::<f64>().map(Value::from).ok(),
+                Some(QueryableType::Bool) => value.parse::<bool>().map(Value::from).ok(),
+                Some(_) => Some(Value::String(value)),
+                None => continue,
+            };
+            properties.insert(key, value.unwrap_or(Value::Null));
+        }
+        Ok(properties)
+    }
+
+    /// Read features matching `filter` and send them with back pressure.
+    /// Blocks on file I/O and sending.
+    fn read_items(
+        &self,
+        filter: &FilterParams,
+        bbox: Option<[f64; 4]>,
+        mut sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        let others = &filter.filters;
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        let mut file = BufReader::new(File::open(&self.path).map_err(datasource_error)?);
+        let fgb = FgbReader::open(&mut file).map_err(datasource_error)?;
+        let mut features = match bbox {
+            Some(bbox) if self.indexed => fgb.select_bbox(bbox[0], bbox[1], bbox[2], bbox[3]),
+            _ => fgb.select_all(),
+        }
+        .map_err(datasource_error)?;
+        let limit = filter.limit_or_default() as u64;
+        let mut offset = filter.offset.unwrap_or(0);
+        let mut number_returned = 0;
+        while let Some(feature) = features.next().map_err(datasource_error)? {
+            if !others.is_empty() {
+                let properties = feature.properties().map_err(datasource_error)?;
+                if others
+                    .iter()
+                    .any(|(key, val)| properties.get(key) != Some(val))
+                {
+                    continue;
+                }
+            }
+            if let (Some(bbox), false) = (bbox, self.indexed) {
+                // Envelope check without spatial index
+                let geometry: Value =
+                    serde_json::from_str(&feature.to_json().map_err(datasource_error)?)
+                        .map_err(|_| Error::GeometryFormatError)?;
+                let Some(b) = geojson_bbox(&geometry) else {
+                    continue;
+                };
+                if b[0] > bbox[2] || b[2] < bbox[0] || b[1] > bbox[3] || b[3] < bbox[1] {
+                    continue;
+                }
+            }
+            if offset > 0 {
+                offset -= 1;
+                continue;
+            }
+            if limit > 0 && number_returned == limit {
+                break;
+            }
+            let feature = self.to_feature(feature, filter, &transform)?;
+            if block_on(sender.send(feature)).is_err() {
+                debug!("Items receiver closed");
+                return Err(Error::ItemsStreamClosed);
+            }
+            number_returned += 1;
+        }
+        let number_matched = if bbox.is_none() && others.is_empty() {
+            Some(self.features_count)
+        } else {
+            None
+        };
+        Ok(ItemsResult {
+            features: Vec::new(),
+            number_matched,
+            number_returned,
+            next_cursor: None,
+        })
+    }
+
+    /// Feature with `fid_column` value `feature_id`. Blocks on file I/O.
+    fn read_item(
+        &self,
+        fid_column: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        let mut file = BufReader::new(File::open(&self.path).map_err(datasource_error)?);
+        let mut features = FgbReader::open(&mut file)
+            .and_then(|fgb| fgb.select_all())
+            .map_err(datasource_error)?;
+        while let Some(feature) = features.next().map_err(datasource_error)? {
+            let properties = feature.properties().map_err(datasource_error)?;
+            if properties.get(fid_column).map(String::as_str) == Some(feature_id) {
+                return Ok(Some(self.to_feature(feature, filter, &transform)?));
+            }
+        }
+        Ok(None)
+    }
+
+    fn to_feature(
+        &self,
+        feature: &FgbFeature,
+        filter: &FilterParams,
+        transform: &Option<GeometryTransform>,
+    ) -> Result<CoreFeature> {
+        let mut properties = self.feature_properties(feature)?;
+        let id = self
+            .fid_column
+            .as_ref()
+            .and_then(|col| properties.remove(col))
+            .map(|id| match id {
+                Value::String(s) => s,
+                v => v.to_string(),
+            });
+        if let Some(selected) = selected_columns(filter, &self.columns) {
+            properties.retain(|key, _| selected.contains(&key.as_str()));
+        }
+        let geometry = if filter.skip_geometry() {
+            Value::Null
+        } else {
+            let mut geometry: Value =
+                serde_json::from_str(&feature.to_json().map_err(datasource_error)?)
+                    .map_err(|_| Error::GeometryFormatError)?;
+            if let Some(transform) = transform {
+                transform.transform(&mut geometry)?;
+            }
+            geometry
+        };
+        Ok(CoreFeature {
+            type_: "Feature".to_string(),
+            geometry,
+            properties: Some(json!(properties)),
+            id,
+            links: vec![],
+            foreign_members: serde_json::Map::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl CollectionSource for FgbCollectionSource {
+    async fn send_items(
+        &self,
+        filter: &FilterParams,
+        sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        if filter.filter.is_some() {
+            error!("CQL2 filters are not supported for FlatGeobuf collections");
+            return Err(Error::QueryParams);
+        }
+        let others = filter.other_params().map_err(|e| {
+            error!("{e}");
+            Error::QueryParams
+        })?;
+        for key in others.keys() {
+            if !self.other_columns.contains_key(key) {
+                return Err(not_queryable(key));
+            }
+        }
+        let bbox = filter_bbox(filter, self.srid)?;
+        let source = self.clone();
+        let filter = filter.clone();
+        blocking(move || source.read_items(&filter, bbox, sender)).await
+    }
+
+    async fn item(
+        &self,
+        base_url: &str,
+        collection_id: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        let Some(fid_column) = &self.fid_column else {
+            warn!("Ignoring error getting item for {collection_id} without `fid_field`");
+            return Ok(None);
+        };
+        let (source, fid_column) = (self.clone(), fid_column.clone());
+        let (id, filter) = (feature_id.to_string(), filter.clone());
+        let item = blocking(move || source.read_item(&fid_column, &id, &filter)).await?;
+        let Some(mut item) = item else {
+            return Ok(None);
+        };
+        item.links = item_links(base_url, collection_id, feature_id);
+        Ok(Some(item))
+    }
+
+    async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        Ok(Some(column_queryables(collection_id, &self.other_columns)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::test_places;
+    use crate::output::fgb::features_to_fgb;
+
+    #[tokio::test]
+    async fn fgb_features() {
+        let features = CoreFeatures {
+            type_: "FeatureCollection".to_string(),
+            links: Vec::new(),
+            time_stamp: None,
+            number_matched: None,
+            number_returned: None,
+            features: test_places()
+                .into_iter()
+                .map(|(name, scalerank, x, y)| CoreFeature {
+                    type_: "Feature".to_string(),
+                    geometry: json!({"type": "Point", "coordinates": [x, y]}),
+                    properties: Some(json!({"name": name, "scalerank": scalerank})),
+                    id: None,
+                    links: Vec::new(),
+                    foreign_members: serde_json::Map::new(),
+                })
+                .collect(),
+        };
+        let path = std::env::temp_dir().join(format!("bbox-test-{}.fgb", std::process::id()));
+        let fgb = features_to_fgb("places", &features, 4326, true).unwrap();
+        std::fs::write(&path, fgb).unwrap();
+
+        let fc = file_collection(&path, "").unwrap();
+        let queryables = fc.source.queryables("places").await.unwrap().unwrap();
+        assert!(matches!(
+            queryables.properties["scalerank"].type_,
+            Some(QueryableType::Integer)
+        ));
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(3));
+        assert_eq!(items.features.len(), 3);
+
+        let filter = FilterParams {
+            bbox: Some("8,47,9,48".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 1);
+        assert_eq!(
+            items.features[0].properties,
+            Some(json!({"name": "Zürich", "scalerank": 1}))
+        );
+
+        let mut filter = FilterParams::default();
+        filter
+            .filters
+            .insert("scalerank".to_string(), "1".to_string());
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 2);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
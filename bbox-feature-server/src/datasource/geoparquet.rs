@@ -0,0 +1,691 @@
+//! GeoParquet feature source.
+
+use crate::config::{CollectionSourceCfg, ConfiguredCollectionCfg};
+use crate::datasource::gpkg::geojson_bbox;
+use crate::datasource::{
+    blocking, column_queryables, datasource_error, filter_bbox, geometry_transform, item_links,
+    not_queryable, selected_columns, source_collection, spatial_extent, CollectionSource,
+    FeatureSender, GeometryTransform, ItemsResult,
+};
+use crate::error::{Error, Result};
+use crate::filter_params::FilterParams;
+use crate::inventory::FeatureCollection;
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_cast::display::array_value_to_string;
+use arrow_schema::DataType;
+use async_trait::async_trait;
+use bbox_core::config::app_dir;
+use bbox_core::ogcapi::*;
+use futures::executor::block_on;
+use futures::SinkExt;
+use geozero::{wkb::Wkb, ToJson};
+use log::{debug, error, info, warn};
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::statistics::Statistics;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const BATCH_SIZE: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct GeoParquetCollectionSource {
+    path: PathBuf,
+    srid: Option<i32>,
+    geometry_column: String,
+    /// Column paths of the bbox covering (xmin, ymin, xmax, ymax)
+    bbox_covering: Option<[String; 4]>,
+    /// Property used as feature id
+    fid_column: Option<String>,
+    /// Property columns in file order
+    columns: Vec<String>,
+    /// Queryable columns
+    other_columns: HashMap<String, QueryableType>,
+    num_rows: u64,
+}
+
+/// Collection of an explicit `[[collection]]` configuration
+pub fn setup_collection(
+    cfg: &ConfiguredCollectionCfg,
+    base_url: &str,
+) -> Result<FeatureCollection> {
+    info!("Setup GeoParquet Collection `{}`", &cfg.name);
+    let CollectionSourceCfg::GeoParquet(ref srccfg) = cfg.source else {
+        panic!();
+    };
+    let path = app_dir(&srccfg.path);
+    let (mut source, extent) = GeoParquetCollectionSource::open(&path)?;
+    source.fid_column = srccfg.fid_field.clone();
+    if !srccfg.queryable_fields.is_empty() {
+        source
+            .other_columns
+            .retain(|col, _| srccfg.queryable_fields.contains(col));
+    }
+    Ok(source.into_collection(
+        &cfg.name,
+        cfg.title.clone(),
+        cfg.description.clone(),
+        extent,
+        &srccfg.crs,
+        base_url,
+    ))
+}
+
+/// Collection of a file found by directory autoscan
+pub fn file_collection(path: &Path, base_url: &str) -> Result<FeatureCollection> {
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (source, extent) = GeoParquetCollectionSource::open(path)?;
+    Ok(source.into_collection(&id, Some(id.clone()), None, extent, &[], base_url))
+}
+
+impl GeoParquetCollectionSource {
+    fn open(path: &Path) -> Result<(Self, Option<CoreExtent>)> {
+        info!("Opening `{}`", path.display());
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(path).map_err(datasource_error)?)
+                .map_err(datasource_error)?;
+        let schema = reader.schema();
+        let geo: Value = schema
+            .metadata()
+            .get("geo")
+            .ok_or(Error::DatasourceError(
+                "`geo` metadata missing - not a GeoParquet file".to_string(),
+            ))
+            .and_then(|geo| serde_json::from_str(geo).map_err(datasource_error))?;
+        let geometry_column = geo["primary_column"]
+            .as_str()
+            .ok_or(Error::DatasourceError("primary_column missing".to_string()))?
+            .to_string();
+        let column_meta = &geo["columns"][&geometry_column];
+        if column_meta["encoding"].as_str() != Some("WKB") {
+            return Err(Error::DatasourceError(format!(
+                "Unsupported geometry encoding {}",
+                column_meta["encoding"]
+            )));
+        }
+        let srid = match &column_meta["crs"] {
+            // Missing CRS means OGC:CRS84
+            Value::Null if column_meta.get("crs").is_none() => Some(4326),
+            crs => projjson_srid(crs),
+        };
+        let bbox_covering = ["xmin", "ymin", "xmax", "ymax"].map(|key| {
+            column_meta["covering"]["bbox"][key]
+                .as_array()
+                .map(|path| {
+                    path.iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .unwrap_or_default()
+        });
+        let bbox_covering = if bbox_covering.iter().all(|path| !path.is_empty()) {
+            Some(bbox_covering)
+        } else {
+            None
+        };
+        let covering_root = bbox_covering
+            .as_ref()
+            .and_then(|paths| paths[0].split('.').next())
+            .map(str::to_string);
+        let mut columns = Vec::new();
+        let mut column_types = HashMap::new();
+        for field in schema.fields() {
+            if field.name() == &geometry_column || Some(field.name()) == covering_root.as_ref() {
+                continue;
+            }
+            let queryable_type = match field.data_type() {
+                DataType::Boolean => QueryableType::Bool,
+                DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64 => QueryableType::Integer,
+                DataType::Float16 | DataType::Float32 | DataType::Float64 => QueryableType::Number,
+                DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+                    QueryableType::Datetime
+                }
+                DataType::Utf8 | DataType::LargeUtf8 => QueryableType::String,
+                _ => {
+                    debug!("Skipping column `{}` with unsupported type", field.name());
+                    continue;
+                }
+            };
+            columns.push(field.name().to_string());
+            column_types.insert(field.name().to_string(), queryable_type);
+        }
+        let extent = column_meta["bbox"].as_array().and_then(|bbox| {
+            let bbox: Vec<f64> = bbox.iter().filter_map(Value::as_f64).collect();
+            let [minx, miny, maxx, maxy] = bbox[..] else {
+                return None;
+            };
+            let spatial = spatial_extent([minx, miny, maxx, maxy], srid)?;
+            Some(CoreExtent {
+                spatial: Some(spatial),
+                temporal: None,
+            })
+        });
+        let source = GeoParquetCollectionSource {
+            path: path.to_path_buf(),
+            srid,
+            geometry_column,
+            bbox_covering,
+            fid_column: None,
+            columns,
+            other_columns: column_types,
+            num_rows: reader.metadata().file_metadata().num_rows() as u64,
+        };
+        Ok((source, extent))
+    }
+
+    fn into_collection(
+        self,
+        id: &str,
+        title: Option<String>,
+        description: Option<String>,
+        extent: Option<CoreExtent>,
+        additional_crs: &[i32],
+        base_url: &str,
+    ) -> FeatureCollection {
+        let (srid, queryables) = (self.srid, !self.other_columns.is_empty());
+        source_collection(
+            Box::new(self),
+            id,
+            title,
+            description,
+            extent,
+            srid,
+            additional_crs,
+            queryables,
+            base_url,
+        )
+    }
+
+    /// Check row group statistics of the bbox covering columns
+    fn row_group_intersects(&self, row_group: &RowGroupMetaData, bbox: &[f64; 4]) -> bool {
+        let Some(covering) = &self.bbox_covering else {
+            return true;
+        };
+        let stats = |path: &str| {
+            row_group
+                .columns()
+                .iter()
+                .find(|col| col.column_path().string() == path)
+                .and_then(|col| col.statistics())
+        };
+        // Lower bound of xmin/ymin and upper bound of xmax/ymax
+        let min = |path: &str| match stats(path)? {
+            Statistics::Double(s) => s.min_opt().copied(),
+            Statistics::Float(s) => s.min_opt().map(|v| *v as f64),
+            _ => None,
+        };
+        let max = |path: &str| match stats(path)? {
+            Statistics::Double(s) => s.max_opt().copied(),
+            Statistics::Float(s) => s.max_opt().map(|v| *v as f64),
+            _ => None,
+        };
+        let (Some(xmin), Some(ymin), Some(xmax), Some(ymax)) = (
+            min(&covering[0]),
+            min(&covering[1]),
+            max(&covering[2]),
+            max(&covering[3]),
+        ) else {
+            return true;
+        };
+        !(xmin > bbox[2] || xmax < bbox[0] || ymin > bbox[3] || ymax < bbox[1])
+    }
+
+    fn reader(&self, bbox: Option<&[f64; 4]>) -> Result<ParquetRecordBatchReader> {
+        let mut builder = ParquetRecordBatchReaderBuilder::try_new(
+            File::open(&self.path).map_err(datasource_error)?,
+        )
+        .map_err(datasource_error)?
+        .with_batch_size(BATCH_SIZE);
+        if let Some(bbox) = bbox {
+            let row_groups: Vec<usize> = builder
+                .metadata()
+                .row_groups()
+                .iter()
+                .enumerate()
+                .filter(|(_, row_group)| self.row_group_intersects(row_group, bbox))
+                .map(|(idx, _)| idx)
+                .collect();
+            debug!(
+                "Reading {} of {} row groups",
+                row_groups.len(),
+                builder.metadata().num_row_groups()
+            );
+            builder = builder.with_row_groups(row_groups);
+        }
+        builder.build().map_err(datasource_error)
+    }
+
+    /// GeoJSON geometry of a row
+    fn row_geometry(&self, batch: &RecordBatch, row: usize) -> Result<Value> {
+        let Some(array) = batch.column_by_name(&self.geometry_column) else {
+            return Ok(Value::Null);
+        };
+        if array.is_null(row) {
+            return Ok(Value::Null);
+        }
+        let wkb = match array.data_type() {
+            DataType::Binary => array.as_binary::<i32>().value(row),
+            DataType::LargeBinary => array.as_binary::<i64>().value(row),
+            _ => return Err(Error::GeometryFormatError),
+        };
+        let json = Wkb(wkb.to_vec())
+            .to_json()
+            .map_err(|_| Error::GeometryFormatError)?;
+        serde_json::from_str(&json).map_err(|_| Error::GeometryFormatError)
+    }
+
+    /// Row properties converted to JSON values
+    fn row_properties(&self, batch: &RecordBatch, row: usize) -> serde_json::Map<String, Value> {
+        let mut properties = serde_json::Map::new();
+        for column in &self.columns {
+            if let Some(array) = batch.column_by_name(column) {
+                properties.insert(column.clone(), array_value(array, row));
+            }
+        }
+        properties
+    }
+
+    /// Read features matching `filter` and send them with back pressure.
+    /// Blocks on file I/O and sending.
+    fn read_items(
+        &self,
+        filter: &FilterParams,
+        bbox: Option<[f64; 4]>,
+        mut sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        let others = &filter.filters;
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        let limit = filter.limit_or_default() as u64;
+        let mut offset = filter.offset.unwrap_or(0);
+        let mut number_returned = 0;
+        'batches: for batch in self.reader(bbox.as_ref())? {
+            let batch = batch.map_err(datasource_error)?;
+            for row in 0..batch.num_rows() {
+                if others.iter().any(|(key, val)| {
+                    batch
+                        .column_by_name(key)
+                        .map(|array| value_string(&array_value(array, row)))
+                        .as_ref()
+                        != Some(val)
+                }) {
+                    continue;
+                }
+                let mut geometry = None;
+                if let Some(bbox) = bbox {
+                    let geom = self.row_geometry(&batch, row)?;
+                    let Some(b) = geojson_bbox(&geom) else {
+                        continue;
+                    };
+                    if b[0] > bbox[2] || b[2] < bbox[0] || b[1] > bbox[3] || b[3] < bbox[1] {
+                        continue;
+                    }
+                    geometry = Some(geom);
+                }
+                if offset > 0 {
+                    offset -= 1;
+                    continue;
+                }
+                if limit > 0 && number_returned == limit {
+                    break 'batches;
+                }
+                let feature = self.to_feature(&batch, row, filter, geometry, &transform)?;
+                if block_on(sender.send(feature)).is_err() {
+                    debug!("Items receiver closed");
+                    return Err(Error::ItemsStreamClosed);
+                }
+                number_returned += 1;
+            }
+        }
+        let number_matched = if bbox.is_none() && others.is_empty() {
+            Some(self.num_rows)
+        } else {
+            None
+        };
+        Ok(ItemsResult {
+            features: Vec::new(),
+            number_matched,
+            number_returned,
+            next_cursor: None,
+        })
+    }
+
+    /// Feature with `fid_column` value `feature_id`. Blocks on file I/O.
+    fn read_item(
+        &self,
+        fid_column: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        for batch in self.reader(None)? {
+            let batch = batch.map_err(datasource_error)?;
+            let Some(fid_array) = batch.column_by_name(fid_column) else {
+                return Ok(None);
+            };
+            for row in 0..batch.num_rows() {
+                if value_string(&array_value(fid_array, row)) == feature_id {
+                    return Ok(Some(
+                        self.to_feature(&batch, row, filter, None, &transform)?,
+                    ));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn to_feature(
+        &self,
+        batch: &RecordBatch,
+        row: usize,
+        filter: &FilterParams,
+        geometry: Option<Value>,
+        transform: &Option<GeometryTransform>,
+    ) -> Result<CoreFeature> {
+        let mut properties = self.row_properties(batch, row);
+        let id = self
+            .fid_column
+            .as_ref()
+            .and_then(|col| properties.remove(col))
+            .map(|id| match id {
+                Value::String(s) => s,
+                v => v.to_string(),
+            });
+        if let Some(selected) = selected_columns(filter, &self.columns) {
+            properties.retain(|key, _| selected.contains(&key.as_str()));
+        }
+        let geometry = if filter.skip_geometry() {
+            Value::Null
+        } else {
+            let mut geometry = match geometry {
+                Some(geometry) => geometry,
+                None => self.row_geometry(batch, row)?,
+            };
+            if let Some(transform) = transform {
+                transform.transform(&mut geometry)?;
+            }
+            geometry
+        };
+        Ok(CoreFeature {
+            type_: "Feature".to_string(),
+            geometry,
+            properties: Some(json!(properties)),
+            id,
+            links: vec![],
+            foreign_members: serde_json::Map::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl CollectionSource for GeoParquetCollectionSource {
+    async fn send_items(
+        &self,
+        filter: &FilterParams,
+        sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        if filter.filter.is_some() {
+            error!("CQL2 filters are not supported for GeoParquet collections");
+            return Err(Error::QueryParams);
+        }
+        let others = filter.other_params().map_err(|e| {
+            error!("{e}");
+            Error::QueryParams
+        })?;
+        for key in others.keys() {
+            if !self.other_columns.contains_key(key) {
+                return Err(not_queryable(key));
+            }
+        }
+        let bbox = filter_bbox(filter, self.srid)?;
+        let source = self.clone();
+        let filter = filter.clone();
+        blocking(move || source.read_items(&filter, bbox, sender)).await
+    }
+
+    async fn item(
+        &self,
+        base_url: &str,
+        collection_id: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        let Some(fid_column) = &self.fid_column else {
+            warn!("Ignoring error getting item for {collection_id} without `fid_field`");
+            return Ok(None);
+        };
+        let (source, fid_column) = (self.clone(), fid_column.clone());
+        let (id, filter) = (feature_id.to_string(), filter.clone());
+        let item = blocking(move || source.read_item(&fid_column, &id, &filter)).await?;
+        let Some(mut item) = item else {
+            return Ok(None);
+        };
+        item.links = item_links(base_url, collection_id, feature_id);
+        Ok(Some(item))
+    }
+
+    async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        Ok(Some(column_queryables(collection_id, &self.other_columns)))
+    }
+}
+
+/// EPSG code of a PROJJSON CRS definition
+fn projjson_srid(crs: &Value) -> Option<i32> {
+    let id = &crs["id"];
+    match id["authority"].as_str()? {
+        "EPSG" => id["code"]
+            .as_i64()
+            .or_else(|| id["code"].as_str().and_then(|code| code.parse().ok()))
+            .map(|code| code as i32),
+        "OGC" if id["code"].as_str() == Some("CRS84") => Some(4326),
+        _ => None,
+    }
+}
+
+/// JSON value of an Arrow array element
+fn array_value(array: &ArrayRef, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+    match array.data_type() {
+        DataType::Boolean => array.as_boolean().value(row).into(),
+        DataType::Int8 => array.as_primitive::<Int8Type>().value(row).into(),
+        DataType::Int16 => array.as_primitive::<Int16Type>().value(row).into(),
+        DataType::Int32 => array.as_primitive::<Int32Type>().value(row).into(),
+        DataType::Int64 => array.as_primitive::<Int64Type>().value(row).into(),
+        DataType::UInt8 => array.as_primitive::<UInt8Type>().value(row).into(),
+        DataType::UInt16 => array.as_primitive::<UInt16Type>().value(row).into(),
+        DataType::UInt32 => array.as_primitive::<UInt32Type>().value(row).into(),
+        DataType::UInt64 => array.as_primitive::<UInt64Type>().value(row).into(),
+        DataType::Float32 => array.as_primitive::<Float32Type>().value(row).into(),
+        DataType::Float64 => array.as_primitive::<Float64Type>().value(row).into(),
+        DataType::Utf8 => array.as_string::<i32>().value(row).into(),
+        DataType::LargeUtf8 => array.as_string::<i64>().value(row).into(),
+        _ => array_value_to_string(array, row)
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+    }
+}
+
+/// Value as used in query parameters
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::test_places;
+    use arrow_array::{BinaryArray, Float64Array, Int64Array, StringArray, StructArray};
+    use arrow_schema::{Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    fn write_geoparquet(path: &Path) {
+        let mut places = test_places();
+        places.push(("Paris", 0, 2.35, 48.86));
+        let wkb_point = |x: f64, y: f64| {
+            let mut wkb = vec![1, 1, 0, 0, 0];
+            wkb.extend_from_slice(&x.to_le_bytes());
+            wkb.extend_from_slice(&y.to_le_bytes());
+            wkb
+        };
+        let coords = |idx: usize| -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(places.iter().map(|p| {
+                if idx == 0 {
+                    p.2
+                } else {
+                    p.3
+                }
+            })))
+        };
+        let bbox = StructArray::from(vec![
+            (
+                Arc::new(Field::new("xmin", DataType::Float64, false)),
+                coords(0),
+            ),
+            (
+                Arc::new(Field::new("ymin", DataType::Float64, false)),
+                coords(1),
+            ),
+            (
+                Arc::new(Field::new("xmax", DataType::Float64, false)),
+                coords(0),
+            ),
+            (
+                Arc::new(Field::new("ymax", DataType::Float64, false)),
+                coords(1),
+            ),
+        ]);
+        let geo = json!({
+            "version": "1.1.0",
+            "primary_column": "geometry",
+            "columns": {"geometry": {
+                "encoding": "WKB",
+                "geometry_types": ["Point"],
+                "bbox": [2.35, 46.20, 8.54, 48.86],
+                "covering": {"bbox": {
+                    "xmin": ["bbox", "xmin"], "ymin": ["bbox", "ymin"],
+                    "xmax": ["bbox", "xmax"], "ymax": ["bbox", "ymax"]
+                }}
+            }}
+        });
+        let schema = Arc::new(Schema::new_with_metadata(
+            vec![
+                Field::new("name", DataType::Utf8, false),
+                Field::new("scalerank", DataType::Int64, false),
+                Field::new("geometry", DataType::Binary, false),
+                Field::new("bbox", bbox.data_type().clone(), false),
+            ],
+            HashMap::from([("geo".to_string(), geo.to_string())]),
+        ));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(places.iter().map(|p| p.0))),
+                Arc::new(Int64Array::from_iter_values(places.iter().map(|p| p.1))),
+                Arc::new(BinaryArray::from_iter_values(
+                    places.iter().map(|p| wkb_point(p.2, p.3)),
+                )),
+                Arc::new(bbox),
+            ],
+        )
+        .unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(path).unwrap(), schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn geoparquet_features() {
+        let path = std::env::temp_dir().join(format!("bbox-test-{}.parquet", std::process::id()));
+        write_geoparquet(&path);
+
+        let fc = file_collection(&path, "").unwrap();
+        assert_eq!(fc.collection.storage_crs, Some(crs_uri(4326)));
+        let extent = fc.collection.extent.as_ref().unwrap();
+        assert_eq!(
+            extent.spatial.as_ref().unwrap().bbox,
+            vec![vec![2.35, 46.20, 8.54, 48.86]]
+        );
+        let queryables = fc.source.queryables("places").await.unwrap().unwrap();
+        assert!(matches!(
+            queryables.properties["scalerank"].type_,
+            Some(QueryableType::Integer)
+        ));
+        assert!(!queryables.properties.contains_key("bbox"));
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(4));
+        assert_eq!(items.features.len(), 4);
+        assert_eq!(
+            items.features[1].geometry,
+            json!({"type": "Point", "coordinates": [8.54, 47.37]})
+        );
+
+        let filter = FilterParams {
+            limit: Some(1),
+            offset: Some(2),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 1);
+        assert_eq!(
+            items.features[0].properties,
+            Some(json!({"name": "Genève", "scalerank": 1}))
+        );
+
+        // Second row group is skipped by its statistics
+        let source = GeoParquetCollectionSource::open(&path).unwrap().0;
+        let metadata = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .metadata()
+            .clone();
+        let bbox = [8.0, 47.0, 9.0, 48.0];
+        assert!(source.row_group_intersects(metadata.row_group(0), &bbox));
+        assert!(!source.row_group_intersects(metadata.row_group(1), &bbox));
+
+        let filter = FilterParams {
+            bbox: Some("8,47,9,48".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 1);
+        assert_eq!(
+            items.features[0].properties,
+            Some(json!({"name": "Zürich", "scalerank": 1}))
+        );
+
+        let mut filter = FilterParams::default();
+        filter
+            .filters
+            .insert("scalerank".to_string(), "1".to_string());
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 2);
+        assert_eq!(items.number_matched, None);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -1,28 +1,68 @@
 //! GeoPackage feature source.
 
-use crate::config::GpkgCollectionCfg;
+use crate::config::{FieldCfg, GpkgCollectionCfg};
+use crate::cql2::{self, Dialect, SpatialIndex, SqlContext, SqlPart, SqlValue};
 use crate::datasource::{
-    AutoscanCollectionDatasource, CollectionDatasource, CollectionSource, CollectionSourceCfg,
-    ConfiguredCollectionCfg, ItemsResult,
+    collection_crs, column_queryables, column_sortables, describe_queryables, describe_schema,
+    feature_schema, geometry_schema_property, geometry_transform, invalid_value, item_links,
+    not_queryable, order_by_clause, property_columns, schema_property, selected_columns,
+    spatial_extent, temporal_extent, AutoscanCollectionDatasource, BboxRow, CollectionDatasource,
+    CollectionSource, CollectionSourceCfg, ConfiguredCollectionCfg, EditFeature, FeatureSender,
+    FieldSelection, GeometryTransform, ItemsResult, SqlParameters,
 };
 use crate::error::{self, Error, Result};
-use crate::filter_params::FilterParams;
+use crate::filter_params::{crs_uri, FilterParams, TemporalType};
 use crate::inventory::FeatureCollection;
 use async_trait::async_trait;
 use bbox_core::config::DsGpkgCfg;
 use bbox_core::ogcapi::*;
-use futures::TryStreamExt;
-use geozero::{geojson, wkb};
+use chrono::{DateTime, FixedOffset};
+use futures::{SinkExt, TryStreamExt};
+use geozero::{geojson, wkb, CoordDimensions, ToWkb};
 use log::{debug, error, info, warn};
+use once_cell::sync::OnceCell;
 use serde_json::json;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
-use sqlx::{Column, Executor, Row, TypeInfo};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions, SqliteRow,
+};
+use sqlx::{
+    Column, Connection, Executor, QueryBuilder, Row, Sqlite, Statement, Transaction, TypeInfo,
+    ValueRef,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Schema name of the attached database with full-text search indexes
+const SEARCH_SCHEMA: &str = "bbox_search";
 
 #[derive(Clone, Debug)]
 pub struct SqliteDatasource {
     pool: SqlitePool,
+    /// Connection pool for transactions with a single connection serializing writes.
+    /// Shared by all collections of the GeoPackage.
+    write_pool: Arc<OnceCell<SqlitePool>>,
+    /// Database with full-text search indexes, attached to all connections
+    search_db: Arc<OnceCell<SearchDb>>,
+}
+
+/// Temporary database file with full-text search indexes, removed when no longer used
+#[derive(Debug)]
+struct SearchDb(PathBuf);
+
+impl Drop for SearchDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
 
+/// Number of search databases created by this process
+static SEARCH_DB_COUNT: AtomicUsize = AtomicUsize::new(0);
+
 impl SqliteDatasource {
     pub async fn from_config(cfg: &DsGpkgCfg) -> Result<Self> {
         let path = cfg.abs_path().to_string_lossy().to_string();
@@ -31,13 +71,99 @@ impl SqliteDatasource {
     }
     pub async fn new_pool(gpkg: &str) -> Result<Self> {
         let conn_options = SqliteConnectOptions::new().filename(gpkg).read_only(true);
-        let pool = SqlitePoolOptions::new()
-            .min_connections(0)
-            .max_connections(8)
-            .connect_with(conn_options)
+        let search_db = Arc::new(OnceCell::new());
+        let pool = connect_pool(conn_options, 8, search_db.clone()).await?;
+        Ok(SqliteDatasource {
+            pool,
+            write_pool: Arc::new(OnceCell::new()),
+            search_db,
+        })
+    }
+    fn filename(&self) -> PathBuf {
+        (*self.pool.connect_options())
+            .clone()
+            .get_filename()
+            .to_path_buf()
+    }
+    async fn enable_writes(&self) -> Result<()> {
+        if self.write_pool.get().is_none() {
+            let options = SqliteConnectOptions::new()
+                .filename(self.filename())
+                .busy_timeout(Duration::from_secs(10));
+            let pool = connect_pool(options, 1, self.search_db.clone()).await?;
+            // Collections are set up sequentially, the pool can't be set concurrently
+            let _ = self.write_pool.set(pool);
+        }
+        Ok(())
+    }
+    /// Database file with full-text search indexes.
+    ///
+    /// The indexes are stored in a separate temporary file of this datasource,
+    /// to keep the GeoPackage unchanged and support read-only files.
+    fn search_db_path(&self) -> PathBuf {
+        match self.search_db.get() {
+            Some(SearchDb(path)) => path.clone(),
+            None => {
+                let n = SEARCH_DB_COUNT.fetch_add(1, Ordering::Relaxed);
+                let name = format!("bbox_search_{}_{n}.db", std::process::id());
+                std::env::temp_dir().join(name)
+            }
+        }
+    }
+    /// Attach the search database to all connections when acquired
+    fn set_search_db(&self, path: PathBuf) {
+        if self.search_db.get().is_none() {
+            let _ = self.search_db.set(SearchDb(path));
+        }
+    }
+}
+
+/// Connection pool attaching the search index database, as soon as it is set
+async fn connect_pool(
+    conn_options: SqliteConnectOptions,
+    max_connections: u32,
+    search_db: Arc<OnceCell<SearchDb>>,
+) -> Result<SqlitePool> {
+    let search_db_idle = search_db.clone();
+    let pool = SqlitePoolOptions::new()
+        .min_connections(0)
+        .max_connections(max_connections)
+        .after_connect(move |conn, _meta| {
+            let search_db = search_db.clone();
+            Box::pin(async move { attach_search_db(conn, &search_db).await })
+        })
+        // Connections opened before the search database was set
+        .before_acquire(move |conn, _meta| {
+            let search_db = search_db_idle.clone();
+            Box::pin(async move {
+                attach_search_db(conn, &search_db).await?;
+                Ok(true)
+            })
+        })
+        .connect_with(conn_options)
+        .await?;
+    Ok(pool)
+}
+
+/// Attach the search index database, if set and not attached yet
+async fn attach_search_db(
+    conn: &mut SqliteConnection,
+    search_db: &OnceCell<SearchDb>,
+) -> sqlx::Result<()> {
+    let Some(SearchDb(path)) = search_db.get() else {
+        return Ok(());
+    };
+    let attached: bool =
+        sqlx::query_scalar("SELECT count(*) > 0 FROM pragma_database_list WHERE name = ?")
+            .bind(SEARCH_SCHEMA)
+            .fetch_one(&mut *conn)
+            .await?;
+    if !attached {
+        let path = path.to_string_lossy().replace('\'', "''");
+        conn.execute(format!("ATTACH DATABASE '{path}' AS {SEARCH_SCHEMA}").as_str())
             .await?;
-        Ok(SqliteDatasource { pool })
     }
+    Ok(())
 }
 
 pub type Datasource = SqliteDatasource;
@@ -63,14 +189,33 @@ impl CollectionDatasource for SqliteDatasource {
         } else if srccfg.table_name.is_some() && srccfg.sql.is_some() {
             warn!("Datasource`{id}`: configuration `table_name` ignored, using `sql` instead");
         }
-        let (pk_column, geometry_column, sql) = if let Some(table_name) = &srccfg.table_name {
+        if srccfg.sql.is_none() && !srccfg.parameters.is_empty() {
+            return Err(Error::DatasourceSetupError(format!(
+                "Datasource `{id}`: parameters require `sql`"
+            )));
+        }
+        let parameters = SqlParameters::new(
+            id,
+            srccfg.sql.as_deref().unwrap_or_default(),
+            &srccfg.parameters,
+        )?;
+        let temporal_column = srccfg.temporal_field.clone();
+        let temporal_end_column = srccfg.temporal_end_field.clone();
+        let mut table = None;
+        let mut rtree = None;
+        let (pk_column, geometry_column, srs_id, sql) = if let Some(table_name) = &srccfg.table_name
+        {
             let pk_column = srccfg
                 .fid_field
                 .clone()
                 .or(detect_pk(self, table_name).await?);
-            let geometry_column = detect_geometry(self, table_name).await?;
+            let (geometry_column, srs_id) = detect_geometry(self, table_name).await?;
             let sql = check_query(self, format!("SELECT * FROM {table_name}")).await?;
-            (pk_column, geometry_column, sql)
+            if srccfg.sql.is_none() {
+                table = Some(table_name.clone());
+                rtree = detect_rtree(self, table_name, &geometry_column).await?;
+            }
+            (pk_column, geometry_column, Some(srs_id), sql)
         } else {
             let pk_column = srccfg.fid_field.clone();
             // TODO: We should also allow user queries without geometry
@@ -81,26 +226,171 @@ impl CollectionDatasource for SqliteDatasource {
                     .ok_or(Error::DatasourceSetupError(format!(
                         "Datasource `{id}`: configuration `geometry_field` missing"
                     )))?;
-            let sql = check_query(self, srccfg.sql.clone().expect("config checked")).await?;
-            (pk_column, geometry_column, sql)
+            // Placeholders are kept for binding request parameters
+            let sql = srccfg.sql.clone().expect("config checked");
+            let setup_sql = check_query(self, parameters.literal_sql(&sql)).await?;
+            let srs_id = detect_srs(self, &setup_sql, &geometry_column).await?;
+            (pk_column, geometry_column, srs_id, sql)
         };
         if pk_column.is_none() {
             warn!("Datasource `{id}`: `fid_field` missing - single item queries will be ignored");
         }
+        // Undefined SRS (0, -1) can't be transformed
+        let storage_srid = srs_id.filter(|srs_id| *srs_id > 0);
+        let field_selection = FieldSelection {
+            include: &srccfg.include_fields,
+            exclude: &srccfg.exclude_fields,
+            fields: &srccfg.fields,
+        };
+        if srccfg.transactions {
+            field_selection.check_writable(id)?;
+        }
+        let source_columns = query_columns(self, &parameters.literal_sql(&sql)).await?;
+        let mut required_columns = vec![geometry_column.as_str()];
+        required_columns.extend(pk_column.as_deref());
+        required_columns.extend(temporal_column.as_deref());
+        required_columns.extend(temporal_end_column.as_deref());
+        let (sql, fields) = match field_selection.query(
+            id,
+            &sql,
+            &source_columns,
+            &required_columns,
+            |col, decimals| {
+                if decimals == 0 {
+                    format!(r#"CAST(round("{col}") AS INTEGER)"#)
+                } else {
+                    format!(r#"round("{col}", {decimals})"#)
+                }
+            },
+        )? {
+            Some(query) => {
+                check_query(self, parameters.literal_sql(&query)).await?;
+                (query, Some(srccfg.fields.clone()))
+            }
+            None => (sql, None),
+        };
+        let setup_sql = parameters.literal_sql(&sql);
+        // All columns with supported types
+        let mut sort_columns = get_column_info(self, &setup_sql, None).await?;
+        sort_columns.remove(&geometry_column);
+        let other_columns = if srccfg.queryable_fields.is_empty() {
+            sort_columns.clone()
+        } else {
+            let mut queryable_fields = srccfg.queryable_fields.clone();
+            queryable_fields.extend(temporal_column.clone());
+            queryable_fields.extend(temporal_end_column.clone());
+            get_column_info(self, &setup_sql, Some(&queryable_fields)).await?
+        };
+        let columns: Vec<String> = query_columns(self, &setup_sql)
+            .await?
+            .into_iter()
+            .filter(|col| Some(col) != pk_column.as_ref() && col != &geometry_column)
+            .collect();
+        let search_index = if srccfg.search_fields.is_empty() {
+            None
+        } else {
+            let (Some(table), Some(_), None) = (&srccfg.table_name, &pk_column, &srccfg.sql) else {
+                return Err(Error::DatasourceSetupError(format!(
+                    "Datasource `{id}`: search fields require `table_name` and a primary key"
+                )));
+            };
+            // Search fields are property names, the index contains table columns
+            let mut search_columns = Vec::new();
+            for field in &srccfg.search_fields {
+                let column = srccfg
+                    .fields
+                    .iter()
+                    .find(|cfg| cfg.property_name() == field)
+                    .map(|cfg| &cfg.name)
+                    .unwrap_or(field);
+                if !source_columns.contains(column) {
+                    return Err(Error::DatasourceSetupError(format!(
+                        "Datasource `{id}`: search field `{field}` not found"
+                    )));
+                }
+                search_columns.push(column.clone());
+            }
+            Some(create_search_index(self, id, table, search_columns).await?)
+        };
+        let edit_table = if srccfg.transactions {
+            let (Some(table), Some(srs_id), None) = (&srccfg.table_name, srs_id, &srccfg.sql)
+            else {
+                return Err(Error::DatasourceSetupError(format!(
+                    "Datasource `{id}`: transactions require `table_name`"
+                )));
+            };
+            if pk_column.is_none() {
+                return Err(Error::DatasourceSetupError(format!(
+                    "Datasource `{id}`: transactions require a single column primary key"
+                )));
+            }
+            self.enable_writes().await?;
+            Some(GpkgEditTable {
+                table: table.clone(),
+                srs_id,
+                columns: columns.clone(),
+            })
+        } else {
+            None
+        };
         let source = GpkgCollectionSource {
             ds: self.clone(),
             sql,
+            parameters,
             geometry_column,
+            srs_id: storage_srid,
             pk_column,
+            temporal_column,
+            temporal_end_column,
+            columns,
+            fields,
+            row_filter: cfg
+                .access
+                .as_ref()
+                .and_then(|access| access.row_filter.clone()),
+            other_columns,
+            sort_columns,
+            table,
+            rtree,
+            search_index,
+            edit_table,
+        };
+        let (spatial, temporal) = extent
+            .map(|extent| (extent.spatial, extent.temporal))
+            .unwrap_or_default();
+        let spatial = match (spatial, &srccfg.table_name, &srccfg.sql) {
+            (Some(spatial), _, _) => Some(spatial),
+            (None, Some(table_name), None) => source
+                .query_spatial_extent(table_name)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Datasource `{id}`: spatial extent query failed: {e}");
+                    None
+                }),
+            _ => None,
+        };
+        let temporal = match temporal {
+            Some(temporal) => Some(temporal),
+            None => source.query_temporal_extent().await.unwrap_or_else(|e| {
+                warn!("Datasource `{id}`: temporal extent query failed: {e}");
+                None
+            }),
+        };
+        let extent = if spatial.is_some() || temporal.is_some() {
+            Some(CoreExtent { spatial, temporal })
+        } else {
+            None
         };
 
-        let collection = CoreCollection {
+        let mut collection = CoreCollection {
             id: id.clone(),
             title: cfg.title.clone(),
             description: cfg.description.clone(),
             extent,
             item_type: None,
-            crs: vec![],
+            crs: collection_crs(storage_srid, GeometryTransform::SUPPORTED, &srccfg.crs),
+            storage_crs: storage_srid.map(crs_uri),
+            foreign_members: serde_json::Map::new(),
             links: vec![ApiLink {
                 href: format!("{base_url}/collections/{id}/items"),
                 rel: Some("items".to_string()),
@@ -110,6 +400,34 @@ impl CollectionDatasource for SqliteDatasource {
                 length: None,
             }],
         };
+        if !source.other_columns.is_empty() || !source.parameters.is_empty() {
+            collection.links.push(ApiLink {
+                href: format!("{base_url}/collections/{id}/queryables"),
+                rel: Some("http://www.opengis.net/def/rel/ogc/1.0/queryables".to_string()),
+                type_: Some("application/schema+json".to_string()),
+                title: cfg.title.clone(),
+                hreflang: None,
+                length: None,
+            })
+        }
+        if !source.sort_columns.is_empty() {
+            collection.links.push(ApiLink {
+                href: format!("{base_url}/collections/{id}/sortables"),
+                rel: Some("http://www.opengis.net/def/rel/ogc/1.0/sortables".to_string()),
+                type_: Some("application/schema+json".to_string()),
+                title: cfg.title.clone(),
+                hreflang: None,
+                length: None,
+            })
+        }
+        collection.links.push(ApiLink {
+            href: format!("{base_url}/collections/{id}/schema"),
+            rel: Some("http://www.opengis.net/def/rel/ogc/1.0/schema".to_string()),
+            type_: Some("application/schema+json".to_string()),
+            title: cfg.title.clone(),
+            hreflang: None,
+            length: None,
+        });
         let fc = FeatureCollection {
             collection,
             source: Box::new(source),
@@ -134,18 +452,6 @@ impl AutoscanCollectionDatasource for SqliteDatasource {
             let table_name: &str = row.try_get("table_name")?;
             let id = table_name.to_string();
             let title: String = row.try_get("identifier")?;
-            let extent = CoreExtent {
-                spatial: Some(CoreExtentSpatial {
-                    bbox: vec![vec![
-                        row.try_get("min_x")?,
-                        row.try_get("min_y")?,
-                        row.try_get("max_x")?,
-                        row.try_get("max_y")?,
-                    ]],
-                    crs: None,
-                }),
-                temporal: None,
-            };
             let coll_cfg = ConfiguredCollectionCfg {
                 source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
                     table_name: Some(table_name.to_string()),
@@ -154,11 +460,10 @@ impl AutoscanCollectionDatasource for SqliteDatasource {
                 name: id.clone(),
                 title: Some(title),
                 description: row.try_get("description")?,
+                extent: None,
+                access: None,
             };
-            if let Ok(fc) = self
-                .setup_collection(&coll_cfg, base_url, Some(extent))
-                .await
-            {
+            if let Ok(fc) = self.setup_collection(&coll_cfg, base_url, None).await {
                 collections.push(fc);
             }
         }
@@ -170,46 +475,287 @@ impl AutoscanCollectionDatasource for SqliteDatasource {
 pub struct GpkgCollectionSource {
     ds: SqliteDatasource,
     sql: String,
+    /// Request parameters bound to placeholders of `sql`
+    parameters: SqlParameters,
     geometry_column: String,
     // geometry_type_name: String,
+    /// SRS of geometry column
+    srs_id: Option<i32>,
     /// Primary key column, None if multi column key.
     pk_column: Option<String>,
+    temporal_column: Option<String>,
+    temporal_end_column: Option<String>,
+    /// Property columns, except primary key and geometry
+    columns: Vec<String>,
+    /// Field output configuration, None without field selection
+    fields: Option<Vec<FieldCfg>>,
+    /// SQL condition restricting readable rows
+    row_filter: Option<String>,
+    /// Queriable columns.
+    other_columns: HashMap<String, QueryableType>,
+    /// Columns which can be used for sorting
+    sort_columns: HashMap<String, QueryableType>,
+    /// Source table, None for custom SQL queries
+    table: Option<String>,
+    /// R-tree spatial index table
+    rtree: Option<String>,
+    /// Full-text index of the search fields
+    search_index: Option<GpkgSearchIndex>,
+    /// Table for transactions, None if collection is read-only
+    edit_table: Option<GpkgEditTable>,
+}
+
+#[derive(Clone, Debug)]
+struct GpkgSearchIndex {
+    /// Quoted FTS5 table name in the attached search database
+    table: String,
+    /// Feature table
+    content: String,
+    /// Indexed columns of the feature table
+    columns: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+struct GpkgEditTable {
+    table: String,
+    srs_id: i32,
+    /// Writable columns, except primary key and geometry
+    columns: Vec<String>,
 }
 
 #[async_trait]
 impl CollectionSource for GpkgCollectionSource {
-    async fn items(&self, filter: &FilterParams) -> Result<ItemsResult> {
-        let mut sql = format!(
-            "
-            WITH query AS ({sql})
-            SELECT *, count(*) OVER() AS __total_cnt FROM query",
-            sql = &self.sql
-        );
-        if let Some(_bboxstr) = &filter.bbox {
-            warn!("Ignoring bbox filter (not supported for this datasource)");
+    async fn send_items(
+        &self,
+        filter: &FilterParams,
+        mut sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        let mut builder = self.query_builder(filter)?;
+        builder.push(format!(
+            "SELECT {select_list}, count(*) OVER() AS __total_cnt FROM query",
+            select_list = self.select_list(filter),
+        ));
+        let mut where_term = false;
+        match filter.bbox() {
+            Ok(Some(bbox)) => {
+                let (Some(rtree), Some(pk)) = (&self.rtree, &self.pk_column) else {
+                    error!("bbox filter requires a table with R-tree spatial index");
+                    return Err(Error::QueryParams);
+                };
+                let mut bbox = match bbox[..] {
+                    [minx, miny, _, maxx, maxy, _] => [minx, miny, maxx, maxy],
+                    _ => [bbox[0], bbox[1], bbox[2], bbox[3]],
+                };
+                if let Some(srs_id) = self.srs_id {
+                    if let Some(transform) = GeometryTransform::new(filter.bbox_srid()?, srs_id)? {
+                        bbox = transform.transform_bbox(bbox)?;
+                    }
+                }
+                push_condition(&mut builder, &mut where_term);
+                builder.push(format!(
+                    r#""{pk}" IN (SELECT id FROM "{rtree}" WHERE minx <= "#
+                ));
+                builder.push_bind(bbox[2]);
+                builder.push(" AND maxx >= ");
+                builder.push_bind(bbox[0]);
+                builder.push(" AND miny <= ");
+                builder.push_bind(bbox[3]);
+                builder.push(" AND maxy >= ");
+                builder.push_bind(bbox[1]);
+                builder.push(")");
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Ignoring invalid bbox: {e}");
+                return Err(Error::QueryParams);
+            }
+        }
+        if let Some(temporal_column) = &self.temporal_column {
+            let temporal_end_column = self.temporal_end_column.as_ref().unwrap_or(temporal_column);
+            match filter.temporal() {
+                Ok(Some(parts)) => {
+                    push_condition(&mut builder, &mut where_term);
+                    match parts[..] {
+                        [TemporalType::DateTime(dt)] => {
+                            push_temporal(&mut builder, temporal_column, "=", dt);
+                        }
+                        [TemporalType::Open, TemporalType::DateTime(dt)] => {
+                            push_temporal(&mut builder, temporal_column, "<=", dt);
+                        }
+                        [TemporalType::DateTime(dt), TemporalType::Open] => {
+                            push_temporal(&mut builder, temporal_column, ">=", dt);
+                        }
+                        [TemporalType::DateTime(dt1), TemporalType::DateTime(dt2)] => {
+                            push_temporal(&mut builder, temporal_column, ">=", dt1);
+                            builder.push(" AND ");
+                            push_temporal(&mut builder, temporal_end_column, "<=", dt2);
+                        }
+                        _ => {
+                            error!("Invalid datetime interval");
+                            return Err(Error::QueryParams);
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Ignoring invalid temporal field: {e}");
+                    return Err(Error::QueryParams);
+                }
+            }
+        }
+        let others = filter.other_params().map_err(|e| {
+            error!("{e}");
+            Error::QueryParams
+        })?;
+        for (key, val) in others {
+            if self.parameters.contains(key) {
+                // Bound to the collection query
+                continue;
+            }
+            // check if the passed in field matches queryables
+            let Some(queryable_type) = self.other_columns.get(key) else {
+                return Err(not_queryable(key));
+            };
+            push_condition(&mut builder, &mut where_term);
+            if val.contains('*') {
+                builder.push(format!(r#""{key}" LIKE "#));
+                builder.push_bind(val.replace('*', "%"));
+                continue;
+            }
+            debug!("{key} = {val}");
+            match queryable_type {
+                QueryableType::String => {
+                    builder.push(format!(r#""{key}" = "#));
+                    builder.push_bind(val.clone());
+                }
+                QueryableType::Integer => {
+                    builder.push(format!(r#""{key}" = "#));
+                    builder.push_bind(val.parse::<i64>().map_err(|_| invalid_value(key, val))?);
+                }
+                QueryableType::Number => {
+                    builder.push(format!(r#""{key}" = "#));
+                    builder.push_bind(val.parse::<f64>().map_err(|_| invalid_value(key, val))?);
+                }
+                QueryableType::Bool => {
+                    builder.push(format!(r#""{key}" = "#));
+                    builder.push_bind(val.parse::<bool>().map_err(|_| invalid_value(key, val))?);
+                }
+                QueryableType::Datetime => {
+                    let dt =
+                        DateTime::parse_from_rfc3339(val).map_err(|_| invalid_value(key, val))?;
+                    push_temporal(&mut builder, key, "=", dt);
+                }
+            }
+        }
+        match filter.cql2_filter() {
+            Ok(Some(expr)) => {
+                let filter_srid = filter.filter_srid()?;
+                // Geometry literals are transformed only when used with spatial operators
+                let transform = match (self.srs_id, &self.rtree) {
+                    (Some(srs_id), Some(_)) => GeometryTransform::new(filter_srid, srs_id).ok(),
+                    _ => None,
+                }
+                .flatten();
+                let ctx = SqlContext {
+                    dialect: Dialect::Gpkg,
+                    geometry_column: &self.geometry_column,
+                    queryables: &self.other_columns,
+                    filter_srid,
+                    storage_srid: self.srs_id,
+                    spatial_index: match (&self.rtree, &self.pk_column) {
+                        (Some(rtree), Some(pk)) => Some(SpatialIndex {
+                            rtree,
+                            id_column: pk,
+                            transform: transform.as_ref(),
+                        }),
+                        _ => None,
+                    },
+                };
+                let parts = cql2::to_sql(&expr, &ctx)?;
+                push_condition(&mut builder, &mut where_term);
+                push_sql_parts(&mut builder, parts);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Invalid filter: {e}");
+                return Err(e.into());
+            }
+        }
+        let search_terms = filter.search_terms();
+        if filter.q.is_some() {
+            let (Some(search), Some(pk)) = (&self.search_index, &self.pk_column) else {
+                error!("Collection has no search fields");
+                return Err(Error::QueryParams);
+            };
+            push_condition(&mut builder, &mut where_term);
+            if search_terms.is_empty() {
+                builder.push("0");
+            } else {
+                let fts = &search.table;
+                builder.push(format!(
+                    r#""{pk}" IN (SELECT rowid FROM {SEARCH_SCHEMA}.{fts} WHERE {fts} MATCH "#
+                ));
+                builder.push_bind(fts_query(&search_terms));
+                builder.push(")");
+            }
+        }
+        if let Some(row_filter) = &self.row_filter {
+            push_condition(&mut builder, &mut where_term);
+            push_row_filter(&mut builder, row_filter, filter.username.clone());
+        }
+        let sortby = filter.sortby()?;
+        match (&self.search_index, &self.pk_column) {
+            (Some(search), Some(pk)) if !search_terms.is_empty() && sortby.is_empty() => {
+                // Most relevant features first
+                let fts = &search.table;
+                builder.push(format!(
+                    r#" ORDER BY (SELECT rank FROM {SEARCH_SCHEMA}.{fts} WHERE {fts} MATCH "#
+                ));
+                builder.push_bind(fts_query(&search_terms));
+                builder.push(format!(r#" AND rowid = query."{pk}"), "{pk}" ASC"#));
+            }
+            _ => {
+                if let Some(order_by) =
+                    order_by_clause(&sortby, &self.sort_columns, self.pk_column.as_ref())?
+                {
+                    builder.push(order_by);
+                }
+            }
         }
         let limit = filter.limit_or_default();
         if limit > 0 {
-            sql.push_str(&format!(" LIMIT {limit}"));
+            builder.push(format!(" LIMIT {limit}"));
+        } else if filter.offset.is_some() {
+            // SQLite requires a LIMIT clause with OFFSET
+            builder.push(" LIMIT -1");
         }
         if let Some(offset) = filter.offset {
-            sql.push_str(&format!(" OFFSET {offset}"));
+            builder.push(format!(" OFFSET {offset}"));
+        }
+        debug!("SQL: {}", builder.sql());
+        let transform = geometry_transform(self.srs_id, filter.crs_srid()?)?;
+        let mut rows = builder.build().fetch(&self.ds.pool);
+        let mut number_matched = 0;
+        let mut number_returned = 0;
+        while let Some(row) = rows.try_next().await? {
+            if number_returned == 0 {
+                number_matched = row.try_get::<u32, _>("__total_cnt")? as u64;
+            }
+            let mut feature = row_to_feature(&row, self)?;
+            if let Some(transform) = &transform {
+                transform.transform(&mut feature.geometry)?;
+            }
+            if sender.send(feature).await.is_err() {
+                debug!("Items receiver closed");
+                return Err(Error::ItemsStreamClosed);
+            }
+            number_returned += 1;
         }
-        let rows = sqlx::query(&sql).fetch_all(&self.ds.pool).await?;
-        let number_matched = if let Some(row) = rows.first() {
-            row.try_get::<u32, _>("__total_cnt")? as u64
-        } else {
-            0
-        };
-        let number_returned = rows.len() as u64;
-        let items = rows
-            .iter()
-            .map(|row| row_to_feature(row, self))
-            .collect::<Result<Vec<_>>>()?;
         let result = ItemsResult {
-            features: items,
-            number_matched,
+            features: Vec::new(),
+            number_matched: Some(number_matched),
             number_returned,
+            next_cursor: None,
         };
         Ok(result)
     }
@@ -219,175 +765,2006 @@ impl CollectionSource for GpkgCollectionSource {
         base_url: &str,
         collection_id: &str,
         feature_id: &str,
+        filter: &FilterParams,
     ) -> Result<Option<CoreFeature>> {
         let Some(pk) = &self.pk_column else {
             warn!("Ignoring error getting item for {collection_id} without single primary key");
             return Ok(None);
         };
-        let sql = format!(
-            "
-            WITH query AS ({sql})
-            SELECT * FROM query WHERE {pk} = ?",
-            sql = &self.sql
-        );
-        if let Some(row) = sqlx::query(&sql)
-            .bind(feature_id)
-            .fetch_optional(&self.ds.pool)
-            .await?
-        {
+        let mut builder = self.query_builder(filter)?;
+        builder.push(format!(
+            r#"SELECT {select_list} FROM query WHERE "{pk}" = "#,
+            select_list = self.select_list(filter),
+        ));
+        builder.push_bind(feature_id);
+        if let Some(row_filter) = &self.row_filter {
+            builder.push(" AND ");
+            push_row_filter(&mut builder, row_filter, filter.username.clone());
+        }
+        if let Some(row) = builder.build().fetch_optional(&self.ds.pool).await? {
             let mut item = row_to_feature(&row, self)?;
-            item.links = vec![
-                ApiLink {
-                    href: format!("{base_url}/collections/{collection_id}/items/{feature_id}"),
-                    rel: Some("self".to_string()),
-                    type_: Some("application/geo+json".to_string()),
-                    title: Some("this document".to_string()),
-                    hreflang: None,
-                    length: None,
-                },
-                ApiLink {
-                    href: format!("{base_url}/collections/{collection_id}"),
-                    rel: Some("collection".to_string()),
-                    type_: Some("application/geo+json".to_string()),
-                    title: Some("the collection document".to_string()),
-                    hreflang: None,
-                    length: None,
-                },
-            ];
+            if let Some(transform) = geometry_transform(self.srs_id, filter.crs_srid()?)? {
+                transform.transform(&mut item.geometry)?;
+            }
+            item.links = item_links(base_url, collection_id, feature_id);
             Ok(Some(item))
         } else {
             Ok(None)
         }
     }
 
-    async fn queryables(&self, _collection_id: &str) -> Result<Option<Queryables>> {
-        Ok(None)
+    async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        if self.other_columns.is_empty() && self.parameters.is_empty() {
+            return Ok(None);
+        }
+        let mut queryables = column_queryables(collection_id, &self.other_columns);
+        self.parameters.add_queryables(&mut queryables);
+        Ok(Some(describe_queryables(queryables, self.field_cfgs())))
     }
-}
-
-fn row_to_feature(row: &SqliteRow, table_info: &GpkgCollectionSource) -> Result<CoreFeature> {
-    let mut id = None;
-    let mut properties = json!({});
-    for col in row.columns() {
-        #[allow(clippy::if_same_then_else)]
-        if col.name() == table_info.geometry_column {
-            // Skip geometry
-        } else if col.name() == "__total_cnt" {
-            // Skip count
-        } else if col.name() == table_info.pk_column.as_ref().unwrap_or(&"".to_string()) {
-            // Get id as String
-            id = match col.type_info().name() {
-                "TEXT" => Some(row.try_get::<String, _>(col.ordinal())?),
-                "INTEGER" => Some(row.try_get::<i64, _>(col.ordinal())?.to_string()),
-                _ => None,
+    async fn sortables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        if self.sort_columns.is_empty() {
+            return Ok(None);
+        }
+        let sortables = column_sortables(collection_id, &self.sort_columns);
+        Ok(Some(describe_queryables(sortables, self.field_cfgs())))
+    }
+    async fn schema(&self, collection_id: &str) -> Result<Option<FeatureSchema>> {
+        let mut properties = Vec::new();
+        let mut required = Vec::new();
+        let geometry_type: Option<String> = if let Some(table) = &self.table {
+            let sql = "SELECT geometry_type_name FROM gpkg_geometry_columns WHERE table_name = ? AND column_name = ?";
+            sqlx::query_scalar(sql)
+                .bind(table)
+                .bind(&self.geometry_column)
+                .fetch_optional(&self.ds.pool)
+                .await?
+        } else {
+            None
+        };
+        if let (Some(table), None) = (&self.table, &self.fields) {
+            let sql = format!(r#"PRAGMA table_info("{table}")"#);
+            let columns = sqlx::query(&sql).fetch_all(&self.ds.pool).await?;
+            for col in columns {
+                let name: String = col.try_get("name")?;
+                let property = if name == self.geometry_column {
+                    geometry_schema_property(geometry_type.as_deref(), true)
+                } else {
+                    gpkg_schema_property(col.try_get("type")?)
+                };
+                if col.try_get::<bool, _>("notnull")? {
+                    required.push(name.clone());
+                }
+                properties.push((name, property));
             }
         } else {
-            properties[col.name()] = match col.type_info().name() {
-                "TEXT" => json!(row.try_get::<&str, _>(col.ordinal())?),
-                "INTEGER" => json!(row.try_get::<i64, _>(col.ordinal())?),
-                "REAL" => json!(row.try_get::<f64, _>(col.ordinal())?),
-                "DATETIME" => json!(row.try_get::<&str, _>(col.ordinal())?),
-                ty => json!(format!("<{ty}>")),
+            // Result columns of custom SQL query or field selection
+            let sql = self.parameters.literal_sql(&self.sql);
+            let stmt = self.ds.pool.acquire().await?.prepare(&sql).await?;
+            for col in stmt.columns() {
+                let property = if col.name() == self.geometry_column {
+                    geometry_schema_property(geometry_type.as_deref(), true)
+                } else {
+                    gpkg_schema_property(col.type_info().name())
+                };
+                properties.push((col.name().to_string(), property));
             }
         }
+        let schema = feature_schema(
+            collection_id,
+            properties,
+            required,
+            self.pk_column.as_ref(),
+            self.temporal_column.as_ref(),
+            self.temporal_end_column.as_ref(),
+        );
+        Ok(Some(describe_schema(schema, self.field_cfgs())))
+    }
+    async fn srs_definition(&self, srid: i32) -> Result<Option<String>> {
+        let definition = sqlx::query_scalar(
+            "SELECT definition FROM gpkg_spatial_ref_sys WHERE srs_id = ? AND definition != 'undefined'",
+        )
+        .bind(srid)
+        .fetch_optional(&self.ds.pool)
+        .await?;
+        Ok(definition)
     }
-    let wkb: wkb::Decode<geojson::GeoJsonString> =
-        row.try_get(table_info.geometry_column.as_str())?;
-    let geom = wkb.geometry.ok_or(error::Error::GeometryFormatError)?;
 
-    let item = CoreFeature {
-        type_: "Feature".to_string(),
-        id,
-        geometry: serde_json::from_str(&geom.0).map_err(|_| error::Error::GeometryFormatError)?,
-        properties: Some(properties),
-        links: vec![],
-    };
+    fn text_search(&self) -> bool {
+        self.search_index.is_some()
+    }
+    fn check_parameters(&self, filter: &FilterParams) -> Result<()> {
+        self.parameters.check(filter)
+    }
 
-    Ok(item)
-}
+    fn transactions(&self) -> bool {
+        self.edit_table.is_some()
+    }
 
-async fn detect_pk(ds: &SqliteDatasource, table: &str) -> Result<Option<String>> {
-    let sql = r#"
-        SELECT
-          (SELECT COUNT(*) FROM pragma_table_info(?) ti WHERE ti.pk > 0) as pksize,
-          (SELECT ti.name FROM pragma_table_info(?) ti WHERE ti.pk = 1) as pk
-    "#;
-    let row = sqlx::query(sql)
-        .bind(table)
-        .bind(table)
-        .fetch_one(&ds.pool)
-        .await?;
-    let pksize: u16 = row.try_get("pksize")?;
-    let pk_column: Option<String> = if pksize == 1 {
-        row.try_get("pk")?
-    } else {
-        None
-    };
-    Ok(pk_column)
-}
+    async fn item_etag(&self, feature_id: &str) -> Result<Option<String>> {
+        if self.edit_table.is_none() {
+            return Ok(None);
+        }
+        let mut conn = self.ds.pool.acquire().await?;
+        self.fetch_etag(&mut conn, feature_id).await
+    }
 
-async fn detect_geometry(ds: &SqliteDatasource, table: &str) -> Result<String> {
-    let sql = r#"
-        SELECT column_name, geometry_type_name
-        FROM gpkg_geometry_columns
-        WHERE table_name = ?
-    "#;
-    let row = sqlx::query(sql)
-        .bind(table)
-        // We take the first result only
-        .fetch_one(&ds.pool)
-        .await?;
-    let geometry_column: String = row.try_get("column_name")?;
-    let _geometry_type_name: String = row.try_get("geometry_type_name")?;
-    Ok(geometry_column)
-}
+    async fn create_item(&self, feature: &EditFeature) -> Result<String> {
+        let (edit, pk) = self.edit_table()?;
+        let mut columns = property_columns(feature, &edit.columns, pk)?;
+        if feature.properties.contains_key(pk) {
+            columns.push(pk);
+        }
+        let geometry = storage_geometry(feature.geometry.as_ref(), feature.srid, edit.srs_id)?;
+        let (wkb, bbox) = gpkg_geometry(geometry.as_ref(), edit.srs_id)?;
+        let table = &edit.table;
+        let mut builder: QueryBuilder<Sqlite> =
+            QueryBuilder::new(format!(r#"INSERT INTO "{table}""#));
+        if columns.is_empty() && feature.geometry.is_none() {
+            builder.push(" DEFAULT VALUES");
+        } else {
+            builder.push(" (");
+            let mut separated = builder.separated(",");
+            for col in &columns {
+                separated.push(format!(r#""{col}""#));
+            }
+            if feature.geometry.is_some() {
+                separated.push(format!(r#""{}""#, self.geometry_column));
+            }
+            builder.push(") VALUES (");
+            for (i, col) in columns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(",");
+                }
+                push_json_value(&mut builder, &feature.properties[*col]);
+            }
+            if feature.geometry.is_some() {
+                if !columns.is_empty() {
+                    builder.push(",");
+                }
+                builder.push_bind(wkb);
+            }
+            builder.push(")");
+        }
+        debug!("SQL: {}", builder.sql());
+        let (mut tx, triggers) = self.begin_write(edit).await?;
+        let rowid = builder.build().execute(&mut *tx).await?.last_insert_rowid();
+        update_rtree(&mut tx, edit, self.rtree.as_deref(), rowid, bbox).await?;
+        update_search_index(&mut tx, self.search_index.as_ref(), rowid, false).await?;
+        let sql = format!(r#"SELECT CAST("{pk}" AS TEXT) FROM "{table}" WHERE rowid = ?"#);
+        let id: String = sqlx::query_scalar(&sql)
+            .bind(rowid)
+            .fetch_one(&mut *tx)
+            .await?;
+        commit_write(tx, triggers).await?;
+        Ok(id)
+    }
 
-async fn check_query(ds: &SqliteDatasource, sql: String) -> Result<String> {
-    debug!("Collection query: {sql}");
-    if let Err(e) = ds.pool.acquire().await?.prepare(&sql).await {
-        error!("Error in collection query `{sql}`: {e}");
-        return Err(e.into());
+    async fn replace_item(
+        &self,
+        feature_id: &str,
+        feature: &EditFeature,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let (edit, pk) = self.edit_table()?;
+        // Validate properties
+        property_columns(feature, &edit.columns, pk)?;
+        let columns = edit.columns.iter().map(String::as_str).collect();
+        let geometry = feature.geometry.clone().unwrap_or_default();
+        self.update(
+            feature_id,
+            feature,
+            columns,
+            Some(&geometry),
+            etag,
+            username,
+        )
+        .await
+    }
+
+    async fn update_item(
+        &self,
+        feature_id: &str,
+        feature: &EditFeature,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let (edit, pk) = self.edit_table()?;
+        let columns = property_columns(feature, &edit.columns, pk)?;
+        self.update(
+            feature_id,
+            feature,
+            columns,
+            feature.geometry.as_ref(),
+            etag,
+            username,
+        )
+        .await
+    }
+
+    async fn delete_item(
+        &self,
+        feature_id: &str,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let (edit, pk) = self.edit_table()?;
+        let (mut tx, triggers) = self.begin_write(edit).await?;
+        let rowid = self.check_item(&mut tx, feature_id, etag, username).await?;
+        update_search_index(&mut tx, self.search_index.as_ref(), rowid, true).await?;
+        let sql = format!(r#"DELETE FROM "{}" WHERE "{pk}" = ?"#, edit.table);
+        sqlx::query(&sql).bind(feature_id).execute(&mut *tx).await?;
+        update_rtree(&mut tx, edit, self.rtree.as_deref(), rowid, None).await?;
+        commit_write(tx, triggers).await?;
+        Ok(())
     }
-    Ok(sql)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl GpkgCollectionSource {
+    /// Configured output fields
+    fn field_cfgs(&self) -> &[FieldCfg] {
+        self.fields.as_deref().unwrap_or_default()
+    }
 
-    #[tokio::test]
-    async fn gpkg_content() {
-        let mut pool = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
-            .await
-            .unwrap();
-        let collections = pool.collections("").await.unwrap();
-        assert_eq!(collections.len(), 3);
-        assert_eq!(
-            collections
-                .iter()
-                .map(|col| col.collection.id.clone())
-                .collect::<Vec<_>>(),
-            vec![
-                "ne_10m_rivers_lake_centerlines",
-                "ne_10m_lakes",
-                "ne_10m_populated_places"
-            ]
-        );
+    /// Spatial extent in WGS84 from `gpkg_contents` or the R-tree index
+    async fn query_spatial_extent(&self, table: &str) -> Result<Option<CoreExtentSpatial>> {
+        let sql = "SELECT min_x, min_y, max_x, max_y FROM gpkg_contents WHERE table_name = ?";
+        let mut bbox: Option<BboxRow> = sqlx::query_as(sql)
+            .bind(table)
+            .fetch_optional(&self.ds.pool)
+            .await?;
+        if !matches!(bbox, Some((Some(_), Some(_), Some(_), Some(_)))) {
+            if let Some(rtree) = &self.rtree {
+                let sql =
+                    format!(r#"SELECT min(minx), min(miny), max(maxx), max(maxy) FROM "{rtree}""#);
+                bbox = Some(sqlx::query_as(&sql).fetch_one(&self.ds.pool).await?);
+            }
+        }
+        let Some((Some(minx), Some(miny), Some(maxx), Some(maxy))) = bbox else {
+            return Ok(None);
+        };
+        Ok(spatial_extent([minx, miny, maxx, maxy], self.srs_id))
     }
 
-    #[tokio::test]
-    async fn gpkg_features() {
-        let filter = FilterParams::default();
-        let ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
-            .await
-            .unwrap();
-        let source = GpkgCollectionSource {
-            ds,
-            sql: "SELECT * FROM ne_10m_lakes".to_string(),
-            geometry_column: "geom".to_string(),
-            pk_column: Some("fid".to_string()),
+    /// Temporal extent from `temporal_field` and `temporal_end_field`
+    async fn query_temporal_extent(&self) -> Result<Option<CoreExtentTemporal>> {
+        let Some(temporal_column) = &self.temporal_column else {
+            return Ok(None);
         };
-        let items = source.items(&filter).await.unwrap();
-        assert_eq!(items.features.len(), filter.limit_or_default() as usize);
+        let temporal_end_column = self.temporal_end_column.as_ref().unwrap_or(temporal_column);
+        let sql = format!(
+            r#"SELECT min("{temporal_column}"), max("{temporal_end_column}") FROM ({}) AS query"#,
+            self.parameters.literal_sql(&self.sql)
+        );
+        let (start, end): (Option<String>, Option<String>) =
+            sqlx::query_as(&sql).fetch_one(&self.ds.pool).await?;
+        Ok(Some(temporal_extent(start, end)))
+    }
+
+    /// Query builder with collection query `query`, bound to the request parameters of `filter`
+    fn query_builder(&self, filter: &FilterParams) -> Result<QueryBuilder<'_, Sqlite>> {
+        let mut builder = QueryBuilder::new("WITH query AS (");
+        push_sql_parts(
+            &mut builder,
+            self.parameters.query_parts(&self.sql, filter)?,
+        );
+        builder.push(")\n");
+        Ok(builder)
+    }
+
+    /// Selected columns of `query` for `properties` and `skipGeometry`
+    fn select_list(&self, filter: &FilterParams) -> String {
+        let selected = selected_columns(filter, &self.columns);
+        if selected.is_none() && !filter.skip_geometry() {
+            return "*".to_string();
+        }
+        let mut columns: Vec<&str> = self.pk_column.iter().map(String::as_str).collect();
+        columns
+            .extend(selected.unwrap_or_else(|| self.columns.iter().map(String::as_str).collect()));
+        if !filter.skip_geometry() {
+            columns.push(&self.geometry_column);
+        }
+        if columns.is_empty() {
+            return format!(r#"NULL AS "{}""#, self.geometry_column);
+        }
+        columns
+            .iter()
+            .map(|col| format!(r#""{col}""#))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn edit_table(&self) -> Result<(&GpkgEditTable, &str)> {
+        match (&self.edit_table, &self.pk_column) {
+            (Some(edit), Some(pk)) => Ok((edit, pk)),
+            _ => Err(Error::TransactionsNotSupported),
+        }
+    }
+
+    async fn fetch_etag(
+        &self,
+        conn: &mut SqliteConnection,
+        feature_id: &str,
+    ) -> Result<Option<String>> {
+        let Some(pk) = &self.pk_column else {
+            return Ok(None);
+        };
+        let sql = format!(
+            r#"
+            WITH query AS ({sql})
+            SELECT * FROM query WHERE "{pk}" = ?"#,
+            sql = &self.sql
+        );
+        let row = sqlx::query(&sql)
+            .bind(feature_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        row.map(|row| row_to_feature(&row, self).map(|feature| feature_etag(&feature)))
+            .transpose()
+    }
+
+    /// Check entity tag and return rowid of feature. Rows hidden by `row_filter` are not found.
+    async fn check_item(
+        &self,
+        conn: &mut SqliteConnection,
+        feature_id: &str,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<i64> {
+        let (edit, pk) = self.edit_table()?;
+        let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+            r#"SELECT rowid FROM "{}" WHERE "{pk}" = "#,
+            edit.table
+        ));
+        builder.push_bind(feature_id);
+        if let Some(row_filter) = &self.row_filter {
+            builder.push(" AND ");
+            push_row_filter(&mut builder, row_filter, username.map(str::to_string));
+        }
+        let Some(rowid) = builder
+            .build_query_scalar()
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            return Err(Error::FeatureNotFound);
+        };
+        if let Some(etag) = etag {
+            if self.fetch_etag(conn, feature_id).await?.as_deref() != Some(etag) {
+                return Err(Error::PreconditionFailed);
+            }
+        }
+        Ok(rowid)
+    }
+
+    /// Start write transaction and remove R-tree triggers
+    ///
+    /// The triggers of the GeoPackage R-tree extension require spatial SQL functions,
+    /// which are not available in our connections. The index is updated explicitly instead.
+    async fn begin_write(
+        &self,
+        edit: &GpkgEditTable,
+    ) -> Result<(Transaction<'static, Sqlite>, Vec<String>)> {
+        let pool = self
+            .ds
+            .write_pool
+            .get()
+            .ok_or(Error::TransactionsNotSupported)?;
+        let mut tx = pool.begin().await?;
+        // Acquire write lock before reading
+        sqlx::query(
+            "UPDATE gpkg_contents SET last_change = strftime('%Y-%m-%dT%H:%M:%fZ','now') WHERE table_name = ?",
+        )
+        .bind(&edit.table)
+        .execute(&mut *tx)
+        .await?;
+        let triggers: Vec<(String, String)> = sqlx::query_as(
+            "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = ? AND name LIKE 'rtree_%'",
+        )
+        .bind(&edit.table)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (name, _) in &triggers {
+            sqlx::query(&format!(r#"DROP TRIGGER "{name}""#))
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok((tx, triggers.into_iter().map(|(_, sql)| sql).collect()))
+    }
+
+    async fn update(
+        &self,
+        feature_id: &str,
+        feature: &EditFeature,
+        columns: Vec<&str>,
+        geometry: Option<&serde_json::Value>,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let (edit, pk) = self.edit_table()?;
+        let storage_geometry = storage_geometry(geometry, feature.srid, edit.srs_id)?;
+        let (wkb, bbox) = gpkg_geometry(storage_geometry.as_ref(), edit.srs_id)?;
+        let (mut tx, triggers) = self.begin_write(edit).await?;
+        let rowid = self.check_item(&mut tx, feature_id, etag, username).await?;
+        if !columns.is_empty() {
+            update_search_index(&mut tx, self.search_index.as_ref(), rowid, true).await?;
+        }
+        if !columns.is_empty() || geometry.is_some() {
+            let mut builder: QueryBuilder<Sqlite> =
+                QueryBuilder::new(format!(r#"UPDATE "{}" SET "#, edit.table));
+            for (i, col) in columns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(",");
+                }
+                builder.push(format!(r#""{col}" = "#));
+                let value = feature.properties.get(*col);
+                push_json_value(&mut builder, value.unwrap_or(&serde_json::Value::Null));
+            }
+            if geometry.is_some() {
+                if !columns.is_empty() {
+                    builder.push(",");
+                }
+                builder.push(format!(r#""{}" = "#, self.geometry_column));
+                builder.push_bind(wkb);
+            }
+            builder.push(format!(r#" WHERE "{pk}" = "#));
+            builder.push_bind(feature_id);
+            debug!("SQL: {}", builder.sql());
+            builder.build().execute(&mut *tx).await?;
+        }
+        if geometry.is_some() {
+            update_rtree(&mut tx, edit, self.rtree.as_deref(), rowid, bbox).await?;
+        }
+        if !columns.is_empty() {
+            update_search_index(&mut tx, self.search_index.as_ref(), rowid, false).await?;
+        }
+        commit_write(tx, triggers).await?;
+        Ok(())
+    }
+}
+
+/// Restore R-tree triggers and commit transaction
+async fn commit_write(mut tx: Transaction<'static, Sqlite>, triggers: Vec<String>) -> Result<()> {
+    for sql in triggers {
+        sqlx::query(&sql).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Update R-tree spatial index and layer extent
+async fn update_rtree(
+    conn: &mut SqliteConnection,
+    edit: &GpkgEditTable,
+    rtree: Option<&str>,
+    rowid: i64,
+    bbox: Option<Bbox>,
+) -> Result<()> {
+    if let Some(rtree) = rtree {
+        if let Some(bbox) = bbox {
+            let sql = format!(r#"INSERT OR REPLACE INTO "{rtree}" VALUES (?, ?, ?, ?, ?)"#);
+            sqlx::query(&sql)
+                .bind(rowid)
+                .bind(bbox[0])
+                .bind(bbox[2])
+                .bind(bbox[1])
+                .bind(bbox[3])
+                .execute(&mut *conn)
+                .await?;
+        } else {
+            let sql = format!(r#"DELETE FROM "{rtree}" WHERE id = ?"#);
+            sqlx::query(&sql).bind(rowid).execute(&mut *conn).await?;
+        }
+    }
+    if let Some(bbox) = bbox {
+        sqlx::query(
+            "UPDATE gpkg_contents SET
+               min_x = MIN(COALESCE(min_x, ?), ?), min_y = MIN(COALESCE(min_y, ?), ?),
+               max_x = MAX(COALESCE(max_x, ?), ?), max_y = MAX(COALESCE(max_y, ?), ?)
+             WHERE table_name = ?",
+        )
+        .bind(bbox[0])
+        .bind(bbox[0])
+        .bind(bbox[1])
+        .bind(bbox[1])
+        .bind(bbox[2])
+        .bind(bbox[2])
+        .bind(bbox[3])
+        .bind(bbox[3])
+        .bind(&edit.table)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Bounding box (minx, miny, maxx, maxy)
+type Bbox = [f64; 4];
+
+/// Encode GeoJSON geometry as GeoPackage binary with bbox
+/// GeoJSON geometry in `srid` (CRS84 if not set), transformed into the layer SRS.
+/// Geometries of layers with undefined SRS are stored unchanged.
+fn storage_geometry(
+    geometry: Option<&serde_json::Value>,
+    srid: Option<i32>,
+    srs_id: i32,
+) -> Result<Option<serde_json::Value>> {
+    let Some(geometry) = geometry else {
+        return Ok(None);
+    };
+    let mut geometry = geometry.clone();
+    if srs_id > 0 && !geometry.is_null() {
+        if let Some(transform) = GeometryTransform::new(srid.unwrap_or(4326), srs_id)? {
+            transform.transform(&mut geometry)?;
+        }
+    }
+    Ok(Some(geometry))
+}
+
+pub(crate) fn gpkg_geometry(
+    geometry: Option<&serde_json::Value>,
+    srs_id: i32,
+) -> Result<(Option<Vec<u8>>, Option<Bbox>)> {
+    let Some(geometry) = geometry.filter(|geom| !geom.is_null()) else {
+        return Ok((None, None));
+    };
+    let bbox = geojson_bbox(geometry);
+    let envelope = bbox
+        .map(|bbox| vec![bbox[0], bbox[2], bbox[1], bbox[3]])
+        .unwrap_or_default();
+    let wkb = geojson::GeoJson(&geometry.to_string())
+        .to_gpkg_wkb(CoordDimensions::xy(), Some(srs_id), envelope)
+        .map_err(|e| Error::InvalidFeature(e.to_string()))?;
+    Ok((Some(wkb), bbox))
+}
+
+pub(crate) fn geojson_bbox(geometry: &serde_json::Value) -> Option<Bbox> {
+    fn extend(coords: &serde_json::Value, bbox: &mut Option<Bbox>) {
+        let Some(coords) = coords.as_array() else {
+            return;
+        };
+        if let [x, y, ..] = coords.as_slice() {
+            if let (Some(x), Some(y)) = (x.as_f64(), y.as_f64()) {
+                let b = bbox.get_or_insert([x, y, x, y]);
+                *b = [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)];
+                return;
+            }
+        }
+        for c in coords {
+            extend(c, bbox);
+        }
+    }
+    let mut bbox = None;
+    if let Some(geometries) = geometry.get("geometries").and_then(|g| g.as_array()) {
+        for geom in geometries {
+            if let Some(coords) = geom.get("coordinates") {
+                extend(coords, &mut bbox);
+            }
+        }
+    } else if let Some(coords) = geometry.get("coordinates") {
+        extend(coords, &mut bbox);
+    }
+    bbox
+}
+
+fn push_json_value(builder: &mut QueryBuilder<Sqlite>, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Null => builder.push_bind(None::<String>),
+        serde_json::Value::Bool(v) => builder.push_bind(*v),
+        serde_json::Value::Number(v) => {
+            if let Some(v) = v.as_i64() {
+                builder.push_bind(v)
+            } else {
+                builder.push_bind(v.as_f64())
+            }
+        }
+        serde_json::Value::String(v) => builder.push_bind(v.clone()),
+        v => builder.push_bind(v.to_string()),
+    };
+}
+
+/// Entity tag from feature content
+fn feature_etag(feature: &CoreFeature) -> String {
+    let mut hasher = DefaultHasher::new();
+    feature.geometry.to_string().hash(&mut hasher);
+    if let Some(properties) = &feature.properties {
+        properties.to_string().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Start next condition of WHERE clause
+fn push_condition(builder: &mut QueryBuilder<Sqlite>, where_term: &mut bool) {
+    builder.push(if *where_term { " AND " } else { " WHERE " });
+    *where_term = true;
+}
+
+/// Push row-level access condition with bound `username`
+fn push_row_filter(builder: &mut QueryBuilder<Sqlite>, row_filter: &str, username: Option<String>) {
+    builder.push("(");
+    let mut parts = row_filter.split("{username}");
+    builder.push(parts.next().unwrap_or_default());
+    for part in parts {
+        builder.push_bind(username.clone());
+        builder.push(part);
+    }
+    builder.push(")");
+}
+
+/// Compare ISO 8601 date/time column with timestamp
+fn push_temporal(
+    builder: &mut QueryBuilder<Sqlite>,
+    column: &str,
+    op: &str,
+    dt: DateTime<FixedOffset>,
+) {
+    builder.push(format!(r#"julianday("{column}") {op} julianday("#));
+    builder.push_bind(dt.to_rfc3339());
+    builder.push(")");
+}
+
+/// Push CQL2 SQL fragments and bind values into query
+fn push_sql_parts(builder: &mut QueryBuilder<Sqlite>, parts: Vec<SqlPart>) {
+    for part in parts {
+        match part {
+            SqlPart::Sql(sql) => builder.push(sql),
+            SqlPart::Bind(SqlValue::Text(v)) => builder.push_bind(v),
+            SqlPart::Bind(SqlValue::Int(v)) => builder.push_bind(v),
+            SqlPart::Bind(SqlValue::Float(v)) => builder.push_bind(v),
+            SqlPart::Bind(SqlValue::Bool(v)) => builder.push_bind(v),
+            SqlPart::Bind(SqlValue::Timestamp(v)) => builder.push_bind(v.to_rfc3339()),
+            SqlPart::Bind(SqlValue::Date(v)) => builder.push_bind(v.to_string()),
+        };
+    }
+}
+
+fn row_to_feature(row: &SqliteRow, table_info: &GpkgCollectionSource) -> Result<CoreFeature> {
+    let mut id = None;
+    let mut properties = json!({});
+    for col in row.columns() {
+        #[allow(clippy::if_same_then_else)]
+        if col.name() == table_info.geometry_column {
+            // Skip geometry
+        } else if col.name() == "__total_cnt" {
+            // Skip count
+        } else if col.name() == table_info.pk_column.as_ref().unwrap_or(&"".to_string()) {
+            // Get id as String
+            id = match col.type_info().name() {
+                "TEXT" => Some(row.try_get::<String, _>(col.ordinal())?),
+                "INTEGER" => Some(row.try_get::<i64, _>(col.ordinal())?.to_string()),
+                _ => None,
+            }
+        } else {
+            // Expression columns have no declared type
+            let value_type = row.try_get_raw(col.ordinal())?.type_info().into_owned();
+            let type_info = if col.type_info().is_null() {
+                &value_type
+            } else {
+                col.type_info()
+            };
+            properties[col.name()] = match type_info.name() {
+                "TEXT" => json!(row.try_get::<Option<&str>, _>(col.ordinal())?),
+                "INTEGER" => json!(row.try_get::<Option<i64>, _>(col.ordinal())?),
+                "REAL" => json!(row.try_get::<Option<f64>, _>(col.ordinal())?),
+                "DATE" | "DATETIME" => json!(row.try_get::<Option<&str>, _>(col.ordinal())?),
+                "NULL" => serde_json::Value::Null,
+                ty => json!(format!("<{ty}>")),
+            }
+        }
+    }
+    let geometry = if row.try_column(table_info.geometry_column.as_str()).is_ok() {
+        let wkb: wkb::Decode<geojson::GeoJsonString> =
+            row.try_get(table_info.geometry_column.as_str())?;
+        match wkb.geometry {
+            Some(geom) => {
+                serde_json::from_str(&geom.0).map_err(|_| error::Error::GeometryFormatError)?
+            }
+            None => serde_json::Value::Null,
+        }
+    } else {
+        // skipGeometry
+        serde_json::Value::Null
+    };
+
+    let item = CoreFeature {
+        type_: "Feature".to_string(),
+        id,
+        geometry,
+        properties: Some(properties),
+        links: vec![],
+        foreign_members: serde_json::Map::new(),
+    };
+
+    Ok(item)
+}
+
+/// Result column names of `sql`
+async fn query_columns(ds: &SqliteDatasource, sql: &str) -> Result<Vec<String>> {
+    let stmt = ds.pool.acquire().await?.prepare(sql).await?;
+    Ok(stmt
+        .columns()
+        .iter()
+        .map(|col| col.name().to_string())
+        .collect())
+}
+
+async fn detect_pk(ds: &SqliteDatasource, table: &str) -> Result<Option<String>> {
+    let sql = r#"
+        SELECT
+          (SELECT COUNT(*) FROM pragma_table_info(?) ti WHERE ti.pk > 0) as pksize,
+          (SELECT ti.name FROM pragma_table_info(?) ti WHERE ti.pk = 1) as pk
+    "#;
+    let row = sqlx::query(sql)
+        .bind(table)
+        .bind(table)
+        .fetch_one(&ds.pool)
+        .await?;
+    let pksize: u16 = row.try_get("pksize")?;
+    let pk_column: Option<String> = if pksize == 1 {
+        row.try_get("pk")?
+    } else {
+        None
+    };
+    Ok(pk_column)
+}
+
+/// Detect geometry column and its SRS
+async fn detect_geometry(ds: &SqliteDatasource, table: &str) -> Result<(String, i32)> {
+    let sql = r#"
+        SELECT column_name, geometry_type_name, srs_id
+        FROM gpkg_geometry_columns
+        WHERE table_name = ?
+    "#;
+    let row = sqlx::query(sql)
+        .bind(table)
+        // We take the first result only
+        .fetch_one(&ds.pool)
+        .await?;
+    let geometry_column: String = row.try_get("column_name")?;
+    let _geometry_type_name: String = row.try_get("geometry_type_name")?;
+    let srs_id: i32 = row.try_get("srs_id")?;
+    Ok((geometry_column, srs_id))
+}
+
+/// SRS of first geometry in query
+async fn detect_srs(
+    ds: &SqliteDatasource,
+    sql: &str,
+    geometry_column: &str,
+) -> Result<Option<i32>> {
+    let sql = format!(
+        "WITH query AS ({sql}) SELECT {geometry_column} FROM query WHERE {geometry_column} IS NOT NULL LIMIT 1"
+    );
+    let blob: Option<Vec<u8>> = sqlx::query_scalar(&sql).fetch_optional(&ds.pool).await?;
+    Ok(blob.as_deref().and_then(gpkg_srs_id))
+}
+
+/// SRS id from GeoPackage binary header
+fn gpkg_srs_id(blob: &[u8]) -> Option<i32> {
+    let [b'G', b'P', _version, flags, s0, s1, s2, s3, ..] = *blob else {
+        return None;
+    };
+    let srs_id = if flags & 1 == 1 {
+        i32::from_le_bytes([s0, s1, s2, s3])
+    } else {
+        i32::from_be_bytes([s0, s1, s2, s3])
+    };
+    Some(srs_id)
+}
+
+async fn detect_rtree(
+    ds: &SqliteDatasource,
+    table: &str,
+    geometry_column: &str,
+) -> Result<Option<String>> {
+    let rtree = format!("rtree_{table}_{geometry_column}");
+    let sql = "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?";
+    let name = sqlx::query_scalar(sql)
+        .bind(&rtree)
+        .fetch_optional(&ds.pool)
+        .await?;
+    Ok(name)
+}
+
+/// Create FTS5 index table `fts_<collection>` of `columns` in the search database
+///
+/// The index is rebuilt from the table content, since changes of other applications
+/// are not tracked.
+async fn create_search_index(
+    ds: &SqliteDatasource,
+    collection_id: &str,
+    table: &str,
+    columns: Vec<String>,
+) -> Result<GpkgSearchIndex> {
+    let fts = format!(r#""fts_{}""#, collection_id.replace('"', "\"\""));
+    let search_db = ds.search_db_path();
+    info!("Building search index {fts} in `{}`", search_db.display());
+    let fts_columns = columns
+        .iter()
+        .map(|col| format!(r#""{col}""#))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let conn_options = SqliteConnectOptions::new()
+        .filename(&search_db)
+        .create_if_missing(true)
+        .busy_timeout(Duration::from_secs(10));
+    let mut conn = SqliteConnection::connect_with(&conn_options).await?;
+    let gpkg = ds.filename().to_string_lossy().replace('\'', "''");
+    conn.execute(format!("ATTACH DATABASE '{gpkg}' AS gpkg").as_str())
+        .await?;
+    let mut tx = conn.begin().await?;
+    for sql in [
+        format!(r#"DROP TABLE IF EXISTS {fts}"#),
+        format!(r#"CREATE VIRTUAL TABLE {fts} USING fts5({fts_columns})"#),
+        format!(
+            r#"INSERT INTO {fts}(rowid, {fts_columns}) SELECT rowid, {fts_columns} FROM gpkg."{table}""#
+        ),
+    ] {
+        sqlx::query(&sql).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    conn.close().await?;
+    ds.set_search_db(search_db);
+    Ok(GpkgSearchIndex {
+        table: fts,
+        content: table.to_string(),
+        columns,
+    })
+}
+
+/// Remove (`delete`) or add the current values of a feature in the search index
+async fn update_search_index(
+    conn: &mut SqliteConnection,
+    search: Option<&GpkgSearchIndex>,
+    rowid: i64,
+    delete: bool,
+) -> Result<()> {
+    let Some(search) = search else {
+        return Ok(());
+    };
+    let (fts, content) = (&search.table, &search.content);
+    let columns = search
+        .columns
+        .iter()
+        .map(|col| format!(r#""{col}""#))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = if delete {
+        format!(r#"DELETE FROM {SEARCH_SCHEMA}.{fts} WHERE rowid = ?"#)
+    } else {
+        format!(
+            r#"INSERT INTO {SEARCH_SCHEMA}.{fts}(rowid, {columns}) SELECT rowid, {columns} FROM main."{content}" WHERE rowid = ?"#
+        )
+    };
+    sqlx::query(&sql).bind(rowid).execute(&mut *conn).await?;
+    Ok(())
+}
+
+/// FTS5 query of alternative search terms with prefix matching
+fn fts_query(alternatives: &[Vec<String>]) -> String {
+    alternatives
+        .iter()
+        .map(|terms| {
+            let terms: Vec<String> = terms.iter().map(|term| format!(r#""{term}"*"#)).collect();
+            format!("({})", terms.join(" AND "))
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Schema property of declared GeoPackage column type like `TEXT(50)`
+fn gpkg_schema_property(type_decl: &str) -> SchemaProperty {
+    let type_decl = type_decl.to_uppercase();
+    let (type_name, max_length) = match type_decl.split_once('(') {
+        Some((type_name, len)) => (type_name.trim(), len.trim_end_matches(')').parse().ok()),
+        None => (type_decl.as_str(), None),
+    };
+    match type_name {
+        "INTEGER" | "INT" | "MEDIUMINT" | "SMALLINT" | "TINYINT" => {
+            schema_property(Some("integer"), None)
+        }
+        "REAL" | "DOUBLE" | "FLOAT" | "NUMERIC" => schema_property(Some("number"), None),
+        "BOOLEAN" => schema_property(Some("boolean"), None),
+        "TEXT" => SchemaProperty {
+            max_length,
+            ..schema_property(Some("string"), None)
+        },
+        "DATE" => schema_property(Some("string"), Some("date")),
+        "DATETIME" => schema_property(Some("string"), Some("date-time")),
+        "GEOMETRY" | "POINT" | "LINESTRING" | "POLYGON" | "MULTIPOINT" | "MULTILINESTRING"
+        | "MULTIPOLYGON" | "GEOMETRYCOLLECTION" => geometry_schema_property(Some(type_name), false),
+        _ => schema_property(None, None),
+    }
+}
+
+/// Queryable types of columns `cols` or of all columns with a supported type
+async fn get_column_info(
+    ds: &SqliteDatasource,
+    sql: &str,
+    cols: Option<&[String]>,
+) -> Result<HashMap<String, QueryableType>> {
+    let mut columns = HashMap::new();
+    if matches!(cols, Some(cols) if cols.is_empty()) {
+        return Ok(columns);
+    }
+    let stmt = ds.pool.acquire().await?.prepare(sql).await?;
+    for col in stmt.columns() {
+        let colname = col.name().to_string();
+        if let Some(cols) = cols {
+            if !cols.contains(&colname) {
+                continue;
+            }
+        }
+        let queryable_type = match col.type_info().name() {
+            "TEXT" => QueryableType::String,
+            "INTEGER" => QueryableType::Integer,
+            "REAL" | "NUMERIC" => QueryableType::Number,
+            "BOOLEAN" => QueryableType::Bool,
+            "DATE" | "DATETIME" => QueryableType::Datetime,
+            _ if cols.is_none() => continue,
+            ty => {
+                return Err(Error::DatasourceSetupError(format!(
+                    "{colname} has a type {ty} which is not currently handled and can't be used a queryable"
+                )))
+            }
+        };
+        columns.insert(colname, queryable_type);
+    }
+    Ok(columns)
+}
+
+async fn check_query(ds: &SqliteDatasource, sql: String) -> Result<String> {
+    debug!("Collection query: {sql}");
+    if let Err(e) = ds.pool.acquire().await?.prepare(&sql).await {
+        error!("Error in collection query `{sql}`: {e}");
+        return Err(e.into());
+    }
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AccessCfg, SqlParameterCfg, SqlParameterType};
+
+    #[tokio::test]
+    async fn gpkg_content() {
+        let mut pool = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let collections = pool.collections("").await.unwrap();
+        assert_eq!(collections.len(), 3);
+        assert_eq!(
+            collections
+                .iter()
+                .map(|col| col.collection.id.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                "ne_10m_rivers_lake_centerlines",
+                "ne_10m_lakes",
+                "ne_10m_populated_places"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn gpkg_crs() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let collections = ds.collections("").await.unwrap();
+        let lakes = collections
+            .iter()
+            .find(|fc| fc.collection.id == "ne_10m_lakes")
+            .unwrap();
+        assert_eq!(
+            lakes.collection.storage_crs.as_deref(),
+            Some("http://www.opengis.net/def/crs/OGC/1.3/CRS84")
+        );
+        assert_eq!(
+            lakes.collection.crs[0],
+            "http://www.opengis.net/def/crs/OGC/1.3/CRS84"
+        );
+        let srs_id = detect_srs(&ds, "SELECT * FROM ne_10m_lakes", "geom")
+            .await
+            .unwrap();
+        assert_eq!(srs_id, Some(4326));
+        // Without `projtransform`, only the storage CRS is supported
+        #[cfg(not(feature = "projtransform"))]
+        {
+            assert_eq!(
+                lakes.collection.crs,
+                vec!["http://www.opengis.net/def/crs/OGC/1.3/CRS84"]
+            );
+            let filter = FilterParams {
+                bbox: Some("2600000,1200000,2700000,1300000".to_string()),
+                bbox_crs: Some("http://www.opengis.net/def/crs/EPSG/0/2056".to_string()),
+                ..Default::default()
+            };
+            assert!(matches!(
+                lakes.source.items(&filter).await,
+                Err(Error::UnsupportedCrs(_))
+            ));
+        }
+        #[cfg(not(feature = "projtransform"))]
+        assert!(matches!(
+            lakes
+                .source
+                .item(
+                    "",
+                    "ne_10m_lakes",
+                    "1",
+                    &FilterParams {
+                        crs: Some("http://www.opengis.net/def/crs/EPSG/0/2056".to_string()),
+                        ..Default::default()
+                    }
+                )
+                .await,
+            Err(Error::UnsupportedCrs(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn gpkg_field_config() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let field = |name: &str| FieldCfg {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                sql: Some("SELECT fid, geom, scalerank, labelrank, featurecla, name, scalerank / 3.0 AS ratio FROM ne_10m_populated_places".to_string()),
+                geometry_field: Some("geom".to_string()),
+                fid_field: Some("fid".to_string()),
+                include_fields: vec![
+                    "name".to_string(),
+                    "ratio".to_string(),
+                    "featurecla".to_string(),
+                    "scalerank".to_string(),
+                ],
+                exclude_fields: vec!["featurecla".to_string()],
+                fields: vec![
+                    FieldCfg {
+                        alias: Some("city".to_string()),
+                        title: Some("City".to_string()),
+                        description: Some("Name of populated place".to_string()),
+                        ..field("name")
+                    },
+                    FieldCfg {
+                        decimals: Some(2),
+                        ..field("ratio")
+                    },
+                    FieldCfg {
+                        alias: Some("rank".to_string()),
+                        decimals: Some(0),
+                        ..field("scalerank")
+                    },
+                ],
+                queryable_fields: vec!["city".to_string()],
+                ..Default::default()
+            }),
+            name: "places".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        let props = items.features[0].properties.as_ref().unwrap();
+        let keys: Vec<&String> = props.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["city", "ratio", "rank"]);
+        assert!(props["rank"].is_i64());
+        let ratio = props["ratio"].as_f64().unwrap();
+        assert_eq!((ratio * 100.0).round() / 100.0, ratio);
+
+        let city = props["city"].as_str().unwrap().to_string();
+        let filter = FilterParams {
+            filters: HashMap::from([("city".to_string(), city.clone())]),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert!(items
+            .features
+            .iter()
+            .all(|f| f.properties.as_ref().unwrap()["city"] == city.as_str()));
+
+        let queryables = fc.source.queryables("places").await.unwrap().unwrap();
+        let city = &queryables.properties["city"];
+        assert_eq!(city.title.as_deref(), Some("City"));
+        assert_eq!(city.description.as_deref(), Some("Name of populated place"));
+
+        let schema = fc.source.schema("places").await.unwrap().unwrap();
+        let names: Vec<&str> = schema
+            .properties
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["city", "ratio", "rank", "fid", "geom"]);
+
+        // Transactions can't write into aliased fields
+        let CollectionSourceCfg::Gpkg(ref srccfg) = coll_cfg.source else {
+            unreachable!();
+        };
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_populated_places".to_string()),
+                fields: srccfg.fields[..1].to_vec(),
+                transactions: true,
+                ..Default::default()
+            }),
+            ..coll_cfg
+        };
+        assert!(ds.setup_collection(&coll_cfg, "", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn gpkg_row_filter() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_populated_places".to_string()),
+                ..Default::default()
+            }),
+            name: "places".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: Some(AccessCfg {
+                row_filter: Some("name = {username}".to_string()),
+                ..Default::default()
+            }),
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.features.len(), 0);
+
+        let all = populated_places(&mut ds).await;
+        let items = all.items(&FilterParams::default()).await.unwrap();
+        let place = &items.features[0];
+        let name = place.properties.as_ref().unwrap()["name"].as_str().unwrap();
+        let filter = FilterParams {
+            username: Some(name.to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert!(!items.features.is_empty());
+        assert!(items
+            .features
+            .iter()
+            .all(|f| f.properties.as_ref().unwrap()["name"] == name));
+
+        let id = place.id.as_ref().unwrap().to_string();
+        assert!(fc
+            .source
+            .item("", "places", &id, &filter)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(fc
+            .source
+            .item("", "places", &id, &FilterParams::default())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn gpkg_schema() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = populated_places(&mut ds).await;
+        let schema = source
+            .schema("ne_10m_populated_places")
+            .await
+            .unwrap()
+            .unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(schema["$id"], "/collections/ne_10m_populated_places/schema");
+        assert_eq!(
+            schema["properties"]["fid"],
+            json!({"type": "integer", "readOnly": true, "x-ogc-role": "id"})
+        );
+        assert_eq!(
+            schema["properties"]["geom"],
+            json!({"format": "geometry-point", "x-ogc-role": "primary-geometry"})
+        );
+        assert_eq!(schema["properties"]["name"]["type"], "string");
+        assert_eq!(schema["properties"]["scalerank"]["type"], "integer");
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("fid")));
+    }
+
+    #[tokio::test]
+    async fn gpkg_features() {
+        let filter = FilterParams::default();
+        let ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = GpkgCollectionSource {
+            ds,
+            sql: "SELECT * FROM ne_10m_lakes".to_string(),
+            parameters: SqlParameters::default(),
+            geometry_column: "geom".to_string(),
+            srs_id: Some(4326),
+            pk_column: Some("fid".to_string()),
+            temporal_column: None,
+            temporal_end_column: None,
+            columns: Vec::new(),
+            fields: None,
+            row_filter: None,
+            other_columns: HashMap::new(),
+            sort_columns: HashMap::new(),
+            table: None,
+            rtree: None,
+            search_index: None,
+            edit_table: None,
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), filter.limit_or_default() as usize);
+    }
+
+    async fn populated_places(ds: &mut SqliteDatasource) -> Box<dyn CollectionSource> {
+        let collections = ds.collections("").await.unwrap();
+        collections
+            .into_iter()
+            .find(|fc| fc.collection.id == "ne_10m_populated_places")
+            .unwrap()
+            .source
+    }
+
+    #[tokio::test]
+    async fn gpkg_bbox_filter() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = populated_places(&mut ds).await;
+        let filter = FilterParams {
+            bbox: Some("5.9,45.8,10.5,47.8".to_string()),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert!(items.number_matched.unwrap() > 0);
+        assert!(items.features.iter().all(|f| {
+            let coords = &f.geometry["coordinates"];
+            (5.9..=10.5).contains(&coords[0].as_f64().unwrap())
+                && (45.8..=47.8).contains(&coords[1].as_f64().unwrap())
+        }));
+        assert!(items
+            .features
+            .iter()
+            .any(|f| f.properties.as_ref().unwrap()["name"] == "Bern"));
+
+        let filter = FilterParams {
+            bbox: Some("-10,-10,-9,-9".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(source.items(&filter).await.unwrap().number_matched, Some(0));
+
+        // Spatial CQL2 operators with the R-tree index
+        let bbox_matched = items.number_matched;
+        let filter = FilterParams {
+            filter: Some("S_INTERSECTS(geom, BBOX(5.9,45.8,10.5,47.8))".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            source.items(&filter).await.unwrap().number_matched,
+            bbox_matched
+        );
+        let filter = FilterParams {
+            filter: Some(
+                "S_WITHIN(geom, POLYGON((5.9 45.8, 10.5 45.8, 10.5 47.8, 5.9 47.8, 5.9 45.8)))"
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            source.items(&filter).await.unwrap().number_matched,
+            bbox_matched
+        );
+        let filter = FilterParams {
+            filter: Some("S_TOUCHES(geom, POINT(7.44 46.95))".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            source.items(&filter).await,
+            Err(Error::FilterError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn gpkg_attribute_filter() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = populated_places(&mut ds).await;
+        let queryables = source.queryables("").await.unwrap().unwrap();
+        assert!(queryables.properties.contains_key("name"));
+        assert!(queryables.properties.contains_key("scalerank"));
+        assert!(!queryables.properties.contains_key("geom"));
+
+        let filter = FilterParams {
+            filters: HashMap::from([("name".to_string(), "Bern".to_string())]),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+
+        let filter = FilterParams {
+            filters: HashMap::from([("name".to_string(), "Be*".to_string())]),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert!(items.number_matched.unwrap() >= 1);
+
+        let filter = FilterParams {
+            filters: HashMap::from([("scalerank".to_string(), "x".to_string())]),
+            ..Default::default()
+        };
+        assert!(source.items(&filter).await.is_err());
+
+        let filter = FilterParams {
+            filters: HashMap::from([("unknown".to_string(), "1".to_string())]),
+            ..Default::default()
+        };
+        assert!(source.items(&filter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn gpkg_sortby() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = populated_places(&mut ds).await;
+        let sortables = source.sortables("").await.unwrap().unwrap();
+        assert!(sortables.properties.contains_key("scalerank"));
+        assert!(!sortables.properties.contains_key("geom"));
+
+        let filter = FilterParams {
+            sortby: Some("-scalerank,name".to_string()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        let keys: Vec<(i64, String)> = items
+            .features
+            .iter()
+            .map(|f| {
+                let props = f.properties.as_ref().unwrap();
+                (
+                    props["scalerank"].as_i64().unwrap(),
+                    props["name"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect();
+        assert!(keys
+            .windows(2)
+            .all(|w| w[0].0 > w[1].0 || (w[0].0 == w[1].0 && w[0].1 <= w[1].1)));
+
+        // Stable pages with primary key tiebreaker
+        let filter = FilterParams {
+            sortby: Some("scalerank".to_string()),
+            limit: Some(5),
+            ..Default::default()
+        };
+        let page1 = source.items(&filter).await.unwrap();
+        let page2 = source.items(&filter.with_offset(5)).await.unwrap();
+        assert!(page1
+            .features
+            .iter()
+            .all(|f| !page2.features.iter().any(|f2| f2.id == f.id)));
+
+        let filter = FilterParams {
+            sortby: Some("geom".to_string()),
+            ..Default::default()
+        };
+        assert!(source.items(&filter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn gpkg_property_selection() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = populated_places(&mut ds).await;
+        let filter = FilterParams {
+            properties: Some("name,unknown".to_string()),
+            skip_geometry: Some(true),
+            limit: Some(5),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 5);
+        for feature in &items.features {
+            assert!(feature.id.is_some());
+            assert!(feature.geometry.is_null());
+            let props = feature.properties.as_ref().unwrap().as_object().unwrap();
+            assert_eq!(props.keys().collect::<Vec<_>>(), vec!["name"]);
+        }
+
+        let filter = FilterParams {
+            properties: Some("scalerank".to_string()),
+            ..Default::default()
+        };
+        let id = items.features[0].id.as_ref().unwrap();
+        let item = source
+            .item("", "places", id, &filter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.geometry["type"], "Point");
+        let props = item.properties.as_ref().unwrap().as_object().unwrap();
+        assert_eq!(props.keys().collect::<Vec<_>>(), vec!["scalerank"]);
+
+        let filter = FilterParams {
+            properties: Some("".to_string()),
+            skip_geometry: Some(true),
+            ..Default::default()
+        };
+        let item = source
+            .item("", "places", id, &filter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.id.as_ref(), Some(id));
+        assert_eq!(item.properties, Some(json!({})));
+    }
+
+    #[tokio::test]
+    async fn gpkg_datetime_filter() {
+        let path = std::env::temp_dir().join("bbox_datetime_test.gpkg");
+        std::fs::copy("../assets/ne_extracts.gpkg", &path).unwrap();
+        let mut ds = SqliteDatasource::new_pool(path.to_str().unwrap())
+            .await
+            .unwrap();
+        ds.enable_writes().await.unwrap();
+        let write_pool = ds.write_pool.get().unwrap();
+        for sql in [
+            "ALTER TABLE ne_10m_lakes ADD COLUMN survey_date DATETIME",
+            "UPDATE ne_10m_lakes SET survey_date = '2020-03-01T12:00:00Z'",
+            "UPDATE ne_10m_lakes SET survey_date = '2024-05-01T08:00:00Z' WHERE name = 'Lake Zurich'",
+        ] {
+            sqlx::query(sql).execute(write_pool).await.unwrap();
+        }
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_lakes".to_string()),
+                temporal_field: Some("survey_date".to_string()),
+                ..Default::default()
+            }),
+            name: "lakes".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+        let extent = fc.collection.extent.as_ref().unwrap();
+        assert_eq!(
+            extent.temporal.as_ref().unwrap().interval,
+            vec![vec![
+                Some("2020-03-01T12:00:00Z".to_string()),
+                Some("2024-05-01T08:00:00Z".to_string())
+            ]]
+        );
+        assert_eq!(
+            extent.spatial.as_ref().unwrap().bbox,
+            vec![vec![-180.0, -90.0, 180.0, 90.0]]
+        );
+        let filter = FilterParams {
+            datetime: Some("2024-01-01T00:00:00Z/..".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        assert_eq!(
+            items.features[0].properties.as_ref().unwrap()["name"],
+            "Lake Zurich"
+        );
+
+        let filter = FilterParams {
+            datetime: Some("../2023-06-01T00:00:00+02:00".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert!(items.number_matched.unwrap() > 1);
+
+        let filter = FilterParams {
+            datetime: Some("2020-03-01T12:00:00Z".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert!(items.number_matched.unwrap() > 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn gpkg_cql2_filter() {
+        let ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let source = GpkgCollectionSource {
+            ds,
+            sql: "SELECT * FROM ne_10m_lakes".to_string(),
+            parameters: SqlParameters::default(),
+            geometry_column: "geom".to_string(),
+            srs_id: Some(4326),
+            pk_column: Some("fid".to_string()),
+            temporal_column: None,
+            temporal_end_column: None,
+            columns: Vec::new(),
+            fields: None,
+            row_filter: None,
+            other_columns: HashMap::from([
+                ("scalerank".to_string(), QueryableType::Integer),
+                ("name".to_string(), QueryableType::String),
+            ]),
+            sort_columns: HashMap::new(),
+            table: None,
+            rtree: None,
+            search_index: None,
+            edit_table: None,
+        };
+        let filter = FilterParams {
+            filter: Some("scalerank = 0 AND name IS NOT NULL".to_string()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        let items = source.items(&filter).await.unwrap();
+        assert!(items.number_returned > 0);
+        assert!(items
+            .features
+            .iter()
+            .all(|f| f.properties.as_ref().unwrap()["scalerank"] == 0));
+
+        let filter = FilterParams {
+            filter: Some(r#"{"op":"in","args":[{"property":"scalerank"},[0,1]]}"#.to_string()),
+            filter_lang: Some("cql2-json".to_string()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        let items_in = source.items(&filter).await.unwrap();
+        assert!(items_in.number_returned > items.number_returned);
+
+        // Non-queryable column
+        let filter = FilterParams {
+            filter: Some("featurecla = 'Lake'".to_string()),
+            ..Default::default()
+        };
+        assert!(source.items(&filter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn gpkg_transactions() {
+        let path = std::env::temp_dir().join("bbox_transactions_test.gpkg");
+        std::fs::copy("../assets/ne_extracts.gpkg", &path).unwrap();
+        let ds = SqliteDatasource::new_pool(path.to_str().unwrap())
+            .await
+            .unwrap();
+        ds.enable_writes().await.unwrap();
+        let source = GpkgCollectionSource {
+            ds,
+            sql: "SELECT * FROM ne_10m_populated_places".to_string(),
+            parameters: SqlParameters::default(),
+            geometry_column: "geom".to_string(),
+            srs_id: Some(4326),
+            pk_column: Some("fid".to_string()),
+            temporal_column: None,
+            temporal_end_column: None,
+            columns: Vec::new(),
+            fields: None,
+            row_filter: None,
+            other_columns: HashMap::new(),
+            sort_columns: HashMap::new(),
+            table: Some("ne_10m_populated_places".to_string()),
+            rtree: Some("rtree_ne_10m_populated_places_geom".to_string()),
+            search_index: None,
+            edit_table: Some(GpkgEditTable {
+                table: "ne_10m_populated_places".to_string(),
+                srs_id: 4326,
+                columns: vec!["scalerank".to_string(), "name".to_string()],
+            }),
+        };
+        let feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [7.45, 46.95]},
+            "properties": {"name": "Bern", "scalerank": 3}
+        }))
+        .unwrap();
+        let id = source.create_item(&feature).await.unwrap();
+        let item = source
+            .item("", "test", &id, &FilterParams::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.properties.as_ref().unwrap()["name"], "Bern");
+        assert_eq!(item.geometry["coordinates"], json!([7.45, 46.95]));
+
+        // R-tree index is maintained
+        let sql = format!("SELECT minx FROM rtree_ne_10m_populated_places_geom WHERE id = {id}");
+        let minx: f64 = sqlx::query_scalar(&sql)
+            .fetch_one(&source.ds.pool)
+            .await
+            .unwrap();
+        assert!((minx - 7.45).abs() < 1e-5);
+
+        // Update with entity tag
+        let etag = source.item_etag(&id).await.unwrap().unwrap();
+        let patch = EditFeature::from_geojson(&json!({"properties": {"scalerank": 2}})).unwrap();
+        source
+            .update_item(&id, &patch, Some(&etag), None)
+            .await
+            .unwrap();
+        let item = source
+            .item("", "test", &id, &FilterParams::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.properties.as_ref().unwrap()["scalerank"], 2);
+        assert_eq!(item.properties.as_ref().unwrap()["name"], "Bern");
+        assert!(matches!(
+            source.update_item(&id, &patch, Some(&etag), None).await,
+            Err(Error::PreconditionFailed)
+        ));
+
+        // Replace resets missing properties
+        let feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": null,
+            "properties": {"name": "Bärn"}
+        }))
+        .unwrap();
+        source
+            .replace_item(&id, &feature, None, None)
+            .await
+            .unwrap();
+        let item = source
+            .item("", "test", &id, &FilterParams::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.properties.as_ref().unwrap()["name"], "Bärn");
+        assert!(item.properties.as_ref().unwrap()["scalerank"].is_null());
+        assert!(item.geometry.is_null());
+
+        // Geometries in other CRS are transformed into the layer SRS
+        let mut feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [828217.0, 5933917.0]},
+            "properties": {"name": "Bern"}
+        }))
+        .unwrap();
+        feature.srid = Some(3857);
+        if GeometryTransform::SUPPORTED {
+            let id = source.create_item(&feature).await.unwrap();
+            let item = source
+                .item("", "test", &id, &FilterParams::default())
+                .await
+                .unwrap()
+                .unwrap();
+            let coords = &item.geometry["coordinates"];
+            assert!((coords[0].as_f64().unwrap() - 7.44).abs() < 1e-5);
+            assert!((coords[1].as_f64().unwrap() - 46.95).abs() < 1e-5);
+            source.delete_item(&id, None, None).await.unwrap();
+        } else {
+            assert!(matches!(
+                source.create_item(&feature).await,
+                Err(Error::UnsupportedCrs(_))
+            ));
+        }
+
+        let feature = EditFeature::from_geojson(&json!({"properties": {"foo": 1}})).unwrap();
+        assert!(matches!(
+            source.create_item(&feature).await,
+            Err(Error::InvalidFeature(_))
+        ));
+
+        source.delete_item(&id, None, None).await.unwrap();
+        assert!(source
+            .item("", "test", &id, &FilterParams::default())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            source.delete_item(&id, None, None).await,
+            Err(Error::FeatureNotFound)
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn gpkg_row_filter_writes() {
+        let path = std::env::temp_dir().join("bbox_row_filter_test.gpkg");
+        std::fs::copy("../assets/ne_extracts.gpkg", &path).unwrap();
+        let ds = SqliteDatasource::new_pool(path.to_str().unwrap())
+            .await
+            .unwrap();
+        ds.enable_writes().await.unwrap();
+        let source = GpkgCollectionSource {
+            ds,
+            sql: "SELECT * FROM ne_10m_populated_places".to_string(),
+            parameters: SqlParameters::default(),
+            geometry_column: "geom".to_string(),
+            srs_id: Some(4326),
+            pk_column: Some("fid".to_string()),
+            temporal_column: None,
+            temporal_end_column: None,
+            columns: Vec::new(),
+            fields: None,
+            row_filter: Some("name = {username}".to_string()),
+            other_columns: HashMap::new(),
+            sort_columns: HashMap::new(),
+            table: Some("ne_10m_populated_places".to_string()),
+            rtree: Some("rtree_ne_10m_populated_places_geom".to_string()),
+            search_index: None,
+            edit_table: Some(GpkgEditTable {
+                table: "ne_10m_populated_places".to_string(),
+                srs_id: 4326,
+                columns: vec!["scalerank".to_string(), "name".to_string()],
+            }),
+        };
+        let feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [7.45, 46.95]},
+            "properties": {"name": "Bern", "scalerank": 3}
+        }))
+        .unwrap();
+        let id = source.create_item(&feature).await.unwrap();
+
+        // Rows of other users are not found
+        let patch = EditFeature::from_geojson(&json!({"properties": {"scalerank": 2}})).unwrap();
+        assert!(matches!(
+            source.update_item(&id, &patch, None, Some("Zurich")).await,
+            Err(Error::FeatureNotFound)
+        ));
+        assert!(matches!(
+            source.replace_item(&id, &feature, None, None).await,
+            Err(Error::FeatureNotFound)
+        ));
+        assert!(matches!(
+            source.delete_item(&id, None, Some("Zurich")).await,
+            Err(Error::FeatureNotFound)
+        ));
+
+        let owner = FilterParams {
+            username: Some("Bern".to_string()),
+            ..Default::default()
+        };
+        let item = source.item("", "test", &id, &owner).await.unwrap().unwrap();
+        assert_eq!(item.properties.as_ref().unwrap()["scalerank"], 3);
+        source
+            .update_item(&id, &patch, None, Some("Bern"))
+            .await
+            .unwrap();
+        source.delete_item(&id, None, Some("Bern")).await.unwrap();
+        assert!(source
+            .item("", "test", &id, &owner)
+            .await
+            .unwrap()
+            .is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn gpkg_text_search() {
+        let path = std::env::temp_dir().join("bbox_search_test.gpkg");
+        std::fs::copy("../assets/ne_extracts.gpkg", &path).unwrap();
+        let mut ds = SqliteDatasource::new_pool(path.to_str().unwrap())
+            .await
+            .unwrap();
+        // Connections opened before setting up the search index attach it when acquired
+        ds.enable_writes().await.unwrap();
+        sqlx::query("SELECT 1")
+            .execute(ds.write_pool.get().unwrap())
+            .await
+            .unwrap();
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_populated_places".to_string()),
+                search_fields: vec!["name".to_string()],
+                transactions: true,
+                ..Default::default()
+            }),
+            name: "places".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+        assert!(fc.source.text_search());
+
+        let search = |q: &str| FilterParams {
+            q: Some(q.to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&search("Bern")).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        assert_eq!(items.features[0].id, Some("1".to_string()));
+        let items = fc.source.items(&search("plac 11")).await.unwrap();
+        assert_eq!(items.number_matched, Some(11));
+        let items = fc.source.items(&search("bern, plac 11")).await.unwrap();
+        assert_eq!(items.number_matched, Some(12));
+        assert!(fc
+            .source
+            .items(&search("-"))
+            .await
+            .unwrap()
+            .features
+            .is_empty());
+
+        // Index is maintained by transactions
+        let feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": null,
+            "properties": {"name": "Bernina"}
+        }))
+        .unwrap();
+        let id = fc.source.create_item(&feature).await.unwrap();
+        let items = fc.source.items(&search("bern")).await.unwrap();
+        assert_eq!(items.number_matched, Some(2));
+        let patch = EditFeature::from_geojson(&json!({"properties": {"name": "Maloja"}})).unwrap();
+        fc.source
+            .update_item(&id, &patch, None, None)
+            .await
+            .unwrap();
+        let items = fc.source.items(&search("bern")).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        fc.source.delete_item(&id, None, None).await.unwrap();
+        let items = fc.source.items(&search("maloja")).await.unwrap();
+        assert_eq!(items.number_matched, Some(0));
+
+        // The GeoPackage contains no index tables
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE name LIKE 'fts_%'")
+                .fetch_all(&ds.pool)
+                .await
+                .unwrap();
+        assert!(tables.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn gpkg_text_search_read_only() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_populated_places".to_string()),
+                search_fields: vec!["name".to_string()],
+                ..Default::default()
+            }),
+            name: "places".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+        assert!(ds.write_pool.get().is_none());
+
+        // Collections on the same table have their own index
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                table_name: Some("ne_10m_populated_places".to_string()),
+                search_fields: vec!["featurecla".to_string()],
+                ..Default::default()
+            }),
+            name: "places_by_class".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc_class = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+
+        let filter = FilterParams {
+            q: Some("bern".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        let items = fc_class.source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(0));
+
+        // Search databases are not shared between datasources
+        let other = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let path = ds.search_db_path();
+        assert!(path.exists());
+        assert_ne!(other.search_db_path(), path);
+    }
+
+    #[tokio::test]
+    async fn gpkg_sql_parameters() {
+        let mut ds = SqliteDatasource::new_pool("../assets/ne_extracts.gpkg")
+            .await
+            .unwrap();
+        let coll_cfg = ConfiguredCollectionCfg {
+            source: CollectionSourceCfg::Gpkg(GpkgCollectionCfg {
+                sql: Some("SELECT fid, geom, name, scalerank FROM ne_10m_populated_places WHERE scalerank <= :maxrank AND (:name IS NULL OR name = :name)".to_string()),
+                parameters: vec![
+                    SqlParameterCfg {
+                        name: "maxrank".to_string(),
+                        type_: SqlParameterType::Integer,
+                        default: Some("2".to_string()),
+                        ..Default::default()
+                    },
+                    SqlParameterCfg {
+                        name: "name".to_string(),
+                        description: Some("Name of populated place".to_string()),
+                        ..Default::default()
+                    },
+                ],
+                geometry_field: Some("geom".to_string()),
+                fid_field: Some("fid".to_string()),
+                exclude_fields: vec!["scalerank".to_string()],
+                ..Default::default()
+            }),
+            name: "places".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+        };
+        let fc = ds.setup_collection(&coll_cfg, "", None).await.unwrap();
+
+        let params = |params: &[(&str, &str)]| FilterParams {
+            filters: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let items = fc.source.items(&params(&[])).await.unwrap();
+        assert_eq!(items.number_matched, Some(56));
+        let items = fc.source.items(&params(&[("maxrank", "0")])).await.unwrap();
+        assert_eq!(items.number_matched, Some(19));
+        let items = fc
+            .source
+            .items(&params(&[("maxrank", "10"), ("name", "Bern")]))
+            .await
+            .unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        assert_eq!(items.features[0].id, Some("1".to_string()));
+        let items = fc
+            .source
+            .items(&params(&[("name", "Bern' OR 1=1 --")]))
+            .await
+            .unwrap();
+        assert_eq!(items.number_matched, Some(0));
+        assert!(fc
+            .source
+            .item("", "places", "1", &params(&[("name", "Bern")]))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(matches!(
+            fc.source.check_parameters(&params(&[("maxrank", "x")])),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let queryables = fc.source.queryables("places").await.unwrap().unwrap();
+        assert!(matches!(
+            queryables.properties["maxrank"].type_,
+            Some(QueryableType::Integer)
+        ));
+        assert_eq!(
+            queryables.properties["name"].description.as_deref(),
+            Some("Name of populated place")
+        );
     }
 }
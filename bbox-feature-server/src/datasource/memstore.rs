@@ -0,0 +1,955 @@
+//! In-memory feature source for Shapefiles, GeoJSON files and static STAC catalogs.
+//!
+//! Shapefiles are converted to GeoJSON when loaded.
+//! STAC catalogs are directories with a `collection.json` and item files.
+
+use crate::config::{CollectionSourceCfg, ConfiguredCollectionCfg, StacCollectionCfg};
+use crate::datasource::gpkg::geojson_bbox;
+use crate::datasource::stac::{apply_stac_collection, stac_datetime_matches, stac_item_links};
+use crate::datasource::{
+    blocking, column_queryables, datasource_error, filter_bbox, geometry_transform, item_links,
+    not_queryable, selected_columns, source_collection, spatial_extent, CollectionSource,
+    FeatureSender, GeometryTransform, ItemsResult,
+};
+use crate::error::{Error, Result};
+use crate::filter_params::FilterParams;
+use crate::inventory::FeatureCollection;
+use async_trait::async_trait;
+use bbox_core::config::app_dir;
+use bbox_core::file_search;
+use bbox_core::ogcapi::*;
+use futures::SinkExt;
+use log::{debug, error, info, warn};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileFormat {
+    GeoJson,
+    Shapefile,
+    Stac,
+}
+
+type IndexEntry = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/// File content with spatial index
+#[derive(Debug)]
+struct MemStore {
+    /// Modification time and size of the loaded file (latest and total for directories)
+    version: Option<(SystemTime, u64)>,
+    features: Vec<MemFeature>,
+    index: RTree<IndexEntry>,
+    /// Property columns in file order
+    columns: Vec<String>,
+    column_types: HashMap<String, QueryableType>,
+}
+
+#[derive(Debug)]
+struct MemFeature {
+    /// Item id of STAC features
+    id: Option<String>,
+    properties: serde_json::Map<String, Value>,
+    geometry: Value,
+    /// Other members of STAC features
+    foreign_members: serde_json::Map<String, Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MemCollectionSource {
+    path: PathBuf,
+    format: FileFormat,
+    srid: Option<i32>,
+    /// Property used as feature id (Default: feature number)
+    fid_column: Option<String>,
+    /// Queryable columns (Default: all)
+    queryable_fields: Vec<String>,
+    collection_id: String,
+    /// API URL of the collection
+    collection_href: String,
+    store: Arc<RwLock<Arc<MemStore>>>,
+    /// Held while reloading the file
+    reload: Arc<Mutex<()>>,
+}
+
+/// Collection of an explicit `[[collection]]` configuration
+pub fn setup_collection(
+    cfg: &ConfiguredCollectionCfg,
+    base_url: &str,
+) -> Result<FeatureCollection> {
+    info!("Setup file Collection `{}`", &cfg.name);
+    let (srccfg, format) = match cfg.source {
+        CollectionSourceCfg::GeoJson(ref srccfg) => (srccfg, FileFormat::GeoJson),
+        CollectionSourceCfg::Shapefile(ref srccfg) => (srccfg, FileFormat::Shapefile),
+        CollectionSourceCfg::Stac(ref srccfg) => {
+            return setup_stac_collection(cfg, srccfg, base_url)
+        }
+        _ => panic!(),
+    };
+    let path = app_dir(&srccfg.path);
+    let srid = srccfg.srid.or_else(|| default_srid(&path, format));
+    let mut source = MemCollectionSource::open(&path, format, srid)?;
+    source.fid_column = srccfg.fid_field.clone();
+    source.queryable_fields = srccfg.queryable_fields.clone();
+    Ok(source.into_collection(
+        &cfg.name,
+        cfg.title.clone(),
+        cfg.description.clone(),
+        &srccfg.crs,
+        base_url,
+    ))
+}
+
+/// Collection of a static STAC catalog directory
+fn setup_stac_collection(
+    cfg: &ConfiguredCollectionCfg,
+    srccfg: &StacCollectionCfg,
+    base_url: &str,
+) -> Result<FeatureCollection> {
+    let path = app_dir(&srccfg.path);
+    let content = match File::open(path.join("collection.json")) {
+        Ok(file) => match serde_json::from_reader(BufReader::new(file))? {
+            Value::Object(content) => content,
+            _ => {
+                return Err(Error::DatasourceSetupError(format!(
+                    "Collection `{}`: invalid collection.json",
+                    cfg.name
+                )))
+            }
+        },
+        Err(_) => {
+            warn!("Collection `{}`: collection.json not found", cfg.name);
+            serde_json::Map::new()
+        }
+    };
+    let source = MemCollectionSource::open(&path, FileFormat::Stac, Some(4326))?;
+    let mut fc = source.into_collection(
+        &cfg.name,
+        cfg.title.clone(),
+        cfg.description.clone(),
+        &[],
+        base_url,
+    );
+    apply_stac_collection(&mut fc.collection, content);
+    Ok(fc)
+}
+
+/// Collection of a file found by directory autoscan
+pub fn file_collection(path: &Path, base_url: &str) -> Result<FeatureCollection> {
+    let format = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("shp") => FileFormat::Shapefile,
+        _ => FileFormat::GeoJson,
+    };
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let source = MemCollectionSource::open(path, format, default_srid(path, format))?;
+    Ok(source.into_collection(&id, Some(id.clone()), None, &[], base_url))
+}
+
+/// GeoJSON and STAC are always WGS84. Shapefiles are detected as WGS84 from the `.prj` file only.
+fn default_srid(path: &Path, format: FileFormat) -> Option<i32> {
+    match format {
+        FileFormat::GeoJson | FileFormat::Stac => Some(4326),
+        FileFormat::Shapefile => {
+            let prj = std::fs::read_to_string(path.with_extension("prj")).ok()?;
+            if prj.starts_with("GEOGCS[") && prj.contains("WGS_1984") {
+                Some(4326)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl MemCollectionSource {
+    fn open(path: &Path, format: FileFormat, srid: Option<i32>) -> Result<Self> {
+        let store = MemStore::load(path, format)?;
+        Ok(MemCollectionSource {
+            path: path.to_path_buf(),
+            format,
+            srid,
+            fid_column: None,
+            queryable_fields: Vec::new(),
+            collection_id: String::new(),
+            collection_href: String::new(),
+            store: Arc::new(RwLock::new(Arc::new(store))),
+            reload: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn into_collection(
+        mut self,
+        id: &str,
+        title: Option<String>,
+        description: Option<String>,
+        additional_crs: &[i32],
+        base_url: &str,
+    ) -> FeatureCollection {
+        let store = self.store.read().unwrap().clone();
+        let extent = store.extent(self.srid);
+        let (srid, queryables) = (self.srid, !self.other_columns(&store).is_empty());
+        self.collection_id = id.to_string();
+        self.collection_href = format!("{base_url}/collections/{id}");
+        source_collection(
+            Box::new(self),
+            id,
+            title,
+            description,
+            extent,
+            srid,
+            additional_crs,
+            queryables,
+            base_url,
+        )
+    }
+
+    /// Current file content, reloaded when the file has changed.
+    /// Concurrent requests wait for a single reload.
+    async fn store(&self) -> Result<Arc<MemStore>> {
+        let path = self.path.clone();
+        let version = blocking(move || Ok(file_version(&path))).await?;
+        let store = self.store.read().unwrap().clone();
+        if version.is_none() {
+            warn!(
+                "Serving cached content of missing `{}`",
+                self.path.display()
+            );
+            return Ok(store);
+        }
+        if version == store.version {
+            return Ok(store);
+        }
+        let _reload = self.reload.lock().await;
+        let store = self.store.read().unwrap().clone();
+        if version == store.version {
+            // Reloaded by a concurrent request
+            return Ok(store);
+        }
+        info!("Reloading changed `{}`", self.path.display());
+        let (path, format) = (self.path.clone(), self.format);
+        let store = Arc::new(blocking(move || MemStore::load(&path, format)).await?);
+        *self.store.write().unwrap() = store.clone();
+        Ok(store)
+    }
+
+    /// Queryable columns
+    fn other_columns(&self, store: &MemStore) -> HashMap<String, QueryableType> {
+        store
+            .column_types
+            .iter()
+            .filter(|(col, _)| {
+                self.queryable_fields.is_empty() || self.queryable_fields.contains(col)
+            })
+            .map(|(col, type_)| (col.clone(), type_.clone()))
+            .collect()
+    }
+
+    fn to_feature(
+        &self,
+        store: &MemStore,
+        idx: usize,
+        filter: &FilterParams,
+        transform: &Option<GeometryTransform>,
+    ) -> Result<CoreFeature> {
+        let feature = &store.features[idx];
+        let mut properties = feature.properties.clone();
+        let id = match &self.fid_column {
+            Some(col) => properties.remove(col).map(|id| value_string(&id)),
+            None => feature.id.clone().or_else(|| Some(idx.to_string())),
+        };
+        let mut foreign_members = feature.foreign_members.clone();
+        let mut links = vec![];
+        if self.format == FileFormat::Stac {
+            links = stac_item_links(&self.collection_href, id.as_deref().unwrap_or_default());
+            foreign_members.insert("collection".to_string(), json!(self.collection_id));
+        }
+        if let Some(selected) = selected_columns(filter, &store.columns) {
+            properties.retain(|key, _| selected.contains(&key.as_str()));
+        }
+        let geometry = if filter.skip_geometry() {
+            Value::Null
+        } else {
+            let mut geometry = feature.geometry.clone();
+            if let Some(transform) = transform {
+                transform.transform(&mut geometry)?;
+            }
+            geometry
+        };
+        Ok(CoreFeature {
+            type_: "Feature".to_string(),
+            geometry,
+            properties: Some(json!(properties)),
+            id,
+            links,
+            foreign_members,
+        })
+    }
+}
+
+impl MemStore {
+    fn load(path: &Path, format: FileFormat) -> Result<Self> {
+        info!("Loading `{}`", path.display());
+        let version = file_version(path);
+        let mut json: Value = match format {
+            FileFormat::GeoJson => {
+                let file = BufReader::new(File::open(path).map_err(datasource_error)?);
+                serde_json::from_reader(file)?
+            }
+            #[cfg(feature = "shapefile")]
+            FileFormat::Shapefile => {
+                let dbase_reader = shapefile::dbase::Reader::from_path(path.with_extension("dbf"))
+                    .map_err(datasource_error)?;
+                // Records are unordered, properties are added in field order
+                let fields: Vec<String> = dbase_reader
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().to_string())
+                    .collect();
+                let shape_reader =
+                    shapefile::ShapeReader::from_path(path).map_err(datasource_error)?;
+                let mut reader = shapefile::Reader::new(shape_reader, dbase_reader);
+                let mut features = Vec::new();
+                for shape_record in reader.iter_shapes_and_records() {
+                    let (shape, mut record) = shape_record.map_err(datasource_error)?;
+                    let properties: serde_json::Map<String, Value> = fields
+                        .iter()
+                        .map(|name| {
+                            let value = record.remove(name).map(shp::field_value);
+                            (name.clone(), value.unwrap_or(Value::Null))
+                        })
+                        .collect();
+                    features.push(json!({
+                        "type": "Feature",
+                        "geometry": shp::shape_geometry(shape)?,
+                        "properties": properties,
+                    }));
+                }
+                json!({"type": "FeatureCollection", "features": features})
+            }
+            #[cfg(not(feature = "shapefile"))]
+            FileFormat::Shapefile => {
+                return Err(Error::DatasourceError(
+                    "Shapefile support not enabled".to_string(),
+                ))
+            }
+            FileFormat::Stac => {
+                let mut items = Vec::new();
+                for path in stac_files(path) {
+                    let file = BufReader::new(File::open(&path).map_err(datasource_error)?);
+                    let item: Value = serde_json::from_reader(file)?;
+                    // Skip catalogs, collections and other JSON files
+                    if item["type"] == "Feature" {
+                        items.push(item);
+                    }
+                }
+                json!({"type": "FeatureCollection", "features": items})
+            }
+        };
+        let features = match json["type"].as_str() {
+            Some("FeatureCollection") => json["features"].take(),
+            Some("Feature") => Value::Array(vec![json]),
+            _ => {
+                return Err(Error::DatasourceError(
+                    "GeoJSON FeatureCollection expected".to_string(),
+                ))
+            }
+        };
+        let Value::Array(features) = features else {
+            return Err(Error::DatasourceError("features missing".to_string()));
+        };
+        let mut store = MemStore {
+            version,
+            features: Vec::with_capacity(features.len()),
+            index: RTree::new(),
+            columns: Vec::new(),
+            column_types: HashMap::new(),
+        };
+        let mut entries = Vec::with_capacity(features.len());
+        for (idx, mut feature) in features.into_iter().enumerate() {
+            let geometry = feature["geometry"].take();
+            let Value::Object(properties) = feature["properties"].take() else {
+                return Err(Error::DatasourceError("properties missing".to_string()));
+            };
+            let (id, foreign_members) = match feature {
+                Value::Object(mut item) if format == FileFormat::Stac => {
+                    item.remove("type");
+                    // Links of catalog files are replaced with API links
+                    item.remove("links");
+                    (item.remove("id").map(|id| value_string(&id)), item)
+                }
+                _ => (None, serde_json::Map::new()),
+            };
+            for (key, value) in &properties {
+                store.add_column(key, value);
+            }
+            if let Some(b) = geojson_bbox(&geometry) {
+                entries.push(GeomWithData::new(
+                    Rectangle::from_corners([b[0], b[1]], [b[2], b[3]]),
+                    idx,
+                ));
+            }
+            store.features.push(MemFeature {
+                id,
+                properties,
+                geometry,
+                foreign_members,
+            });
+        }
+        store.index = RTree::bulk_load(entries);
+        debug!("Loaded {} features", store.features.len());
+        Ok(store)
+    }
+
+    /// Register column with type of first non-null value
+    fn add_column(&mut self, key: &str, value: &Value) {
+        if !self.column_types.contains_key(key) {
+            if !self.columns.iter().any(|col| col == key) {
+                self.columns.push(key.to_string());
+            }
+            let queryable_type = match value {
+                Value::Bool(_) => QueryableType::Bool,
+                Value::Number(n) if n.is_f64() => QueryableType::Number,
+                Value::Number(_) => QueryableType::Integer,
+                Value::String(_) => QueryableType::String,
+                _ => return,
+            };
+            self.column_types.insert(key.to_string(), queryable_type);
+        }
+    }
+
+    fn extent(&self, srid: Option<i32>) -> Option<CoreExtent> {
+        if self.index.size() == 0 {
+            return None;
+        }
+        let envelope = self.index.root().envelope();
+        let (lower, upper) = (envelope.lower(), envelope.upper());
+        let spatial = spatial_extent([lower[0], lower[1], upper[0], upper[1]], srid)?;
+        Some(CoreExtent {
+            spatial: Some(spatial),
+            temporal: None,
+        })
+    }
+}
+
+#[async_trait]
+impl CollectionSource for MemCollectionSource {
+    async fn send_items(
+        &self,
+        filter: &FilterParams,
+        mut sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        if filter.filter.is_some() {
+            error!("CQL2 filters are not supported for file collections");
+            return Err(Error::QueryParams);
+        }
+        let store = self.store().await?;
+        let others = filter.other_params().map_err(|e| {
+            error!("{e}");
+            Error::QueryParams
+        })?;
+        let other_columns = self.other_columns(&store);
+        for key in others.keys() {
+            if !other_columns.contains_key(key) {
+                return Err(not_queryable(key));
+            }
+        }
+        let temporal = if self.format == FileFormat::Stac {
+            filter.temporal().map_err(|e| {
+                error!("{e}");
+                Error::QueryParams
+            })?
+        } else {
+            None
+        };
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        let candidates: Vec<usize> = match filter_bbox(filter, self.srid)? {
+            Some(bbox) => {
+                let envelope = AABB::from_corners([bbox[0], bbox[1]], [bbox[2], bbox[3]]);
+                let mut candidates: Vec<usize> = store
+                    .index
+                    .locate_in_envelope_intersecting(&envelope)
+                    .map(|entry| entry.data)
+                    .collect();
+                candidates.sort_unstable();
+                candidates
+            }
+            None => (0..store.features.len()).collect(),
+        };
+        let limit = filter.limit_or_default() as u64;
+        let offset = filter.offset.unwrap_or(0) as u64;
+        let mut number_matched = 0;
+        let mut number_returned = 0;
+        for idx in candidates {
+            let properties = &store.features[idx].properties;
+            if others
+                .iter()
+                .any(|(key, val)| properties.get(key).map(value_string).as_ref() != Some(val))
+            {
+                continue;
+            }
+            if let Some(temporal) = &temporal {
+                if !stac_datetime_matches(properties, temporal) {
+                    continue;
+                }
+            }
+            number_matched += 1;
+            if number_matched <= offset || (limit > 0 && number_returned == limit) {
+                continue;
+            }
+            let feature = self.to_feature(&store, idx, filter, &transform)?;
+            if sender.send(feature).await.is_err() {
+                debug!("Items receiver closed");
+                return Err(Error::ItemsStreamClosed);
+            }
+            number_returned += 1;
+        }
+        Ok(ItemsResult {
+            features: Vec::new(),
+            number_matched: Some(number_matched),
+            number_returned,
+            next_cursor: None,
+        })
+    }
+
+    async fn item(
+        &self,
+        base_url: &str,
+        collection_id: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        let store = self.store().await?;
+        let idx = match &self.fid_column {
+            Some(col) => store.features.iter().position(|feature| {
+                feature.properties.get(col).map(value_string).as_deref() == Some(feature_id)
+            }),
+            None if self.format == FileFormat::Stac => store
+                .features
+                .iter()
+                .position(|feature| feature.id.as_deref() == Some(feature_id)),
+            None => feature_id
+                .parse::<usize>()
+                .ok()
+                .filter(|idx| *idx < store.features.len()),
+        };
+        let Some(idx) = idx else {
+            return Ok(None);
+        };
+        let transform = geometry_transform(self.srid, filter.crs_srid()?)?;
+        let mut item = self.to_feature(&store, idx, filter, &transform)?;
+        item.links = item_links(base_url, collection_id, feature_id);
+        Ok(Some(item))
+    }
+
+    async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        let store = self.store().await?;
+        Ok(Some(column_queryables(
+            collection_id,
+            &self.other_columns(&store),
+        )))
+    }
+}
+
+fn file_version(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.is_dir() {
+        // Latest modification time and total size of catalog files
+        let versions: Vec<_> = stac_files(path)
+            .iter()
+            .filter_map(|path| file_version(path))
+            .collect();
+        let modified = versions.iter().map(|(modified, _)| *modified).max()?;
+        let len = versions.iter().map(|(_, len)| len).sum::<u64>() + versions.len() as u64;
+        return Some((modified, len));
+    }
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// JSON files of a STAC catalog directory in path order
+fn stac_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = file_search::search(path, "*.json");
+    files.sort();
+    files
+}
+
+/// Value as used in query parameters
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// Conversion of Shapefile records to GeoJSON
+#[cfg(feature = "shapefile")]
+mod shp {
+    use crate::error::{Error, Result};
+    use serde_json::{json, Value};
+    use shapefile::dbase::FieldValue;
+    use shapefile::record::traits::HasXY;
+    use shapefile::{PolygonRing, Shape};
+
+    pub fn shape_geometry(shape: Shape) -> Result<Value> {
+        let geometry = match shape {
+            Shape::NullShape => Value::Null,
+            Shape::Point(p) => point(&p),
+            Shape::PointM(p) => point(&p),
+            Shape::PointZ(p) => point(&p),
+            Shape::Polyline(l) => linestrings(l.parts()),
+            Shape::PolylineM(l) => linestrings(l.parts()),
+            Shape::PolylineZ(l) => linestrings(l.parts()),
+            Shape::Polygon(p) => polygons(p.rings()),
+            Shape::PolygonM(p) => polygons(p.rings()),
+            Shape::PolygonZ(p) => polygons(p.rings()),
+            Shape::Multipoint(p) => multipoint(p.points()),
+            Shape::MultipointM(p) => multipoint(p.points()),
+            Shape::MultipointZ(p) => multipoint(p.points()),
+            Shape::Multipatch(_) => {
+                return Err(Error::DatasourceError(
+                    "Multipatch shapes are not supported".to_string(),
+                ))
+            }
+        };
+        Ok(geometry)
+    }
+
+    fn coords<P: HasXY>(points: &[P]) -> Vec<[f64; 2]> {
+        points.iter().map(|p| [p.x(), p.y()]).collect()
+    }
+
+    fn point<P: HasXY>(p: &P) -> Value {
+        json!({"type": "Point", "coordinates": [p.x(), p.y()]})
+    }
+
+    fn multipoint<P: HasXY>(points: &[P]) -> Value {
+        json!({"type": "MultiPoint", "coordinates": coords(points)})
+    }
+
+    fn linestrings<P: HasXY>(parts: &[Vec<P>]) -> Value {
+        match parts {
+            [part] => json!({"type": "LineString", "coordinates": coords(part)}),
+            parts => {
+                let lines: Vec<_> = parts.iter().map(|part| coords(part)).collect();
+                json!({"type": "MultiLineString", "coordinates": lines})
+            }
+        }
+    }
+
+    /// Group rings into polygons, each starting with an outer ring
+    fn polygons<P: HasXY>(rings: &[PolygonRing<P>]) -> Value {
+        let mut polygons: Vec<Vec<Vec<[f64; 2]>>> = Vec::new();
+        for ring in rings {
+            match ring {
+                PolygonRing::Inner(points) if !polygons.is_empty() => {
+                    if let Some(polygon) = polygons.last_mut() {
+                        polygon.push(coords(points));
+                    }
+                }
+                PolygonRing::Outer(points) | PolygonRing::Inner(points) => {
+                    polygons.push(vec![coords(points)])
+                }
+            }
+        }
+        if polygons.len() == 1 {
+            json!({"type": "Polygon", "coordinates": polygons[0]})
+        } else {
+            json!({"type": "MultiPolygon", "coordinates": polygons})
+        }
+    }
+
+    pub fn field_value(value: FieldValue) -> Value {
+        match value {
+            FieldValue::Character(s) => json!(s),
+            FieldValue::Memo(s) => json!(s),
+            FieldValue::Numeric(Some(n)) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                json!(n as i64)
+            }
+            FieldValue::Numeric(n) => json!(n),
+            FieldValue::Float(n) => json!(n),
+            FieldValue::Double(n) | FieldValue::Currency(n) => json!(n),
+            FieldValue::Integer(n) => json!(n),
+            FieldValue::Logical(b) => json!(b),
+            FieldValue::Date(d) => {
+                json!(d.map(|d| format!("{:04}-{:02}-{:02}", d.year(), d.month(), d.day())))
+            }
+            FieldValue::DateTime(dt) => {
+                let (d, t) = (dt.date(), dt.time());
+                json!(format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                    d.year(),
+                    d.month(),
+                    d.day(),
+                    t.hours(),
+                    t.minutes(),
+                    t.seconds()
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::test_places;
+    use crate::filter_params::crs_uri;
+
+    fn write_geojson(path: &Path, places: &[(&str, i64, f64, f64)]) {
+        let features: Vec<Value> = places
+            .iter()
+            .map(|(name, scalerank, x, y)| {
+                json!({
+                    "type": "Feature",
+                    "properties": {"name": name, "scalerank": scalerank},
+                    "geometry": {"type": "Point", "coordinates": [x, y]}
+                })
+            })
+            .collect();
+        let fc = json!({"type": "FeatureCollection", "features": features});
+        std::fs::write(path, fc.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn geojson_features() {
+        let path = std::env::temp_dir().join(format!("bbox-test-{}.geojson", std::process::id()));
+        let mut places = test_places();
+        write_geojson(&path, &places);
+
+        let fc = file_collection(&path, "").unwrap();
+        assert_eq!(fc.collection.storage_crs, Some(crs_uri(4326)));
+        assert_eq!(
+            fc.collection
+                .extent
+                .as_ref()
+                .unwrap()
+                .spatial
+                .as_ref()
+                .unwrap()
+                .bbox,
+            vec![vec![6.14, 46.20, 8.54, 47.37]]
+        );
+        let queryables = fc.source.queryables("places").await.unwrap().unwrap();
+        assert!(matches!(
+            queryables.properties["scalerank"].type_,
+            Some(QueryableType::Integer)
+        ));
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(3));
+        assert_eq!(items.features.len(), 3);
+        assert_eq!(items.features[1].id, Some("1".to_string()));
+
+        let filter = FilterParams {
+            bbox: Some("8,47,9,48".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.features.len(), 1);
+        assert_eq!(
+            items.features[0].properties,
+            Some(json!({"name": "Zürich", "scalerank": 1}))
+        );
+
+        let mut filter = FilterParams {
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        };
+        filter
+            .filters
+            .insert("scalerank".to_string(), "1".to_string());
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(2));
+        assert_eq!(items.features.len(), 1);
+        assert_eq!(items.features[0].id, Some("2".to_string()));
+
+        let item = fc
+            .source
+            .item("", "places", "2", &FilterParams::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            item.geometry,
+            json!({"type": "Point", "coordinates": [6.14, 46.2]})
+        );
+
+        // Changed file is reloaded
+        places.push(("Basel", 2, 7.59, 47.56));
+        write_geojson(&path, &places);
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(4));
+
+        // Concurrent requests share a single reload
+        let source = MemCollectionSource::open(&path, FileFormat::GeoJson, Some(4326)).unwrap();
+        places.push(("Lugano", 3, 8.95, 46.0));
+        write_geojson(&path, &places);
+        let (store1, store2) = futures::join!(source.store(), source.store());
+        let (store1, store2) = (store1.unwrap(), store2.unwrap());
+        assert!(Arc::ptr_eq(&store1, &store2));
+        assert_eq!(store1.features.len(), 5);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(feature = "shapefile")]
+    #[tokio::test]
+    async fn shapefile_features() {
+        use shapefile::dbase::{FieldValue, Record, TableWriterBuilder};
+
+        let path = std::env::temp_dir().join(format!("bbox-test-{}.shp", std::process::id()));
+        let table = TableWriterBuilder::new()
+            .add_character_field("name".try_into().unwrap(), 50)
+            .add_numeric_field("scalerank".try_into().unwrap(), 4, 0);
+        let mut writer = shapefile::Writer::from_path(&path, table).unwrap();
+        for (name, scalerank, x, y) in test_places() {
+            let mut record = Record::default();
+            record.insert(
+                "name".to_string(),
+                FieldValue::Character(Some(name.to_string())),
+            );
+            record.insert(
+                "scalerank".to_string(),
+                FieldValue::Numeric(Some(scalerank as f64)),
+            );
+            writer
+                .write_shape_and_record(&shapefile::Point::new(x, y), &record)
+                .unwrap();
+        }
+        drop(writer);
+
+        let fc = file_collection(&path, "").unwrap();
+        let queryables = fc.source.queryables("").await.unwrap().unwrap();
+        assert!(matches!(
+            queryables.properties["scalerank"].type_,
+            Some(QueryableType::Integer)
+        ));
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(3));
+        let properties = items.features[1].properties.as_ref().unwrap();
+        let keys: Vec<_> = properties.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["name", "scalerank"]);
+        assert_eq!(
+            items.features[1].properties,
+            Some(json!({"name": "Zürich", "scalerank": 1}))
+        );
+        assert_eq!(
+            items.features[1].geometry,
+            json!({"type": "Point", "coordinates": [8.54, 47.37]})
+        );
+
+        for ext in ["shp", "shx", "dbf"] {
+            std::fs::remove_file(path.with_extension(ext)).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn stac_catalog() {
+        let dir = std::env::temp_dir().join(format!("bbox-test-stac-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("items")).unwrap();
+        let collection = json!({
+            "type": "Collection",
+            "stac_version": "1.0.0",
+            "id": "scenes",
+            "description": "Satellite scenes",
+            "license": "CC-BY-4.0",
+            "extent": {
+                "spatial": {"bbox": [[6.0, 46.0, 9.0, 48.0]]},
+                "temporal": {"interval": [["2021-01-01T00:00:00Z", null]]}
+            },
+            "links": [{"rel": "self", "href": "./collection.json"}]
+        });
+        std::fs::write(dir.join("collection.json"), collection.to_string()).unwrap();
+        for (id, datetime, x) in [
+            ("scene-1", "2021-03-01T10:00:00Z", 7.0),
+            ("scene-2", "2021-09-01T10:00:00Z", 8.0),
+        ] {
+            let item = json!({
+                "type": "Feature",
+                "stac_version": "1.0.0",
+                "id": id,
+                "properties": {"datetime": datetime},
+                "geometry": {"type": "Point", "coordinates": [x, 47.0]},
+                "links": [{"rel": "parent", "href": "../collection.json"}],
+                "assets": {"visual": {"href": format!("https://example.com/{id}.tif")}}
+            });
+            std::fs::write(
+                dir.join("items").join(format!("{id}.json")),
+                item.to_string(),
+            )
+            .unwrap();
+        }
+
+        let cfg = ConfiguredCollectionCfg {
+            name: "scenes".to_string(),
+            title: None,
+            description: None,
+            extent: None,
+            access: None,
+            source: CollectionSourceCfg::Stac(StacCollectionCfg { path: dir.clone() }),
+        };
+        let fc = setup_collection(&cfg, "http://localhost/api").unwrap();
+        assert_eq!(
+            fc.collection.description,
+            Some("Satellite scenes".to_string())
+        );
+        assert_eq!(fc.collection.foreign_members["license"], json!("CC-BY-4.0"));
+        assert!(fc.collection.foreign_members.get("links").is_none());
+        assert_eq!(
+            fc.collection
+                .extent
+                .as_ref()
+                .unwrap()
+                .spatial
+                .as_ref()
+                .unwrap()
+                .bbox,
+            vec![vec![6.0, 46.0, 9.0, 48.0]]
+        );
+
+        let items = fc.source.items(&FilterParams::default()).await.unwrap();
+        assert_eq!(items.number_matched, Some(2));
+        assert_eq!(items.features[0].id, Some("scene-1".to_string()));
+        assert_eq!(
+            items.features[0].foreign_members["collection"],
+            json!("scenes")
+        );
+        assert!(items.features[0].foreign_members.contains_key("assets"));
+        assert_eq!(
+            items.features[0].links[0].href,
+            "http://localhost/api/collections/scenes/items/scene-1"
+        );
+
+        let filter = FilterParams {
+            datetime: Some("2021-06-01T00:00:00Z/..".to_string()),
+            ..Default::default()
+        };
+        let items = fc.source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        assert_eq!(items.features[0].id, Some("scene-2".to_string()));
+
+        let item = fc
+            .source
+            .item(
+                "http://localhost/api",
+                "scenes",
+                "scene-2",
+                &FilterParams::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.foreign_members["stac_version"], json!("1.0.0"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,21 +1,42 @@
 //! Feature source implementations.
 
-use crate::config::{CollectionSourceCfg, ConfiguredCollectionCfg};
+use crate::config::{
+    CollectionSourceCfg, ConfiguredCollectionCfg, ExtentCfg, FieldCfg, SqlParameterCfg,
+    SqlParameterType,
+};
+use crate::cql2::{SqlPart, SqlValue};
 use crate::error::{Error, Result};
-use crate::filter_params::FilterParams;
+use crate::filter_params::{crs_uri, FilterParams, SortKey};
 use crate::inventory::FeatureCollection;
 use async_trait::async_trait;
 use bbox_core::config::{DatasourceCfg, NamedDatasourceCfg};
-use bbox_core::ogcapi::{CoreExtent, CoreFeature, Queryables};
+use bbox_core::ogcapi::{
+    ApiLink, CoreCollection, CoreExtent, CoreExtentSpatial, CoreExtentTemporal, CoreFeature,
+    FeatureSchema, QueryableProperty, QueryableType, Queryables, SchemaProperty,
+};
 use bbox_core::NamedObjectStore;
+use chrono::{DateTime, NaiveDate};
 use dyn_clone::{clone_trait_object, DynClone};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use tokio::sync::broadcast;
 
+#[cfg(feature = "flatgeobuf")]
+pub mod fgb;
+#[cfg(feature = "geoparquet")]
+pub mod geoparquet;
 pub mod gpkg;
+pub mod memstore;
 pub mod postgis;
+pub mod stac;
 
 #[async_trait]
 pub trait CollectionDatasource {
+    /// Setup collection with known `extent`. Missing spatial or temporal extents are computed.
     async fn setup_collection(
         &mut self,
         cfg: &ConfiguredCollectionCfg,
@@ -31,18 +52,141 @@ pub trait AutoscanCollectionDatasource {
 
 #[async_trait]
 pub trait CollectionSource: DynClone + Sync + Send {
-    async fn items(&self, filter: &FilterParams) -> Result<ItemsResult>;
+    /// Send features matching `filter` to `sender`. Returned result contains no features.
+    async fn send_items(&self, filter: &FilterParams, sender: FeatureSender)
+        -> Result<ItemsResult>;
+    async fn items(&self, filter: &FilterParams) -> Result<ItemsResult> {
+        let (sender, receiver) = mpsc::channel(ITEMS_CHANNEL_SIZE);
+        let (result, features) = futures::join!(
+            self.send_items(filter, sender),
+            receiver.collect::<Vec<_>>()
+        );
+        let mut result = result?;
+        result.features = features;
+        Ok(result)
+    }
+    /// Single feature with CRS, properties and geometry selection of `filter`
     async fn item(
         &self,
         base_url: &str,
         collection_id: &str,
         feature_id: &str,
+        filter: &FilterParams,
     ) -> Result<Option<CoreFeature>>;
     async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>>;
+    /// Properties which can be used in `sortby`
+    async fn sortables(&self, _collection_id: &str) -> Result<Option<Queryables>> {
+        Ok(None)
+    }
+    /// JSON Schema of the feature properties
+    async fn schema(&self, _collection_id: &str) -> Result<Option<FeatureSchema>> {
+        Ok(None)
+    }
+    /// WKT definition of a coordinate reference system known by the datasource
+    async fn srs_definition(&self, _srid: i32) -> Result<Option<String>> {
+        Ok(None)
+    }
+    /// Whether items can be searched with the `q` parameter
+    fn text_search(&self) -> bool {
+        false
+    }
+    /// Validate request parameters bound to placeholders of the collection query
+    fn check_parameters(&self, _filter: &FilterParams) -> Result<()> {
+        Ok(())
+    }
+
+    // -- Transactions (OGC API Features Part 4) --
+    /// Whether the collection accepts create, replace, update and delete requests
+    fn transactions(&self) -> bool {
+        false
+    }
+    /// Entity tag of a feature for optimistic concurrency control
+    async fn item_etag(&self, _feature_id: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+    /// Insert a new feature and return its id
+    async fn create_item(&self, _feature: &EditFeature) -> Result<String> {
+        Err(Error::TransactionsNotSupported)
+    }
+    /// Replace an existing feature, which is readable by `username`
+    async fn replace_item(
+        &self,
+        _feature_id: &str,
+        _feature: &EditFeature,
+        _etag: Option<&str>,
+        _username: Option<&str>,
+    ) -> Result<()> {
+        Err(Error::TransactionsNotSupported)
+    }
+    /// Update geometry and properties contained in `feature`
+    async fn update_item(
+        &self,
+        _feature_id: &str,
+        _feature: &EditFeature,
+        _etag: Option<&str>,
+        _username: Option<&str>,
+    ) -> Result<()> {
+        Err(Error::TransactionsNotSupported)
+    }
+    async fn delete_item(
+        &self,
+        _feature_id: &str,
+        _etag: Option<&str>,
+        _username: Option<&str>,
+    ) -> Result<()> {
+        Err(Error::TransactionsNotSupported)
+    }
+
+    /// Subscribe to feature change notifications, None if not supported
+    fn changes(&self) -> Option<ChangeReceiver> {
+        None
+    }
+    /// Whether a deleted feature was readable with `filter` before deletion
+    async fn deleted_item_readable(
+        &self,
+        _change: &FeatureChange,
+        _filter: &FilterParams,
+    ) -> Result<bool> {
+        Ok(true)
+    }
 }
 
 clone_trait_object!(CollectionSource);
 
+/// Channel for streaming features of an items request
+pub type FeatureSender = mpsc::Sender<CoreFeature>;
+
+/// Number of features buffered between datasource and response
+pub const ITEMS_CHANNEL_SIZE: usize = 100;
+
+/// Operation of a feature change notification
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// Feature change notification
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct FeatureChange {
+    pub op: ChangeOp,
+    pub id: String,
+    /// WGS84 extent of a deleted feature, if known
+    #[serde(skip)]
+    pub bbox: Option<[f64; 4]>,
+    /// Column values of a deleted row, if sent for checking row filters
+    #[serde(skip)]
+    pub row: Option<serde_json::Value>,
+}
+
+/// Channel for feature change notifications
+pub type ChangeReceiver = broadcast::Receiver<FeatureChange>;
+
+/// Number of change notifications buffered for slow subscribers
+pub const CHANGES_CHANNEL_SIZE: usize = 256;
+
 /// Datasource connection pools
 #[derive(Default)]
 pub struct Datasources {
@@ -79,7 +223,23 @@ impl Datasources {
         collection: &ConfiguredCollectionCfg,
         base_url: &str,
     ) -> Result<FeatureCollection> {
-        match &collection.source {
+        let extent = collection.extent.as_ref().map(configured_extent);
+        if collection
+            .access
+            .as_ref()
+            .and_then(|access| access.row_filter.as_ref())
+            .is_some()
+            && !matches!(
+                collection.source,
+                CollectionSourceCfg::Postgis(_) | CollectionSourceCfg::Gpkg(_)
+            )
+        {
+            return Err(Error::DatasourceSetupError(format!(
+                "Collection `{}`: `row_filter` requires a PostGIS or GeoPackage source",
+                collection.name
+            )));
+        }
+        let mut fc = match &collection.source {
             CollectionSourceCfg::Postgis(cfg) => {
                 let source = self
                     .pg_datasources
@@ -90,7 +250,9 @@ impl Datasources {
                             .unwrap_or(&"(default)".to_string())
                             .clone(),
                     ))?;
-                source.setup_collection(collection, base_url, None).await
+                source
+                    .setup_collection(collection, base_url, extent.clone())
+                    .await
             }
             CollectionSourceCfg::Gpkg(ref cfg) => {
                 let source = self
@@ -102,15 +264,1096 @@ impl Datasources {
                             .unwrap_or(&"(default)".to_string())
                             .clone(),
                     ))?;
-                source.setup_collection(collection, base_url, None).await
+                source
+                    .setup_collection(collection, base_url, extent.clone())
+                    .await
             }
+            #[cfg(feature = "flatgeobuf")]
+            CollectionSourceCfg::Fgb(_) => fgb::setup_collection(collection, base_url),
+            #[cfg(not(feature = "flatgeobuf"))]
+            CollectionSourceCfg::Fgb(_) => Err(Error::DatasourceSetupError(format!(
+                "Collection `{}`: FlatGeobuf support not enabled",
+                collection.name
+            ))),
+            #[cfg(feature = "geoparquet")]
+            CollectionSourceCfg::GeoParquet(_) => {
+                geoparquet::setup_collection(collection, base_url)
+            }
+            #[cfg(not(feature = "geoparquet"))]
+            CollectionSourceCfg::GeoParquet(_) => Err(Error::DatasourceSetupError(format!(
+                "Collection `{}`: GeoParquet support not enabled",
+                collection.name
+            ))),
+            CollectionSourceCfg::GeoJson(_)
+            | CollectionSourceCfg::Shapefile(_)
+            | CollectionSourceCfg::Stac(_) => memstore::setup_collection(collection, base_url),
+            CollectionSourceCfg::Pgstac(cfg) => {
+                let source = self
+                    .pg_datasources
+                    .get_or_default_mut(cfg.datasource.as_deref())
+                    .ok_or(Error::DatasourceNotFound(
+                        cfg.datasource
+                            .as_ref()
+                            .unwrap_or(&"(default)".to_string())
+                            .clone(),
+                    ))?;
+                stac::setup_collection(source, collection, base_url).await
+            }
+        }?;
+        if let Some(extent) = extent {
+            // Configured extents take precedence
+            let computed = fc.collection.extent.take();
+            fc.collection.extent = Some(CoreExtent {
+                spatial: extent
+                    .spatial
+                    .or_else(|| computed.as_ref().and_then(|e| e.spatial.clone())),
+                temporal: extent
+                    .temporal
+                    .or_else(|| computed.and_then(|e| e.temporal)),
+            });
         }
+        Ok(fc)
+    }
+}
+
+fn configured_extent(cfg: &ExtentCfg) -> CoreExtent {
+    let open_end = |ts: &String| Some(ts.clone()).filter(|ts| !ts.is_empty() && ts != "..");
+    CoreExtent {
+        spatial: cfg.bbox.map(|bbox| CoreExtentSpatial {
+            bbox: vec![bbox.to_vec()],
+            crs: None,
+        }),
+        temporal: cfg
+            .interval
+            .as_ref()
+            .map(|[start, end]| temporal_extent(open_end(start), open_end(end))),
+    }
+}
+
+/// Nullable `minx, miny, maxx, maxy` query result
+pub(crate) type BboxRow = (Option<f64>, Option<f64>, Option<f64>, Option<f64>);
+
+/// Spatial extent in WGS84 of `bbox` in `srid`, None if not transformable
+pub(crate) fn spatial_extent(bbox: [f64; 4], srid: Option<i32>) -> Option<CoreExtentSpatial> {
+    let mut bbox = bbox;
+    if let Some(srid) = srid {
+        match GeometryTransform::new(srid, 4326) {
+            Ok(Some(transform)) => bbox = transform.transform_bbox(bbox).ok()?,
+            Ok(None) => {}
+            Err(_) => return None,
+        }
+    }
+    Some(CoreExtentSpatial {
+        bbox: vec![bbox.to_vec()],
+        crs: None,
+    })
+}
+
+/// Temporal extent, None for an open end
+pub(crate) fn temporal_extent(start: Option<String>, end: Option<String>) -> CoreExtentTemporal {
+    CoreExtentTemporal {
+        interval: vec![vec![start, end]],
+        trs: None,
     }
 }
 
 #[derive(Debug)]
 pub struct ItemsResult {
     pub features: Vec<CoreFeature>,
-    pub number_matched: u64,
+    pub number_matched: Option<u64>,
     pub number_returned: u64,
+    /// Token for the next page with keyset pagination
+    pub next_cursor: Option<String>,
+}
+
+/// GeoJSON feature of a transaction request
+#[derive(Debug, Default)]
+pub struct EditFeature {
+    /// GeoJSON geometry. `None` if not included, `Some(Value::Null)` for an empty geometry.
+    pub geometry: Option<serde_json::Value>,
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    /// EPSG code of the geometry coordinates (`Content-Crs`), CRS84 if not set
+    pub srid: Option<i32>,
+}
+
+impl EditFeature {
+    /// Read GeoJSON feature or feature patch
+    pub fn from_geojson(feature: &serde_json::Value) -> Result<Self> {
+        let Some(obj) = feature.as_object() else {
+            return Err(Error::InvalidFeature("GeoJSON object expected".to_string()));
+        };
+        if let Some(type_) = obj.get("type") {
+            if type_ != "Feature" {
+                return Err(Error::InvalidFeature(format!("unexpected type {type_}")));
+            }
+        }
+        let geometry = obj.get("geometry").cloned();
+        if let Some(geom) = &geometry {
+            let geometry_type = geom.get("type");
+            if !(geom.is_null() || matches!(geometry_type, Some(serde_json::Value::String(_)))) {
+                return Err(Error::InvalidFeature("invalid geometry".to_string()));
+            }
+        }
+        let properties = match obj.get("properties") {
+            Some(serde_json::Value::Object(props)) => props.clone(),
+            Some(serde_json::Value::Null) | None => serde_json::Map::new(),
+            Some(_) => return Err(Error::InvalidFeature("invalid properties".to_string())),
+        };
+        Ok(EditFeature {
+            geometry,
+            properties,
+            srid: None,
+        })
+    }
+}
+
+/// Table columns of feature properties
+pub(crate) fn property_columns<'a>(
+    feature: &'a EditFeature,
+    edit_columns: &[String],
+    pk_column: &str,
+) -> Result<Vec<&'a str>> {
+    let mut columns = Vec::new();
+    for key in feature.properties.keys() {
+        if edit_columns.contains(key) {
+            columns.push(key.as_str());
+        } else if key != pk_column {
+            return Err(Error::InvalidFeature(format!("unknown property `{key}`")));
+        }
+    }
+    Ok(columns)
+}
+
+/// Columns of requested properties in requested order (`None` for all properties).
+/// Unknown properties are ignored.
+pub(crate) fn selected_columns<'a>(
+    filter: &FilterParams,
+    columns: &'a [String],
+) -> Option<Vec<&'a str>> {
+    let properties = filter.properties()?;
+    let selected = properties
+        .into_iter()
+        .filter_map(|prop| columns.iter().find(|col| *col == prop))
+        .map(String::as_str)
+        .collect();
+    Some(selected)
+}
+
+/// Field selection and output configuration of a collection
+pub(crate) struct FieldSelection<'a> {
+    /// Fields included in output, in this order (all fields if empty)
+    pub include: &'a [String],
+    /// Fields excluded from output
+    pub exclude: &'a [String],
+    pub fields: &'a [FieldCfg],
+}
+
+impl FieldSelection<'_> {
+    /// Query of `sql` with field selection, aliases and rounding applied to its result `columns`.
+    /// `required` columns like primary key, geometry and temporal fields are always selected
+    /// with their original name. Returns `None` without field configuration.
+    pub fn query(
+        &self,
+        id: &str,
+        sql: &str,
+        columns: &[String],
+        required: &[&str],
+        round: fn(&str, u32) -> String,
+    ) -> Result<Option<String>> {
+        if self.include.is_empty() && self.exclude.is_empty() && self.fields.is_empty() {
+            return Ok(None);
+        }
+        if let Some(col) = self
+            .include
+            .iter()
+            .chain(self.exclude)
+            .chain(self.fields.iter().map(|field| &field.name))
+            .find(|col| !columns.contains(col))
+        {
+            return Err(Error::DatasourceSetupError(format!(
+                "Datasource `{id}`: configured field `{col}` not found"
+            )));
+        }
+        let selected = if self.include.is_empty() {
+            columns
+        } else {
+            self.include
+        };
+        let mut select_list: Vec<String> = selected
+            .iter()
+            .filter(|col| !self.exclude.contains(col) || required.contains(&col.as_str()))
+            .map(
+                |col| match self.fields.iter().find(|field| &field.name == col) {
+                    Some(field) if !required.contains(&col.as_str()) => {
+                        let expr = match field.decimals {
+                            Some(decimals) => round(col, decimals),
+                            None => format!(r#""{col}""#),
+                        };
+                        format!(r#"{expr} AS "{}""#, field.property_name())
+                    }
+                    _ => format!(r#""{col}""#),
+                },
+            )
+            .collect();
+        // Required columns missing in `include`
+        select_list.extend(
+            columns
+                .iter()
+                .filter(|col| required.contains(&col.as_str()) && !selected.contains(col))
+                .map(|col| format!(r#""{col}""#)),
+        );
+        Ok(Some(format!(
+            "SELECT {} FROM ({sql}) AS fields",
+            select_list.join(", ")
+        )))
+    }
+
+    /// Check that transactions don't write into aliased or rounded fields
+    pub fn check_writable(&self, id: &str) -> Result<()> {
+        if let Some(field) = self
+            .fields
+            .iter()
+            .find(|field| field.alias.is_some() || field.decimals.is_some())
+        {
+            return Err(Error::DatasourceSetupError(format!(
+                "Datasource `{id}`: transactions are not supported with alias or decimals of field `{}`",
+                field.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Queryables with titles and descriptions of configured fields
+pub(crate) fn describe_queryables(mut queryables: Queryables, fields: &[FieldCfg]) -> Queryables {
+    for field in fields {
+        if let Some(property) = queryables.properties.get_mut(field.property_name()) {
+            if field.title.is_some() {
+                property.title = field.title.clone();
+            }
+            property.description = field.description.clone();
+        }
+    }
+    queryables
+}
+
+/// Schema with titles and descriptions of configured fields
+pub(crate) fn describe_schema(mut schema: FeatureSchema, fields: &[FieldCfg]) -> FeatureSchema {
+    for field in fields {
+        if let Some((_, property)) = schema
+            .properties
+            .iter_mut()
+            .find(|(name, _)| name == field.property_name())
+        {
+            property.title = field.title.clone();
+            property.description = field.description.clone();
+        }
+    }
+    schema
+}
+
+/// Supported CRS URIs of a collection with the default CRS first
+pub(crate) fn collection_crs(
+    storage_srid: Option<i32>,
+    transform: bool,
+    additional_crs: &[i32],
+) -> Vec<String> {
+    let Some(storage_srid) = storage_srid else {
+        return Vec::new();
+    };
+    let mut srids = vec![storage_srid];
+    if transform {
+        srids.insert(0, 4326);
+        srids.extend(additional_crs);
+    }
+    let mut crs: Vec<String> = Vec::new();
+    for uri in srids.into_iter().map(crs_uri) {
+        if !crs.contains(&uri) {
+            crs.push(uri);
+        }
+    }
+    crs
+}
+
+/// Collection of a file source with `items` link and `queryables` link, if `queryables` is set
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn source_collection(
+    source: Box<dyn CollectionSource>,
+    id: &str,
+    title: Option<String>,
+    description: Option<String>,
+    extent: Option<CoreExtent>,
+    srid: Option<i32>,
+    additional_crs: &[i32],
+    queryables: bool,
+    base_url: &str,
+) -> FeatureCollection {
+    let mut collection = CoreCollection {
+        id: id.to_string(),
+        title: title.clone(),
+        description,
+        extent,
+        item_type: None,
+        crs: collection_crs(srid, GeometryTransform::SUPPORTED, additional_crs),
+        storage_crs: srid.map(crs_uri),
+        foreign_members: serde_json::Map::new(),
+        links: vec![ApiLink {
+            href: format!("{base_url}/collections/{id}/items"),
+            rel: Some("items".to_string()),
+            type_: Some("application/geo+json".to_string()),
+            title: title.clone(),
+            hreflang: None,
+            length: None,
+        }],
+    };
+    if queryables {
+        collection.links.push(ApiLink {
+            href: format!("{base_url}/collections/{id}/queryables"),
+            rel: Some("http://www.opengis.net/def/rel/ogc/1.0/queryables".to_string()),
+            type_: Some("application/schema+json".to_string()),
+            title,
+            hreflang: None,
+            length: None,
+        })
+    }
+    FeatureCollection { collection, source }
+}
+
+/// `self` and `collection` links of a single item
+pub(crate) fn item_links(base_url: &str, collection_id: &str, feature_id: &str) -> Vec<ApiLink> {
+    vec![
+        ApiLink {
+            href: format!("{base_url}/collections/{collection_id}/items/{feature_id}"),
+            rel: Some("self".to_string()),
+            type_: Some("application/geo+json".to_string()),
+            title: Some("this document".to_string()),
+            hreflang: None,
+            length: None,
+        },
+        ApiLink {
+            href: format!("{base_url}/collections/{collection_id}"),
+            rel: Some("collection".to_string()),
+            type_: Some("application/geo+json".to_string()),
+            title: Some("the collection document".to_string()),
+            hreflang: None,
+            length: None,
+        },
+    ]
+}
+
+/// Transformation from storage CRS `srid` into output CRS `crs` (Default: WGS84)
+pub(crate) fn geometry_transform(
+    srid: Option<i32>,
+    crs: Option<i32>,
+) -> Result<Option<GeometryTransform>> {
+    match (srid, crs) {
+        (Some(srid), Some(crs)) => GeometryTransform::new(srid, crs),
+        (Some(srid), None) if GeometryTransform::SUPPORTED => GeometryTransform::new(srid, 4326),
+        (None, Some(crs)) => Err(Error::UnsupportedCrs(crs_uri(crs))),
+        _ => Ok(None),
+    }
+}
+
+/// Bounding box of `filter` in storage CRS `srid`
+pub(crate) fn filter_bbox(filter: &FilterParams, srid: Option<i32>) -> Result<Option<[f64; 4]>> {
+    let Some(bbox) = filter.bbox().map_err(|e| {
+        error!("Ignoring invalid bbox: {e}");
+        Error::QueryParams
+    })?
+    else {
+        return Ok(None);
+    };
+    let mut bbox = match bbox[..] {
+        [minx, miny, _, maxx, maxy, _] => [minx, miny, maxx, maxy],
+        _ => [bbox[0], bbox[1], bbox[2], bbox[3]],
+    };
+    if let Some(srid) = srid {
+        if let Some(transform) = GeometryTransform::new(filter.bbox_srid()?, srid)? {
+            bbox = transform.transform_bbox(bbox)?;
+        }
+    }
+    Ok(Some(bbox))
+}
+
+/// Error of a file datasource
+pub(crate) fn datasource_error(e: impl std::fmt::Display) -> Error {
+    Error::DatasourceError(e.to_string())
+}
+
+/// Coordinate transformation of GeoJSON geometries
+pub(crate) struct GeometryTransform {
+    #[cfg(feature = "projtransform")]
+    proj: proj::Proj,
+}
+
+impl GeometryTransform {
+    /// Transformation between arbitrary CRS is available
+    pub const SUPPORTED: bool = cfg!(feature = "projtransform");
+
+    /// Transformation between EPSG codes, None if no transformation is needed
+    pub fn new(from_srid: i32, to_srid: i32) -> Result<Option<Self>> {
+        if from_srid == to_srid {
+            return Ok(None);
+        }
+        #[cfg(feature = "projtransform")]
+        {
+            let proj = proj::Proj::new_known_crs(
+                &format!("EPSG:{from_srid}"),
+                &format!("EPSG:{to_srid}"),
+                None,
+            )
+            .map_err(|_| Error::UnsupportedCrs(crs_uri(to_srid)))?;
+            Ok(Some(GeometryTransform { proj }))
+        }
+        #[cfg(not(feature = "projtransform"))]
+        Err(Error::UnsupportedCrs(crs_uri(to_srid)))
+    }
+
+    /// Transform coordinates of GeoJSON geometry in place
+    pub fn transform(&self, geometry: &mut serde_json::Value) -> Result<()> {
+        if let Some(geometries) = geometry.get_mut("geometries") {
+            for geometry in geometries.as_array_mut().into_iter().flatten() {
+                self.transform(geometry)?;
+            }
+        } else if let Some(coords) = geometry.get_mut("coordinates") {
+            self.transform_coords(coords)?;
+        }
+        Ok(())
+    }
+
+    /// Transform bounding box (minx, miny, maxx, maxy) by its corner points
+    pub fn transform_bbox(&self, bbox: [f64; 4]) -> Result<[f64; 4]> {
+        let [minx, miny, maxx, maxy] = bbox;
+        let mut points = serde_json::json!({
+            "type": "MultiPoint",
+            "coordinates": [[minx, miny], [maxx, miny], [maxx, maxy], [minx, maxy]]
+        });
+        self.transform(&mut points)?;
+        let mut bbox = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+        for point in points["coordinates"].as_array().into_iter().flatten() {
+            let (Some(x), Some(y)) = (point[0].as_f64(), point[1].as_f64()) else {
+                return Err(Error::GeometryFormatError);
+            };
+            bbox = [
+                bbox[0].min(x),
+                bbox[1].min(y),
+                bbox[2].max(x),
+                bbox[3].max(y),
+            ];
+        }
+        Ok(bbox)
+    }
+
+    #[cfg(feature = "projtransform")]
+    fn transform_coords(&self, coords: &mut serde_json::Value) -> Result<()> {
+        let Some(coords) = coords.as_array_mut() else {
+            return Ok(());
+        };
+        if let [x, y, ..] = coords.as_mut_slice() {
+            if let (Some(xv), Some(yv)) = (x.as_f64(), y.as_f64()) {
+                let (tx, ty) = self
+                    .proj
+                    .convert((xv, yv))
+                    .map_err(|_| Error::GeometryFormatError)?;
+                *x = tx.into();
+                *y = ty.into();
+                return Ok(());
+            }
+        }
+        for coord in coords {
+            self.transform_coords(coord)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "projtransform"))]
+    fn transform_coords(&self, _coords: &mut serde_json::Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Queryables of columns with known types
+pub(crate) fn column_queryables(
+    collection_id: &str,
+    columns: &HashMap<String, QueryableType>,
+) -> Queryables {
+    let properties: HashMap<String, QueryableProperty> = columns
+        .iter()
+        .map(|s| {
+            let title = s.0.to_string();
+            (
+                title.clone(),
+                QueryableProperty {
+                    title: Some(title),
+                    description: None,
+                    type_: Some(s.1.clone()),
+                    format: None,
+                },
+            )
+        })
+        .collect();
+    Queryables {
+        id: format!("/collections/{collection_id}/queryables"),
+        title: Some(collection_id.to_string()),
+        schema: "http://json-schema.org/draft/2019-09/schema".to_string(),
+        type_: "object".to_string(),
+        properties,
+    }
+}
+
+/// Sortables of columns with known types
+pub(crate) fn column_sortables(
+    collection_id: &str,
+    columns: &HashMap<String, QueryableType>,
+) -> Queryables {
+    Queryables {
+        id: format!("/collections/{collection_id}/sortables"),
+        ..column_queryables(collection_id, columns)
+    }
+}
+
+/// Schema of `properties` with roles of primary key and temporal columns
+pub(crate) fn feature_schema(
+    collection_id: &str,
+    mut properties: Vec<(String, SchemaProperty)>,
+    required: Vec<String>,
+    pk_column: Option<&String>,
+    temporal_column: Option<&String>,
+    temporal_end_column: Option<&String>,
+) -> FeatureSchema {
+    for (name, property) in &mut properties {
+        if Some(&*name) == pk_column {
+            property.role = Some("id".to_string());
+            property.read_only = true;
+        } else if Some(&*name) == temporal_column {
+            property.role = Some(if temporal_end_column.is_some() {
+                "primary-interval-start".to_string()
+            } else {
+                "primary-instant".to_string()
+            });
+        } else if Some(&*name) == temporal_end_column {
+            property.role = Some("primary-interval-end".to_string());
+        }
+    }
+    FeatureSchema {
+        schema: "https://json-schema.org/draft/2020-12/schema".to_string(),
+        id: format!("/collections/{collection_id}/schema"),
+        type_: "object".to_string(),
+        title: Some(collection_id.to_string()),
+        properties,
+        required,
+    }
+}
+
+/// Schema property of a geometry column with type name like `MULTIPOLYGON`
+pub(crate) fn geometry_schema_property(
+    geometry_type: Option<&str>,
+    primary: bool,
+) -> SchemaProperty {
+    let geometry_type = geometry_type
+        .map(|t| t.to_uppercase().trim_end_matches(['Z', 'M']).to_string())
+        .unwrap_or_default();
+    let format = match geometry_type.as_str() {
+        "POINT" => "geometry-point",
+        "LINESTRING" => "geometry-linestring",
+        "POLYGON" => "geometry-polygon",
+        "MULTIPOINT" => "geometry-multipoint",
+        "MULTILINESTRING" => "geometry-multilinestring",
+        "MULTIPOLYGON" => "geometry-multipolygon",
+        "GEOMETRYCOLLECTION" => "geometry-geometrycollection",
+        _ => "geometry-any",
+    };
+    SchemaProperty {
+        format: Some(format.to_string()),
+        role: primary.then(|| "primary-geometry".to_string()),
+        ..Default::default()
+    }
+}
+
+/// Schema property of a JSON Schema type and format
+pub(crate) fn schema_property(type_: Option<&str>, format: Option<&str>) -> SchemaProperty {
+    SchemaProperty {
+        type_: type_.map(str::to_string),
+        format: format.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+/// Request parameters bound to `:name` placeholders of a custom SQL query
+#[derive(Clone, Default, Debug)]
+pub(crate) struct SqlParameters {
+    parameters: Vec<SqlParameterCfg>,
+}
+
+impl SqlParameters {
+    /// Check that the placeholders of `sql` match the configured `parameters`
+    pub fn new(id: &str, sql: &str, parameters: &[SqlParameterCfg]) -> Result<Self> {
+        let setup_error =
+            |msg: String| Error::DatasourceSetupError(format!("Datasource `{id}`: {msg}"));
+        let placeholders = sql_placeholders(sql);
+        for (i, param) in parameters.iter().enumerate() {
+            let valid_name = param
+                .name
+                .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && param
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name {
+                return Err(setup_error(format!(
+                    "invalid parameter name `{}`",
+                    param.name
+                )));
+            }
+            if parameters[..i]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&param.name))
+            {
+                return Err(setup_error(format!("duplicate parameter `{}`", param.name)));
+            }
+            if let Some(default) = &param.default {
+                parameter_value(param.type_, default).map_err(|e| {
+                    setup_error(format!(
+                        "invalid default of parameter `{}`: {e}",
+                        param.name
+                    ))
+                })?;
+            }
+            if !placeholders.iter().any(|(_, name)| *name == param.name) {
+                return Err(setup_error(format!(
+                    "parameter `{}` not used in sql",
+                    param.name
+                )));
+            }
+        }
+        if let Some((_, name)) = placeholders
+            .iter()
+            .find(|(_, name)| !parameters.iter().any(|param| param.name == *name))
+        {
+            return Err(setup_error(format!(
+                "placeholder `:{name}` without parameter configuration"
+            )));
+        }
+        Ok(SqlParameters {
+            parameters: parameters.to_vec(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty()
+    }
+
+    /// Whether the request parameter `key` is bound to a placeholder
+    pub fn contains(&self, key: &str) -> bool {
+        self.parameters
+            .iter()
+            .any(|param| param.name.eq_ignore_ascii_case(key))
+    }
+
+    /// `sql` with placeholders replaced by default values or `NULL`, for setup queries
+    pub fn literal_sql(&self, sql: &str) -> String {
+        let mut literal = String::with_capacity(sql.len());
+        let mut pos = 0;
+        for (start, name) in sql_placeholders(sql) {
+            literal.push_str(&sql[pos..start]);
+            let value = self
+                .parameter(name)
+                .and_then(|param| param.default.as_ref().map(|val| (param.type_, val)))
+                .and_then(|(type_, val)| parameter_value(type_, val).ok());
+            literal.push_str(&match value {
+                Some(value) => sql_literal(&value),
+                None => "NULL".to_string(),
+            });
+            pos = start + 1 + name.len();
+        }
+        literal.push_str(&sql[pos..]);
+        literal
+    }
+
+    /// `sql` with placeholders bound to the request parameters of `filter`
+    pub fn query_parts(&self, sql: &str, filter: &FilterParams) -> Result<Vec<SqlPart>> {
+        let mut parts = Vec::new();
+        let mut pos = 0;
+        for (start, name) in sql_placeholders(sql) {
+            parts.push(SqlPart::Sql(sql[pos..start].to_string()));
+            let value = match self.parameter(name) {
+                Some(param) => self.value(param, filter)?,
+                None => None,
+            };
+            parts.push(match value {
+                Some(value) => SqlPart::Bind(value),
+                None => SqlPart::Sql("NULL".to_string()),
+            });
+            pos = start + 1 + name.len();
+        }
+        parts.push(SqlPart::Sql(sql[pos..].to_string()));
+        Ok(parts)
+    }
+
+    /// Check types and required values of the request parameters of `filter`
+    pub fn check(&self, filter: &FilterParams) -> Result<()> {
+        for param in &self.parameters {
+            self.value(param, filter)?;
+        }
+        Ok(())
+    }
+
+    /// Queryables of parameters with their description
+    pub fn add_queryables(&self, queryables: &mut Queryables) {
+        for param in &self.parameters {
+            let type_ = match param.type_ {
+                SqlParameterType::String => QueryableType::String,
+                SqlParameterType::Integer => QueryableType::Integer,
+                SqlParameterType::Number => QueryableType::Number,
+                SqlParameterType::Boolean => QueryableType::Bool,
+                SqlParameterType::Datetime => QueryableType::Datetime,
+            };
+            queryables.properties.insert(
+                param.name.clone(),
+                QueryableProperty {
+                    title: Some(param.name.clone()),
+                    description: param.description.clone(),
+                    type_: Some(type_),
+                    format: None,
+                },
+            );
+        }
+    }
+
+    fn parameter(&self, name: &str) -> Option<&SqlParameterCfg> {
+        self.parameters.iter().find(|param| param.name == name)
+    }
+
+    fn value(&self, param: &SqlParameterCfg, filter: &FilterParams) -> Result<Option<SqlValue>> {
+        // Query parameter keys are lowercase
+        match filter
+            .filters
+            .get(&param.name.to_lowercase())
+            .or(param.default.as_ref())
+        {
+            Some(val) => parameter_value(param.type_, val)
+                .map(Some)
+                .map_err(|e| Error::InvalidParameter(format!("`{}`: {e}", param.name))),
+            None if param.required => Err(Error::InvalidParameter(format!(
+                "`{}` is required",
+                param.name
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Typed value of a request parameter
+fn parameter_value(type_: SqlParameterType, val: &str) -> std::result::Result<SqlValue, String> {
+    match type_ {
+        SqlParameterType::String => Ok(SqlValue::Text(val.to_string())),
+        SqlParameterType::Integer => val
+            .parse()
+            .map(SqlValue::Int)
+            .map_err(|_| format!("`{val}` is not an integer")),
+        SqlParameterType::Number => match val.parse::<f64>() {
+            Ok(num) if num.is_finite() => Ok(SqlValue::Float(num)),
+            _ => Err(format!("`{val}` is not a number")),
+        },
+        SqlParameterType::Boolean => match val.to_lowercase().as_str() {
+            "true" => Ok(SqlValue::Bool(true)),
+            "false" => Ok(SqlValue::Bool(false)),
+            _ => Err(format!("`{val}` is not a boolean")),
+        },
+        SqlParameterType::Datetime => DateTime::parse_from_rfc3339(val)
+            .map(SqlValue::Timestamp)
+            .or_else(|_| NaiveDate::parse_from_str(val, "%Y-%m-%d").map(SqlValue::Date))
+            .map_err(|_| format!("`{val}` is not a RFC 3339 datetime or date")),
+    }
+}
+
+/// SQL literal of a parameter value
+fn sql_literal(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Text(val) => format!("'{}'", val.replace('\'', "''")),
+        SqlValue::Int(val) => val.to_string(),
+        SqlValue::Float(val) => val.to_string(),
+        SqlValue::Bool(val) => if *val { "TRUE" } else { "FALSE" }.to_string(),
+        SqlValue::Timestamp(val) => format!("'{}'", val.to_rfc3339()),
+        SqlValue::Date(val) => format!("'{val}'"),
+    }
+}
+
+/// `:name` placeholders of `sql` with the offset of their colon.
+/// String literals, quoted identifiers, comments and `::` casts are skipped.
+fn sql_placeholders(sql: &str) -> Vec<(usize, &str)> {
+    let bytes = sql.as_bytes();
+    let mut placeholders = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                // Escaped quotes are scanned as two adjacent literals
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => {
+                i += 1;
+            }
+            b':' => {
+                let start = i + 1;
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+                    .count();
+                if len > 0 && !bytes[start].is_ascii_digit() {
+                    placeholders.push((i, &sql[start..start + len]));
+                    i += len;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    placeholders
+}
+
+/// Run blocking file I/O on the thread pool for blocking tasks
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    actix_web::web::block(f)
+        .await
+        .map_err(|e| Error::DatasourceError(e.to_string()))?
+}
+
+/// Error of an attribute filter on a property which is not queryable
+pub(crate) fn not_queryable(key: &str) -> Error {
+    Error::InvalidParameter(format!("property `{key}` is not queryable"))
+}
+
+/// Error of an attribute filter value not matching the queryable type
+pub(crate) fn invalid_value(key: &str, val: &str) -> Error {
+    Error::InvalidParameter(format!("invalid value `{val}` of property `{key}`"))
+}
+
+/// `ORDER BY` clause for `sortby` keys, with primary key as tiebreaker
+pub(crate) fn order_by_clause(
+    sortby: &[SortKey],
+    sortables: &HashMap<String, QueryableType>,
+    pk_column: Option<&String>,
+) -> Result<Option<String>> {
+    if sortby.is_empty() {
+        return Ok(None);
+    }
+    let mut terms = Vec::new();
+    for key in sortby {
+        if !sortables.contains_key(&key.property) {
+            return Err(Error::InvalidSortby(format!(
+                "property `{}` is not sortable",
+                key.property
+            )));
+        }
+        let dir = if key.descending { "DESC" } else { "ASC" };
+        terms.push(format!(r#""{}" {dir}"#, key.property));
+    }
+    if let Some(pk) = pk_column {
+        if !sortby.iter().any(|key| &key.property == pk) {
+            terms.push(format!(r#""{pk}" ASC"#));
+        }
+    }
+    Ok(Some(format!(" ORDER BY {}", terms.join(", "))))
+}
+
+/// Point places (name, scalerank, x, y) shared by the file datasource tests
+#[cfg(test)]
+pub(crate) fn test_places() -> Vec<(&'static str, i64, f64, f64)> {
+    vec![
+        ("Bern", 2, 7.44, 46.95),
+        ("Zürich", 1, 8.54, 47.37),
+        ("Genève", 1, 6.14, 46.20),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sortby_order() {
+        let sortables = HashMap::from([
+            ("name".to_string(), QueryableType::String),
+            ("fid".to_string(), QueryableType::Integer),
+        ]);
+        let pk = Some("fid".to_string());
+        let sortby = FilterParams {
+            sortby: Some("-name".to_string()),
+            ..Default::default()
+        }
+        .sortby()
+        .unwrap();
+        assert_eq!(
+            order_by_clause(&sortby, &sortables, pk.as_ref()).unwrap(),
+            Some(r#" ORDER BY "name" DESC, "fid" ASC"#.to_string())
+        );
+        let sortby = FilterParams {
+            sortby: Some("-fid".to_string()),
+            ..Default::default()
+        }
+        .sortby()
+        .unwrap();
+        assert_eq!(
+            order_by_clause(&sortby, &sortables, pk.as_ref()).unwrap(),
+            Some(r#" ORDER BY "fid" DESC"#.to_string())
+        );
+        assert_eq!(order_by_clause(&[], &sortables, pk.as_ref()).unwrap(), None);
+        let sortby = vec![SortKey {
+            property: "geom".to_string(),
+            descending: false,
+        }];
+        assert!(order_by_clause(&sortby, &sortables, None).is_err());
+    }
+
+    #[test]
+    fn sql_parameters() {
+        let sql = "SELECT *, 'a:b' AS \"x:y\" FROM t -- :c\nWHERE year = :year AND name = :name AND ts::date > :since";
+        assert_eq!(
+            sql_placeholders(sql)
+                .iter()
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>(),
+            vec!["year", "name", "since"]
+        );
+        let param = |name: &str, type_, default: Option<&str>, required| SqlParameterCfg {
+            name: name.to_string(),
+            type_,
+            default: default.map(str::to_string),
+            required,
+            description: None,
+        };
+        let params = SqlParameters::new(
+            "t",
+            sql,
+            &[
+                param("year", SqlParameterType::Integer, Some("2020"), false),
+                param("name", SqlParameterType::String, None, true),
+                param("since", SqlParameterType::Datetime, None, false),
+            ],
+        )
+        .unwrap();
+        assert!(params.contains("Year"));
+        assert!(!params.contains("ts"));
+        assert!(params
+            .literal_sql(sql)
+            .ends_with("WHERE year = 2020 AND name = NULL AND ts::date > NULL"));
+
+        let mut filter = FilterParams::default();
+        assert!(matches!(
+            params.check(&filter),
+            Err(Error::InvalidParameter(_))
+        ));
+        filter
+            .filters
+            .insert("name".to_string(), "O'Hara".to_string());
+        filter
+            .filters
+            .insert("since".to_string(), "2021-05-09".to_string());
+        let parts = params.query_parts(sql, &filter).unwrap();
+        assert_eq!(parts[1], SqlPart::Bind(SqlValue::Int(2020)));
+        assert_eq!(
+            parts[3],
+            SqlPart::Bind(SqlValue::Text("O'Hara".to_string()))
+        );
+        assert_eq!(
+            parts[5],
+            SqlPart::Bind(SqlValue::Date(NaiveDate::from_ymd_opt(2021, 5, 9).unwrap()))
+        );
+        filter
+            .filters
+            .insert("year".to_string(), "2020; DROP TABLE t".to_string());
+        assert!(params.query_parts(sql, &filter).is_err());
+
+        // Unknown placeholder and unused parameter
+        assert!(SqlParameters::new("t", "SELECT * FROM t WHERE a = :a", &[]).is_err());
+        assert!(SqlParameters::new(
+            "t",
+            "SELECT * FROM t",
+            &[param("a", SqlParameterType::String, None, false)]
+        )
+        .is_err());
+        assert!(SqlParameters::new(
+            "t",
+            "SELECT * FROM t WHERE a = :a",
+            &[param("a", SqlParameterType::Integer, Some("x"), false)]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn edit_feature() {
+        let feature = EditFeature::from_geojson(&json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [7.44, 46.95]},
+            "properties": {"name": "Bern"}
+        }))
+        .unwrap();
+        assert_eq!(feature.geometry.unwrap()["type"], "Point");
+        assert_eq!(feature.properties["name"], "Bern");
+
+        // Patch without geometry
+        let feature = EditFeature::from_geojson(&json!({"properties": {"name": null}})).unwrap();
+        assert!(feature.geometry.is_none());
+        assert!(feature.properties["name"].is_null());
+
+        let feature = EditFeature::from_geojson(&json!({"geometry": null})).unwrap();
+        assert_eq!(feature.geometry, Some(serde_json::Value::Null));
+
+        assert!(EditFeature::from_geojson(&json!({"type": "FeatureCollection"})).is_err());
+        assert!(EditFeature::from_geojson(&json!({"geometry": [1, 2]})).is_err());
+        assert!(EditFeature::from_geojson(&json!({"properties": "name"})).is_err());
+        assert!(EditFeature::from_geojson(&json!([])).is_err());
+    }
+
+    #[test]
+    fn crs_list() {
+        assert_eq!(
+            collection_crs(Some(2056), true, &[3857, 2056]),
+            vec![
+                "http://www.opengis.net/def/crs/OGC/1.3/CRS84",
+                "http://www.opengis.net/def/crs/EPSG/0/2056",
+                "http://www.opengis.net/def/crs/EPSG/0/3857"
+            ]
+        );
+        assert_eq!(
+            collection_crs(Some(4326), false, &[2056]),
+            vec!["http://www.opengis.net/def/crs/OGC/1.3/CRS84"]
+        );
+        assert!(collection_crs(None, true, &[2056]).is_empty());
+    }
+
+    #[cfg(feature = "projtransform")]
+    #[test]
+    fn geometry_transform() {
+        let transform = GeometryTransform::new(4326, 3857).unwrap().unwrap();
+        let mut geometry =
+            json!({"type": "LineString", "coordinates": [[0.0, 0.0], [7.44, 46.95]]});
+        transform.transform(&mut geometry).unwrap();
+        let coords = &geometry["coordinates"];
+        assert!(coords[0][0].as_f64().unwrap().abs() < 1e-6);
+        assert!((coords[1][0].as_f64().unwrap() - 828217.0).abs() < 1.0);
+        assert!((coords[1][1].as_f64().unwrap() - 5933916.6).abs() < 1.0);
+        assert!(GeometryTransform::new(2056, 2056).unwrap().is_none());
+    }
 }
@@ -0,0 +1,490 @@
+//! STAC collections of pgstac databases and STAC metadata helpers.
+//!
+//! Static STAC catalogs are served by the in-memory source of [`super::memstore`].
+
+use crate::config::{CollectionSourceCfg, ConfiguredCollectionCfg};
+use crate::datasource::{collection_crs, CollectionSource, FeatureSender, ItemsResult};
+use crate::error::{Error, Result};
+use crate::filter_params::{crs_uri, encode_cursor, FilterParams, TemporalType};
+use crate::inventory::FeatureCollection;
+use async_trait::async_trait;
+use bbox_core::ogcapi::*;
+use bbox_core::pg_ds::PgDatasource;
+use chrono::{DateTime, FixedOffset};
+use futures::SinkExt;
+use log::{debug, error, info};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// STAC version of generated catalog and collection members
+pub const STAC_VERSION: &str = "1.0.0";
+
+/// Conformance classes of the STAC API
+///
+/// `/search` is not announced as item search, since `ids` and `intersects` are not supported.
+pub const STAC_CONFORMANCE_CLASSES: [&str; 3] = [
+    "https://api.stacspec.org/v1.0.0/core",
+    "https://api.stacspec.org/v1.0.0/collections",
+    "https://api.stacspec.org/v1.0.0/ogcapi-features",
+];
+
+#[derive(Clone, Debug)]
+pub struct PgstacCollectionSource {
+    ds: PgDatasource,
+    /// pgstac collection id
+    stac_id: String,
+    /// API URL of the collection
+    collection_href: String,
+    collection_id: String,
+}
+
+/// Collection of a `[collection.pgstac]` configuration
+pub async fn setup_collection(
+    ds: &PgDatasource,
+    cfg: &ConfiguredCollectionCfg,
+    base_url: &str,
+) -> Result<FeatureCollection> {
+    info!("Setup pgstac Collection `{}`", &cfg.name);
+    let CollectionSourceCfg::Pgstac(ref srccfg) = cfg.source else {
+        panic!();
+    };
+    let id = &cfg.name;
+    let stac_id = srccfg.collection.clone().unwrap_or(id.clone());
+    let content: Option<String> =
+        sqlx::query_scalar("SELECT content::text FROM pgstac.collections WHERE id = $1")
+            .bind(&stac_id)
+            .fetch_optional(&ds.pool)
+            .await?;
+    let Some(Value::Object(content)) = content
+        .map(|content| serde_json::from_str(&content))
+        .transpose()?
+    else {
+        return Err(Error::DatasourceSetupError(format!(
+            "Collection `{id}`: pgstac collection `{stac_id}` not found"
+        )));
+    };
+    let mut collection = CoreCollection {
+        id: id.clone(),
+        title: cfg.title.clone(),
+        description: cfg.description.clone(),
+        links: vec![ApiLink {
+            href: format!("{base_url}/collections/{id}/items"),
+            rel: Some("items".to_string()),
+            type_: Some("application/geo+json".to_string()),
+            title: Some(id.clone()),
+            hreflang: None,
+            length: None,
+        }],
+        extent: None,
+        item_type: None,
+        crs: collection_crs(Some(4326), false, &[]),
+        storage_crs: Some(crs_uri(4326)),
+        foreign_members: Map::new(),
+    };
+    apply_stac_collection(&mut collection, content);
+    let source = PgstacCollectionSource {
+        ds: ds.clone(),
+        stac_id,
+        collection_href: format!("{base_url}/collections/{id}"),
+        collection_id: id.clone(),
+    };
+    Ok(FeatureCollection {
+        collection,
+        source: Box::new(source),
+    })
+}
+
+/// Request body of `pgstac.search`
+fn search_body(stac_id: &str, filter: &FilterParams) -> Result<Value> {
+    if !matches!(filter.crs_srid()?, None | Some(4326)) {
+        return Err(Error::UnsupportedCrs(
+            filter.crs.clone().unwrap_or_default(),
+        ));
+    }
+    // pgstac has no offset parameter, skipped items are fetched as well
+    let limit = match filter.limit_or_default() {
+        0 => i32::MAX as u32,
+        limit => limit.saturating_add(filter.offset.unwrap_or(0)),
+    };
+    let mut body = json!({"collections": [stac_id], "limit": limit});
+    let bbox = filter.bbox().map_err(|e| {
+        error!("Ignoring invalid bbox: {e}");
+        Error::QueryParams
+    })?;
+    if let Some(bbox) = bbox {
+        body["bbox"] = json!(bbox);
+    }
+    if let Some(datetime) = &filter.datetime {
+        body["datetime"] = json!(datetime);
+    }
+    if let Some(cursor) = filter.cursor()? {
+        let Some(Value::String(token)) = cursor.first() else {
+            return Err(Error::InvalidCursor);
+        };
+        body["token"] = json!(format!("next:{token}"));
+    }
+    let mut conditions: Vec<Value> = filter
+        .other_params()
+        .map_err(|e| {
+            error!("{e}");
+            Error::QueryParams
+        })?
+        .iter()
+        .map(|(key, val)| json!({"op": "=", "args": [{"property": key}, val]}))
+        .collect();
+    if let Some(expr) = &filter.filter {
+        if filter.filter_lang.as_deref() != Some("cql2-json") {
+            error!("pgstac collections support cql2-json filters only");
+            return Err(Error::QueryParams);
+        }
+        conditions.push(serde_json::from_str(expr)?);
+    }
+    match conditions.len() {
+        0 => {}
+        1 => body["filter"] = conditions.remove(0),
+        _ => body["filter"] = json!({"op": "and", "args": conditions}),
+    }
+    if body.get("filter").is_some() {
+        body["filter-lang"] = json!("cql2-json");
+    }
+    let sortby: Vec<Value> = filter
+        .sortby()?
+        .iter()
+        .map(|key| {
+            let direction = if key.descending { "desc" } else { "asc" };
+            json!({"field": key.property, "direction": direction})
+        })
+        .collect();
+    if !sortby.is_empty() {
+        body["sortby"] = json!(sortby);
+    }
+    Ok(body)
+}
+
+impl PgstacCollectionSource {
+    /// Item with collection member and API links
+    fn to_feature(&self, item: Value) -> Option<CoreFeature> {
+        let Value::Object(item) = item else {
+            return None;
+        };
+        let mut feature = stac_item(item);
+        let id = feature.id.clone().unwrap_or_default();
+        feature.links = stac_item_links(&self.collection_href, &id);
+        feature
+            .foreign_members
+            .insert("collection".to_string(), json!(self.collection_id));
+        Some(feature)
+    }
+}
+
+#[async_trait]
+impl CollectionSource for PgstacCollectionSource {
+    async fn send_items(
+        &self,
+        filter: &FilterParams,
+        mut sender: FeatureSender,
+    ) -> Result<ItemsResult> {
+        let body = search_body(&self.stac_id, filter)?;
+        debug!("pgstac search: {body}");
+        let result: String = sqlx::query_scalar("SELECT pgstac.search($1::text::jsonb)::text")
+            .bind(body.to_string())
+            .fetch_one(&self.ds.pool)
+            .await?;
+        let mut result: Value = serde_json::from_str(&result)?;
+        let number_matched = result["numberMatched"]
+            .as_u64()
+            .or_else(|| result["context"]["matched"].as_u64());
+        let next_cursor = result["next"]
+            .as_str()
+            .map(|token| encode_cursor(&[json!(token)]));
+        let Value::Array(items) = result["features"].take() else {
+            return Err(Error::DatasourceError(
+                "pgstac search result without features".to_string(),
+            ));
+        };
+        let mut number_returned = 0;
+        for item in items.into_iter().skip(filter.offset.unwrap_or(0) as usize) {
+            let Some(feature) = self.to_feature(item) else {
+                continue;
+            };
+            if sender.send(feature).await.is_err() {
+                debug!("Items receiver closed");
+                return Err(Error::ItemsStreamClosed);
+            }
+            number_returned += 1;
+        }
+        Ok(ItemsResult {
+            features: Vec::new(),
+            number_matched,
+            number_returned,
+            next_cursor,
+        })
+    }
+
+    async fn item(
+        &self,
+        _base_url: &str,
+        _collection_id: &str,
+        feature_id: &str,
+        filter: &FilterParams,
+    ) -> Result<Option<CoreFeature>> {
+        if !matches!(filter.crs_srid()?, None | Some(4326)) {
+            return Err(Error::UnsupportedCrs(
+                filter.crs.clone().unwrap_or_default(),
+            ));
+        }
+        let item: Option<String> = sqlx::query_scalar("SELECT pgstac.get_item($1, $2)::text")
+            .bind(feature_id)
+            .bind(&self.stac_id)
+            .fetch_one(&self.ds.pool)
+            .await?;
+        let Some(item) = item else {
+            return Ok(None);
+        };
+        Ok(self.to_feature(serde_json::from_str(&item)?))
+    }
+
+    async fn queryables(&self, collection_id: &str) -> Result<Option<Queryables>> {
+        let schema: Option<String> = sqlx::query_scalar("SELECT pgstac.get_queryables($1)::text")
+            .bind(&self.stac_id)
+            .fetch_one(&self.ds.pool)
+            .await?;
+        let Some(schema) = schema else {
+            return Ok(None);
+        };
+        let schema: Value = serde_json::from_str(&schema)?;
+        let Some(properties) = schema["properties"].as_object() else {
+            return Ok(None);
+        };
+        let properties: HashMap<String, QueryableProperty> = properties
+            .iter()
+            .map(|(name, property)| {
+                let type_ = match property["type"].as_str() {
+                    Some("string") if property["format"] == "date-time" => {
+                        Some(QueryableType::Datetime)
+                    }
+                    Some("string") => Some(QueryableType::String),
+                    Some("integer") => Some(QueryableType::Integer),
+                    Some("number") => Some(QueryableType::Number),
+                    Some("boolean") => Some(QueryableType::Bool),
+                    _ => None,
+                };
+                let text = |key: &str| property[key].as_str().map(str::to_string);
+                let queryable = QueryableProperty {
+                    type_,
+                    title: text("title"),
+                    description: text("description"),
+                    format: text("format"),
+                };
+                (name.clone(), queryable)
+            })
+            .collect();
+        Ok(Some(Queryables {
+            type_: "object".to_string(),
+            title: Some(collection_id.to_string()),
+            id: format!("{}/queryables", self.collection_href),
+            schema: "https://json-schema.org/draft/2019-09/schema".to_string(),
+            properties,
+        }))
+    }
+}
+
+/// Add STAC collection metadata of `content` to `collection`.
+/// Configured title and description take precedence.
+pub(crate) fn apply_stac_collection(
+    collection: &mut CoreCollection,
+    mut content: Map<String, Value>,
+) {
+    let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+    if collection.title.is_none() {
+        collection.title = text(content.get("title"));
+    }
+    if collection.description.is_none() {
+        collection.description = text(content.get("description"));
+    }
+    if let Some(extent) = content.get("extent").and_then(stac_extent) {
+        collection.extent = Some(extent);
+    }
+    // Members of CoreCollection and file specific links
+    for key in [
+        "id",
+        "title",
+        "description",
+        "links",
+        "extent",
+        "itemType",
+        "crs",
+        "storageCrs",
+    ] {
+        content.remove(key);
+    }
+    content.entry("type").or_insert_with(|| json!("Collection"));
+    content
+        .entry("stac_version")
+        .or_insert_with(|| json!(STAC_VERSION));
+    content.entry("license").or_insert_with(|| json!("other"));
+    collection.foreign_members = content;
+}
+
+fn stac_extent(extent: &Value) -> Option<CoreExtent> {
+    let bbox: Option<Vec<Vec<f64>>> =
+        serde_json::from_value(extent["spatial"]["bbox"].clone()).ok();
+    let interval: Option<Vec<Vec<Option<String>>>> =
+        serde_json::from_value(extent["temporal"]["interval"].clone()).ok();
+    if bbox.is_none() && interval.is_none() {
+        return None;
+    }
+    Some(CoreExtent {
+        spatial: bbox.map(|bbox| CoreExtentSpatial { bbox, crs: None }),
+        temporal: interval.map(|interval| CoreExtentTemporal {
+            interval,
+            trs: None,
+        }),
+    })
+}
+
+/// Feature of a STAC item with all other members than `links` as foreign members
+pub(crate) fn stac_item(mut item: Map<String, Value>) -> CoreFeature {
+    let id = item.remove("id").map(|id| match id {
+        Value::String(id) => id,
+        id => id.to_string(),
+    });
+    let geometry = item.remove("geometry").unwrap_or(Value::Null);
+    let properties = item.remove("properties");
+    item.remove("type");
+    // Links of catalog files are replaced with API links
+    item.remove("links");
+    item.entry("stac_version")
+        .or_insert_with(|| json!(STAC_VERSION));
+    CoreFeature {
+        type_: "Feature".to_string(),
+        geometry,
+        properties,
+        id,
+        links: Vec::new(),
+        foreign_members: item,
+    }
+}
+
+/// Self and collection links of a STAC item
+pub(crate) fn stac_item_links(collection_href: &str, id: &str) -> Vec<ApiLink> {
+    vec![
+        ApiLink {
+            href: format!("{collection_href}/items/{id}"),
+            rel: Some("self".to_string()),
+            type_: Some("application/geo+json".to_string()),
+            title: None,
+            hreflang: None,
+            length: None,
+        },
+        ApiLink {
+            href: collection_href.to_string(),
+            rel: Some("collection".to_string()),
+            type_: Some("application/json".to_string()),
+            title: None,
+            hreflang: None,
+            length: None,
+        },
+    ]
+}
+
+/// Whether the `datetime` or `start_datetime`/`end_datetime` properties of a STAC item
+/// intersect the `datetime` parameter
+pub(crate) fn stac_datetime_matches(
+    properties: &Map<String, Value>,
+    temporal: &[TemporalType],
+) -> bool {
+    let datetime = |key: &str| -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(properties.get(key)?.as_str()?).ok()
+    };
+    let (Some(start), Some(end)) = (
+        datetime("start_datetime").or_else(|| datetime("datetime")),
+        datetime("end_datetime").or_else(|| datetime("datetime")),
+    ) else {
+        return false;
+    };
+    let bound = |t: &TemporalType| match t {
+        TemporalType::DateTime(dt) => Some(*dt),
+        TemporalType::Open => None,
+    };
+    let (from, to) = match temporal {
+        [instant] => (bound(instant), bound(instant)),
+        [from, to] => (bound(from), bound(to)),
+        _ => return false,
+    };
+    !matches!(from, Some(from) if end < from) && !matches!(to, Some(to) if start > to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pgstac_search_body() {
+        let mut filter = FilterParams {
+            limit: Some(10),
+            bbox: Some("7,46,8,47".to_string()),
+            datetime: Some("2020-01-01T00:00:00Z/..".to_string()),
+            sortby: Some("-datetime".to_string()),
+            ..Default::default()
+        };
+        filter
+            .filters
+            .insert("platform".to_string(), "sentinel-2a".to_string());
+        let body = search_body("sentinel-2-l2a", &filter).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "collections": ["sentinel-2-l2a"],
+                "limit": 10,
+                "bbox": [7.0, 46.0, 8.0, 47.0],
+                "datetime": "2020-01-01T00:00:00Z/..",
+                "filter": {"op": "=", "args": [{"property": "platform"}, "sentinel-2a"]},
+                "filter-lang": "cql2-json",
+                "sortby": [{"field": "datetime", "direction": "desc"}]
+            })
+        );
+
+        filter.cursor = Some(encode_cursor(&[json!("S2A_20200101")]));
+        let body = search_body("sentinel-2-l2a", &filter).unwrap();
+        assert_eq!(body["token"], json!("next:S2A_20200101"));
+
+        let filter = FilterParams {
+            filter: Some("eo:cloud_cover < 10".to_string()),
+            ..Default::default()
+        };
+        assert!(search_body("sentinel-2-l2a", &filter).is_err());
+        let filter = FilterParams {
+            limit: Some(10),
+            offset: Some(20),
+            ..Default::default()
+        };
+        let body = search_body("sentinel-2-l2a", &filter).unwrap();
+        assert_eq!(body["limit"], json!(30));
+    }
+
+    #[test]
+    fn stac_datetime() {
+        let dt = |s: &str| TemporalType::DateTime(DateTime::parse_from_rfc3339(s).unwrap());
+        let Value::Object(instant) = json!({"datetime": "2021-06-01T10:00:00Z"}) else {
+            panic!()
+        };
+        let Value::Object(range) = json!({
+            "datetime": null,
+            "start_datetime": "2021-01-01T00:00:00Z",
+            "end_datetime": "2021-12-31T23:59:59Z"
+        }) else {
+            panic!()
+        };
+        let after = [dt("2021-07-01T00:00:00Z"), TemporalType::Open];
+        assert!(!stac_datetime_matches(&instant, &after));
+        assert!(stac_datetime_matches(&range, &after));
+        let before = [TemporalType::Open, dt("2021-06-01T10:00:00Z")];
+        assert!(stac_datetime_matches(&instant, &before));
+        assert!(stac_datetime_matches(&range, &[dt("2021-03-01T00:00:00Z")]));
+        assert!(!stac_datetime_matches(
+            &Map::new(),
+            &[dt("2021-03-01T00:00:00Z")]
+        ));
+    }
+}
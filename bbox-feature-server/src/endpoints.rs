@@ -1,12 +1,19 @@
-use crate::filter_params::FilterParams;
+use crate::config::FeatureServerCfg;
+use crate::datasource::EditFeature;
+use crate::error;
+use crate::filter_params::{crs_uri, srid_from_crs_uri, FilterParams};
 use crate::inventory::Inventory;
+use crate::output::{gpkg::features_to_gpkg, ItemsFormat};
 use crate::service::FeatureService;
-use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web::http::header::{self, EntityTag};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
 use bbox_core::api::OgcApiInventory;
+use bbox_core::auth::Identity;
 use bbox_core::endpoints::absurl;
-use bbox_core::ogcapi::{ApiLink, CoreCollections};
+use bbox_core::ogcapi::{ApiLink, CoreCollection, CoreCollections};
 use bbox_core::service::ServiceEndpoints;
 use bbox_core::templates::{create_env_embedded, html_accepted, render_endpoint};
+use log::{error, warn};
 use minijinja::{context, Environment};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -27,7 +34,7 @@ async fn collections(
             length: None,
         }],
         //TODO: include also collections from other services
-        collections: inventory.collections(), //TODO: convert urls with absurl (?)
+        collections: inventory.collections(Identity::from_request(&req).as_ref()), //TODO: convert urls with absurl (?)
     };
     if html_accepted(&req).await {
         render_endpoint(
@@ -47,6 +54,9 @@ async fn collection(
     req: HttpRequest,
     collection_id: web::Path<String>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
     if let Some(collection) = inventory.core_collection(&collection_id) {
         if html_accepted(&req).await {
             render_endpoint(
@@ -69,6 +79,9 @@ async fn queryables(
     req: HttpRequest,
     collection_id: web::Path<String>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
     if let Some(queryables) = inventory.collection_queryables(&collection_id).await {
         if html_accepted(&req).await {
             render_endpoint(
@@ -87,12 +100,217 @@ async fn queryables(
     }
 }
 
+/// describe the properties which can be used for sorting items of the collection with id `collectionId`
+async fn sortables(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    collection_id: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
+    if let Some(sortables) = inventory.collection_sortables(&collection_id).await {
+        Ok(HttpResponse::Ok()
+            .content_type("application/schema+json")
+            .json(sortables))
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// the schema of the features of the collection with id `collectionId`
+async fn schema(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    collection_id: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
+    if let Some(schema) = inventory.collection_schema(&collection_id).await {
+        Ok(HttpResponse::Ok()
+            .content_type("application/schema+json")
+            .json(schema))
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// search features in multiple collections
+async fn search(
+    inventory: web::Data<Inventory>,
+    server_cfg: web::Data<FeatureServerCfg>,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let Ok(params) = serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string()) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+    search_response(&inventory, &server_cfg, &req, params.into_iter().collect()).await
+}
+
+/// search features in multiple collections with a JSON request body
+async fn search_post(
+    inventory: web::Data<Inventory>,
+    server_cfg: web::Data<FeatureServerCfg>,
+    req: HttpRequest,
+    body: web::Json<serde_json::Map<String, serde_json::Value>>,
+) -> Result<HttpResponse, Error> {
+    // Arrays like `bbox` and `collections` are converted into comma separated lists
+    let param = |value: serde_json::Value| match value {
+        serde_json::Value::String(v) => v,
+        v => v.to_string(),
+    };
+    let params = body
+        .into_inner()
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Array(values) => {
+                    values.into_iter().map(param).collect::<Vec<_>>().join(",")
+                }
+                value => param(value),
+            };
+            (key, value)
+        })
+        .collect();
+    search_response(&inventory, &server_cfg, &req, params).await
+}
+
+async fn search_response(
+    inventory: &Inventory,
+    server_cfg: &FeatureServerCfg,
+    req: &HttpRequest,
+    params: HashMap<String, String>,
+) -> Result<HttpResponse, Error> {
+    let mut filters: HashMap<String, String> = params
+        .into_iter()
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect();
+    filters.remove("f");
+    let identity = Identity::from_request(req);
+    let collection_ids: Vec<String> = match filters.remove("collections") {
+        Some(ids) => ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => {
+            // Sorted for stable paging
+            let mut ids: Vec<String> = inventory
+                .collections(identity.as_ref())
+                .into_iter()
+                .map(|collection| collection.id)
+                .collect();
+            ids.sort();
+            ids
+        }
+    };
+    if let Some(id) = collection_ids.iter().find(|id| {
+        inventory.core_collection(id).is_none() || !inventory.can_read(id, identity.as_ref())
+    }) {
+        return Ok(HttpResponse::NotFound().body(format!("Collection `{id}` not found")));
+    }
+    let mut parse_param = |name: &str| filters.remove(name).map(|v| v.parse::<u32>()).transpose();
+    let (Ok(limit), Ok(offset)) = (parse_param("limit"), parse_param("offset")) else {
+        return Ok(HttpResponse::BadRequest().body("Invalid limit or offset"));
+    };
+    let skip_geometry = match filters
+        .remove("skipgeometry")
+        .map(|v| v.parse())
+        .transpose()
+    {
+        Ok(skip_geometry) => skip_geometry,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(format!("Invalid skipGeometry: {e}"))),
+    };
+    // Search pages are navigated by offset
+    filters.remove("cursor");
+    // Parameters of the STAC item search, which are not property filters
+    if let Some(param) = ["ids", "intersects"]
+        .into_iter()
+        .find(|param| filters.contains_key(*param))
+    {
+        return Ok(HttpResponse::BadRequest().body(format!("Parameter `{param}` is not supported")));
+    }
+    let fp = FilterParams {
+        limit: server_cfg.limit(limit),
+        offset,
+        bbox: filters.remove("bbox"),
+        datetime: filters.remove("datetime"),
+        q: filters.remove("q"),
+        filter: filters.remove("filter"),
+        filter_lang: filters.remove("filter-lang"),
+        filter_crs: filters.remove("filter-crs"),
+        crs: filters.remove("crs"),
+        bbox_crs: filters.remove("bbox-crs"),
+        sortby: filters.remove("sortby"),
+        properties: filters.remove("properties"),
+        skip_geometry,
+        filters,
+        username: identity.map(|identity| identity.username),
+        ..Default::default()
+    };
+    if let Err(e) = fp.bbox() {
+        return Ok(HttpResponse::BadRequest().body(format!("Invalid bbox: {e}")));
+    }
+    if let Err(e) = fp.temporal() {
+        return Ok(HttpResponse::BadRequest().body(format!("Invalid datetime: {e}")));
+    }
+    if let Err(e) = fp.cql2_filter().and(fp.filter_srid()) {
+        return Ok(HttpResponse::BadRequest().body(format!("Invalid filter: {e}")));
+    }
+    match inventory.search_items(&collection_ids, &fp).await {
+        Ok(features) => Ok(HttpResponse::Ok()
+            .content_type("application/geo+json")
+            .json(features)),
+        Err(e) => Ok(items_error_response(e)),
+    }
+}
+
+/// stream feature changes as Server-Sent Events
+async fn changes(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    collection_id: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
+    let params: HashMap<String, String> =
+        serde_urlencoded::from_str(req.query_string()).unwrap_or_default();
+    let with_features = match params.get("features").map(|v| v.parse()).transpose() {
+        Ok(with_features) => with_features.unwrap_or(false),
+        Err(e) => return Ok(HttpResponse::BadRequest().body(format!("Invalid features: {e}"))),
+    };
+    let filter = FilterParams {
+        bbox: params.get("bbox").cloned(),
+        username: Identity::from_request(&req).map(|identity| identity.username),
+        ..Default::default()
+    };
+    if let Err(e) = filter.bbox() {
+        return Ok(HttpResponse::BadRequest().body(format!("Invalid bbox: {e}")));
+    }
+    if let Some(body) = inventory.collection_changes_stream(&collection_id, &filter, with_features)
+    {
+        Ok(HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(header::CacheControl(vec![header::CacheDirective::NoCache]))
+            .streaming(body))
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
 /// fetch features
 async fn features(
     inventory: web::Data<Inventory>,
+    server_cfg: web::Data<FeatureServerCfg>,
     req: HttpRequest,
     collection_id: web::Path<String>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
     if let Some(collection) = inventory.core_collection(&collection_id) {
         let mut filters: HashMap<String, String> =
             match serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string()) {
@@ -103,8 +321,30 @@ async fn features(
                 Err(_e) => return Ok(HttpResponse::BadRequest().finish()),
             };
 
+        let Some(format) = items_format(&req, filters.remove("f").as_deref()).await else {
+            return Ok(HttpResponse::NotAcceptable().finish());
+        };
         let bbox = filters.remove("bbox");
         let datetime = filters.remove("datetime");
+        let q = filters.remove("q");
+        let filter = filters.remove("filter");
+        let filter_lang = filters.remove("filter-lang");
+        let filter_crs = filters.remove("filter-crs");
+        let crs = filters.remove("crs");
+        let bbox_crs = filters.remove("bbox-crs");
+        let cursor = filters.remove("cursor");
+        let sortby = filters.remove("sortby");
+        let properties = filters.remove("properties");
+        let skip_geometry = match filters
+            .remove("skipgeometry")
+            .map(|v| v.parse())
+            .transpose()
+        {
+            Ok(skip_geometry) => skip_geometry,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().body(format!("Invalid skipGeometry: {e}")))
+            }
+        };
 
         let offset = if let Some(offset_str) = filters.get("offset") {
             match offset_str.parse::<u32>() {
@@ -131,26 +371,145 @@ async fn features(
 
         let fp = FilterParams {
             offset,
-            limit,
+            limit: server_cfg.limit(limit),
+            cursor,
             bbox,
             datetime,
             filters,
+            q,
+            filter,
+            filter_lang,
+            filter_crs,
+            crs,
+            bbox_crs,
+            sortby,
+            properties,
+            skip_geometry,
+            username: Identity::from_request(&req).map(|identity| identity.username),
         };
+        if let Err(e) = fp.cursor() {
+            return Ok(HttpResponse::BadRequest().body(e.to_string()));
+        }
+        if fp.q.is_some() && !inventory.collection_text_search(&collection_id) {
+            return Ok(HttpResponse::BadRequest().body("Collection does not support `q`"));
+        }
+        if let Err(e) = inventory.check_collection_parameters(&collection_id, &fp) {
+            return Ok(HttpResponse::BadRequest().body(e.to_string()));
+        }
+        if let Err(e) = fp.cql2_filter().and(fp.filter_srid()) {
+            return Ok(HttpResponse::BadRequest().body(format!("Invalid filter: {e}")));
+        }
+        let content_crs = match fp.crs_srid().and_then(|crs| response_crs(collection, crs)) {
+            Ok(crs) => crs,
+            Err(e) => return Ok(HttpResponse::BadRequest().body(format!("Invalid crs: {e}"))),
+        };
+        if fp.bbox_crs.is_some() {
+            if let Err(e) = fp
+                .bbox_srid()
+                .and_then(|srid| response_crs(collection, Some(srid)))
+            {
+                return Ok(HttpResponse::BadRequest().body(format!("Invalid bbox-crs: {e}")));
+            }
+        }
 
-        if let Some(features) = inventory.collection_items(&collection_id, &fp).await {
-            if html_accepted(&req).await {
-                render_endpoint(
-                    &TEMPLATES,
-                    "features.html",
-                    context!(cur_menu=>"Collections", base_url => inventory.base_url(), collection => &collection, features => &features),
-                ).await
-            } else {
-                Ok(HttpResponse::Ok()
-                    .content_type("application/geo+json")
-                    .json(features))
+        match fp.sortby() {
+            Ok(keys) if !keys.is_empty() => {
+                let sortables = inventory.collection_sortables(&collection_id).await;
+                if let Some(key) = keys.iter().find(|key| {
+                    !matches!(&sortables, Some(sortables) if sortables.properties.contains_key(&key.property))
+                }) {
+                    return Ok(HttpResponse::BadRequest().body(format!(
+                        "Invalid sortby: property `{}` is not sortable",
+                        key.property
+                    )));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+        }
+
+        match format {
+            ItemsFormat::Html => {
+                match inventory.collection_items(&collection_id, &fp).await {
+                    Ok(features) => render_endpoint(
+                        &TEMPLATES,
+                        "features.html",
+                        context!(cur_menu=>"Collections", base_url => inventory.base_url(), collection => &collection, features => &features),
+                    ).await,
+                    Err(e) => Ok(items_error_response(e)),
+                }
+            }
+            ItemsFormat::GeoJson | ItemsFormat::GeoJsonSeq | ItemsFormat::Csv => {
+                match inventory
+                    .collection_items_stream(&collection_id, &fp, format)
+                    .await
+                {
+                    Ok(body) => {
+                        let mut response = HttpResponse::Ok();
+                        if let Some(crs) = content_crs {
+                            response.insert_header(("Content-Crs", format!("<{crs}>")));
+                        }
+                        Ok(response.content_type(format.content_type()).streaming(body))
+                    }
+                    Err(e) => Ok(items_error_response(e)),
+                }
+            }
+            ItemsFormat::GeoPackage => {
+                let features = match inventory.collection_items(&collection_id, &fp).await {
+                    Ok(features) => features,
+                    Err(e) => return Ok(items_error_response(e)),
+                };
+                let srid = content_crs
+                    .as_deref()
+                    .and_then(srid_from_crs_uri)
+                    .unwrap_or(4326);
+                let srs_definition = inventory
+                    .collection_srs_definition(&collection_id, srid)
+                    .await;
+                match features_to_gpkg(&collection_id, &features, srid, srs_definition)
+                    .await
+                    .and_then(|gpkg| gpkg.into_stream())
+                {
+                    Ok(body) => Ok(HttpResponse::Ok()
+                        .content_type(format.content_type())
+                        .insert_header((
+                            "Content-Disposition",
+                            format!(r#"attachment; filename="{collection_id}.gpkg""#),
+                        ))
+                        .streaming(body)),
+                    Err(e) => {
+                        error!("GeoPackage output error: {e}");
+                        Ok(HttpResponse::InternalServerError().finish())
+                    }
+                }
+            }
+            #[cfg(feature = "flatgeobuf")]
+            ItemsFormat::FlatGeobuf => {
+                let features = match inventory.collection_items(&collection_id, &fp).await {
+                    Ok(features) => features,
+                    Err(e) => return Ok(items_error_response(e)),
+                };
+                let srid = content_crs
+                    .as_deref()
+                    .and_then(srid_from_crs_uri)
+                    .unwrap_or(4326);
+                // Spatial index requires all features, which is only reasonable for bounded result sets
+                let write_index = fp.limit_or_default() > 0;
+                match crate::output::fgb::features_to_fgb(
+                    &collection_id,
+                    &features,
+                    srid,
+                    write_index,
+                ) {
+                    Ok(fgb) => Ok(HttpResponse::Ok()
+                        .content_type(format.content_type())
+                        .body(fgb)),
+                    Err(e) => {
+                        error!("FlatGeobuf output error: {e}");
+                        Ok(HttpResponse::InternalServerError().finish())
+                    }
+                }
             }
-        } else {
-            Ok(HttpResponse::NotFound().finish())
         }
     } else {
         Ok(HttpResponse::NotFound().finish())
@@ -164,9 +523,44 @@ async fn feature(
     path: web::Path<(String, String)>,
 ) -> Result<HttpResponse, Error> {
     let (collection_id, feature_id) = path.into_inner();
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, false) {
+        return Ok(response);
+    }
     if let Some(collection) = inventory.core_collection(&collection_id) {
+        let mut params: HashMap<String, String> =
+            serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| (key.to_lowercase(), value))
+                .collect();
+        let skip_geometry = match params.remove("skipgeometry").map(|v| v.parse()).transpose() {
+            Ok(skip_geometry) => skip_geometry,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().body(format!("Invalid skipGeometry: {e}")))
+            }
+        };
+        let fp = FilterParams {
+            crs: params.remove("crs"),
+            properties: params.remove("properties"),
+            skip_geometry,
+            // Request parameters of the collection query
+            filters: params,
+            username: Identity::from_request(&req).map(|identity| identity.username),
+            ..Default::default()
+        };
+        if let Err(e) = inventory.check_collection_parameters(&collection_id, &fp) {
+            return Ok(HttpResponse::BadRequest().body(e.to_string()));
+        }
+        let crs = match fp.crs_srid() {
+            Ok(crs) => crs,
+            Err(e) => return Ok(HttpResponse::BadRequest().body(format!("Invalid crs: {e}"))),
+        };
+        let content_crs = match response_crs(collection, crs) {
+            Ok(crs) => crs,
+            Err(e) => return Ok(HttpResponse::BadRequest().body(format!("Invalid crs: {e}"))),
+        };
         if let Some(feature) = inventory
-            .collection_item(inventory.href_prefix(), &collection_id, &feature_id)
+            .collection_item(inventory.href_prefix(), &collection_id, &feature_id, &fp)
             .await
         {
             if html_accepted(&req).await {
@@ -176,9 +570,17 @@ async fn feature(
                     context!(cur_menu=>"Collections", base_url => inventory.base_url(), collection => &collection, feature => &feature),
                 ).await
             } else {
-                Ok(HttpResponse::Ok()
-                    .content_type("application/geo+json")
-                    .json(feature))
+                let mut response = HttpResponse::Ok();
+                if let Some(etag) = inventory
+                    .collection_item_etag(&collection_id, &feature_id)
+                    .await
+                {
+                    response.insert_header(header::ETag(EntityTag::new_strong(etag)));
+                }
+                if let Some(crs) = content_crs {
+                    response.insert_header(("Content-Crs", format!("<{crs}>")));
+                }
+                Ok(response.content_type("application/geo+json").json(feature))
             }
         } else {
             Ok(HttpResponse::NotFound().finish())
@@ -188,6 +590,270 @@ async fn feature(
     }
 }
 
+/// CRS of response geometries, checked against the CRS supported by the collection
+fn response_crs(collection: &CoreCollection, crs: Option<i32>) -> error::Result<Option<String>> {
+    let Some(srid) = crs else {
+        return Ok(collection.crs.first().cloned());
+    };
+    let uri = crs_uri(srid);
+    if collection.crs.contains(&uri) {
+        Ok(Some(uri))
+    } else {
+        Err(error::Error::UnsupportedCrs(uri))
+    }
+}
+
+/// Items format requested with `f` parameter or `Accept` header
+async fn items_format(req: &HttpRequest, f: Option<&str>) -> Option<ItemsFormat> {
+    if let Some(f) = f {
+        return ItemsFormat::from_param(f);
+    }
+    if req.path().ends_with(".json") {
+        return Some(ItemsFormat::GeoJson);
+    }
+    if html_accepted(req).await {
+        return Some(ItemsFormat::Html);
+    }
+    let format = req
+        .get_header::<header::Accept>()
+        .and_then(|accept| ItemsFormat::from_media_type(accept.preference().essence_str()));
+    Some(format.unwrap_or(ItemsFormat::GeoJson))
+}
+
+/// add a feature to the collection
+async fn create_feature(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    collection_id: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, true) {
+        return Ok(response);
+    }
+    let feature = match parse_feature(&inventory, &req, &collection_id, &body) {
+        Ok(feature) => feature,
+        Err(e) => return Ok(transaction_error_response(e)),
+    };
+    match inventory
+        .create_collection_item(&collection_id, &feature)
+        .await
+    {
+        Ok(feature_id) => {
+            let location = format!(
+                "{}/collections/{collection_id}/items/{feature_id}",
+                inventory.href_prefix()
+            );
+            Ok(HttpResponse::Created()
+                .insert_header((header::LOCATION, location))
+                .finish())
+        }
+        Err(e) => Ok(transaction_error_response(e)),
+    }
+}
+
+/// replace the feature with id `featureId`
+async fn replace_feature(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let (collection_id, feature_id) = path.into_inner();
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, true) {
+        return Ok(response);
+    }
+    let feature = match parse_feature(&inventory, &req, &collection_id, &body) {
+        Ok(feature) => feature,
+        Err(e) => return Ok(transaction_error_response(e)),
+    };
+    let username = Identity::from_request(&req).map(|identity| identity.username);
+    let result = inventory
+        .replace_collection_item(
+            &collection_id,
+            &feature_id,
+            &feature,
+            if_match(&req).as_deref(),
+            username.as_deref(),
+        )
+        .await;
+    transaction_response(&inventory, &collection_id, &feature_id, result).await
+}
+
+/// update properties and geometry of the feature with id `featureId`
+async fn update_feature(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let (collection_id, feature_id) = path.into_inner();
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, true) {
+        return Ok(response);
+    }
+    let feature = match parse_feature(&inventory, &req, &collection_id, &body) {
+        Ok(feature) => feature,
+        Err(e) => return Ok(transaction_error_response(e)),
+    };
+    let username = Identity::from_request(&req).map(|identity| identity.username);
+    let result = inventory
+        .update_collection_item(
+            &collection_id,
+            &feature_id,
+            &feature,
+            if_match(&req).as_deref(),
+            username.as_deref(),
+        )
+        .await;
+    transaction_response(&inventory, &collection_id, &feature_id, result).await
+}
+
+/// delete the feature with id `featureId`
+async fn delete_feature(
+    inventory: web::Data<Inventory>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (collection_id, feature_id) = path.into_inner();
+    if let Some(response) = access_denied(&inventory, &req, &collection_id, true) {
+        return Ok(response);
+    }
+    let username = Identity::from_request(&req).map(|identity| identity.username);
+    match inventory
+        .delete_collection_item(
+            &collection_id,
+            &feature_id,
+            if_match(&req).as_deref(),
+            username.as_deref(),
+        )
+        .await
+    {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Ok(transaction_error_response(e)),
+    }
+}
+
+/// Error response, if the logged in user has no read access or no write access (with `write`)
+/// to the collection. Collections without read access are reported as not found.
+fn access_denied(
+    inventory: &Inventory,
+    req: &HttpRequest,
+    collection_id: &str,
+    write: bool,
+) -> Option<HttpResponse> {
+    let identity = Identity::from_request(req);
+    if !inventory.can_read(collection_id, identity.as_ref()) {
+        return Some(HttpResponse::NotFound().finish());
+    }
+    if write && !inventory.can_write(collection_id, identity.as_ref()) {
+        return Some(if identity.is_some() {
+            HttpResponse::Forbidden().finish()
+        } else {
+            HttpResponse::Unauthorized().finish()
+        });
+    }
+    None
+}
+
+fn parse_feature(
+    inventory: &Inventory,
+    req: &HttpRequest,
+    collection_id: &str,
+    body: &[u8],
+) -> error::Result<EditFeature> {
+    let json: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| error::Error::InvalidFeature(e.to_string()))?;
+    let mut feature = EditFeature::from_geojson(&json)?;
+    feature.srid = content_crs(inventory, req, collection_id)?;
+    Ok(feature)
+}
+
+/// EPSG code of `Content-Crs` header, which has to be one of the collection CRS
+fn content_crs(
+    inventory: &Inventory,
+    req: &HttpRequest,
+    collection_id: &str,
+) -> error::Result<Option<i32>> {
+    let Some(value) = req.headers().get("Content-Crs") else {
+        return Ok(None);
+    };
+    let uri = value
+        .to_str()
+        .unwrap_or_default()
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    let srid = srid_from_crs_uri(uri).ok_or(error::Error::UnsupportedCrs(uri.to_string()))?;
+    match inventory.core_collection(collection_id) {
+        Some(collection) if !collection.crs.contains(&crs_uri(srid)) => {
+            Err(error::Error::UnsupportedCrs(uri.to_string()))
+        }
+        _ => Ok(Some(srid)),
+    }
+}
+
+/// Entity tag of `If-Match` header
+fn if_match(req: &HttpRequest) -> Option<String> {
+    match req.get_header::<header::IfMatch>()? {
+        header::IfMatch::Any => None,
+        header::IfMatch::Items(tags) => tags.first().map(|tag| tag.tag().to_string()),
+    }
+}
+
+async fn transaction_response(
+    inventory: &Inventory,
+    collection_id: &str,
+    feature_id: &str,
+    result: error::Result<()>,
+) -> Result<HttpResponse, Error> {
+    match result {
+        Ok(()) => {
+            let mut response = HttpResponse::NoContent();
+            if let Some(etag) = inventory
+                .collection_item_etag(collection_id, feature_id)
+                .await
+            {
+                response.insert_header(header::ETag(EntityTag::new_strong(etag)));
+            }
+            Ok(response.finish())
+        }
+        Err(e) => Ok(transaction_error_response(e)),
+    }
+}
+
+/// Invalid filters or query parameters are client errors
+fn items_error_response(e: error::Error) -> HttpResponse {
+    match e {
+        error::Error::QueryParams
+        | error::Error::FilterError(_)
+        | error::Error::UnsupportedCrs(_)
+        | error::Error::InvalidSortby(_)
+        | error::Error::InvalidCursor
+        | error::Error::InvalidParameter(_) => HttpResponse::BadRequest().body(e.to_string()),
+        _ => HttpResponse::NotFound().finish(),
+    }
+}
+
+fn transaction_error_response(e: error::Error) -> HttpResponse {
+    match e {
+        error::Error::CollectionNotFound(_) | error::Error::FeatureNotFound => {
+            HttpResponse::NotFound().finish()
+        }
+        error::Error::TransactionsNotSupported => HttpResponse::MethodNotAllowed().finish(),
+        error::Error::PreconditionFailed => HttpResponse::PreconditionFailed().finish(),
+        error::Error::InvalidFeature(_) | error::Error::UnsupportedCrs(_) => {
+            HttpResponse::BadRequest().body(e.to_string())
+        }
+        error::Error::DbError(sqlx::Error::Database(ref db_error)) => {
+            warn!("Transaction failed: {db_error}");
+            HttpResponse::BadRequest().body(format!("Invalid feature - {db_error}"))
+        }
+        e => {
+            error!("Transaction failed: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 #[cfg(feature = "html")]
 #[derive(rust_embed::RustEmbed)]
 #[folder = "templates/"]
@@ -201,6 +867,7 @@ static TEMPLATES: Lazy<Environment<'static>> = Lazy::new(create_env_embedded::<T
 impl ServiceEndpoints for FeatureService {
     fn register_endpoints(&self, cfg: &mut web::ServiceConfig) {
         cfg.app_data(web::Data::new(self.inventory.clone()))
+            .app_data(web::Data::new(self.server_cfg.clone()))
             .service(web::resource("/collections").route(web::get().to(collections)))
             .service(web::resource("/collections.json").route(web::get().to(collections)))
             .service(
@@ -216,7 +883,24 @@ impl ServiceEndpoints for FeatureService {
                     .route(web::get().to(queryables)),
             )
             .service(
-                web::resource("/collections/{collectionId}/items").route(web::get().to(features)),
+                web::resource("/collections/{collectionId}/sortables")
+                    .route(web::get().to(sortables)),
+            )
+            .service(
+                web::resource("/collections/{collectionId}/schema").route(web::get().to(schema)),
+            )
+            .service(
+                web::resource("/search")
+                    .route(web::get().to(search))
+                    .route(web::post().to(search_post)),
+            )
+            .service(
+                web::resource("/collections/{collectionId}/changes").route(web::get().to(changes)),
+            )
+            .service(
+                web::resource("/collections/{collectionId}/items")
+                    .route(web::get().to(features))
+                    .route(web::post().to(create_feature)),
             )
             .service(
                 web::resource("/collections/{collectionId}/items.json")
@@ -228,7 +912,10 @@ impl ServiceEndpoints for FeatureService {
             )
             .service(
                 web::resource("/collections/{collectionId}/items/{featureId}")
-                    .route(web::get().to(feature)),
+                    .route(web::get().to(feature))
+                    .route(web::put().to(replace_feature))
+                    .route(web::patch().to(update_feature))
+                    .route(web::delete().to(delete_feature)),
             );
     }
 }
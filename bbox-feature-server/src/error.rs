@@ -8,13 +8,42 @@ pub enum Error {
     GeometryFormatError,
     #[error("datasource setup error - {0}")]
     DatasourceSetupError(String),
+    #[error("datasource error - {0}")]
+    DatasourceError(String),
     #[error("datasource `{0}` not found")]
     DatasourceNotFound(String),
+    #[error("collection `{0}` not found")]
+    CollectionNotFound(String),
     // Database errors
     #[error(transparent)]
     DbError(#[from] sqlx::Error),
     #[error("Query parameters error")]
     QueryParams,
+    #[error("filter error - {0}")]
+    FilterError(#[from] crate::cql2::Error),
+    #[error("unsupported CRS `{0}`")]
+    UnsupportedCrs(String),
+    #[error("invalid sortby - {0}")]
+    InvalidSortby(String),
+    #[error("invalid parameter - {0}")]
+    InvalidParameter(String),
+    #[error("invalid cursor")]
+    InvalidCursor,
+    #[error("items stream closed")]
+    ItemsStreamClosed,
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[error("output format error - {0}")]
+    OutputFormatError(String),
+    // Transaction errors
+    #[error("collection does not support transactions")]
+    TransactionsNotSupported,
+    #[error("feature not found")]
+    FeatureNotFound,
+    #[error("feature has been modified")]
+    PreconditionFailed,
+    #[error("invalid feature - {0}")]
+    InvalidFeature(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,3 +1,5 @@
+use crate::cql2;
+use crate::error::{self, Error};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -6,10 +8,44 @@ pub struct FilterParams {
     // Pagination
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    /// Keyset pagination token
+    pub cursor: Option<String>,
     // Filters
     pub bbox: Option<String>,
     pub datetime: Option<String>,
     pub filters: HashMap<String, String>,
+    /// Free-text search terms
+    pub q: Option<String>,
+    /// CQL2 filter expression
+    pub filter: Option<String>,
+    #[serde(rename = "filter-lang")]
+    pub filter_lang: Option<String>,
+    #[serde(rename = "filter-crs")]
+    pub filter_crs: Option<String>,
+    // Coordinate reference systems
+    /// CRS of response geometries
+    pub crs: Option<String>,
+    #[serde(rename = "bbox-crs")]
+    pub bbox_crs: Option<String>,
+    // Sorting
+    /// Sort properties (`+name,-population`)
+    pub sortby: Option<String>,
+    // Response content
+    /// Comma separated list of properties to return
+    pub properties: Option<String>,
+    #[serde(rename = "skipGeometry")]
+    pub skip_geometry: Option<bool>,
+    // Access control
+    /// Authenticated user for row-level access filters
+    #[serde(skip)]
+    pub username: Option<String>,
+}
+
+/// Property with sort direction of a `sortby` parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub property: String,
+    pub descending: bool,
 }
 
 #[derive(Debug)]
@@ -27,9 +63,16 @@ impl FilterParams {
         params.offset = Some(offset);
         params
     }
+    pub fn with_cursor(&self, cursor: String) -> FilterParams {
+        let mut params = self.clone();
+        params.offset = None;
+        params.cursor = Some(cursor);
+        params
+    }
     pub fn prev(&self) -> Option<FilterParams> {
         let offset = self.offset.unwrap_or(0);
-        if offset > 0 {
+        // Keyset pages can't be navigated backwards
+        if offset > 0 && self.cursor.is_none() {
             let prev = offset.saturating_sub(self.limit_or_default());
             Some(self.with_offset(prev))
         } else {
@@ -50,8 +93,28 @@ impl FilterParams {
             Some("".to_string()),
             self.limit.map(|v| format!("limit={v}")),
             self.offset.map(|v| format!("offset={v}")),
+            self.cursor.as_ref().map(|v| format!("cursor={v}")),
             self.bbox.as_ref().map(|v| format!("bbox={v}")),
             self.datetime.as_ref().map(|v| format!("datetime={v}")),
+            self.q
+                .as_ref()
+                .and_then(|v| serde_urlencoded::to_string([("q", v)]).ok()),
+            self.filter
+                .as_ref()
+                .and_then(|v| serde_urlencoded::to_string([("filter", v)]).ok()),
+            self.filter_lang
+                .as_ref()
+                .map(|v| format!("filter-lang={v}")),
+            self.filter_crs.as_ref().map(|v| format!("filter-crs={v}")),
+            self.crs.as_ref().map(|v| format!("crs={v}")),
+            self.bbox_crs.as_ref().map(|v| format!("bbox-crs={v}")),
+            self.sortby
+                .as_ref()
+                .and_then(|v| serde_urlencoded::to_string([("sortby", v)]).ok()),
+            self.properties
+                .as_ref()
+                .and_then(|v| serde_urlencoded::to_string([("properties", v)]).ok()),
+            self.skip_geometry.map(|v| format!("skipGeometry={v}")),
         ]
         .into_iter()
         .flatten()
@@ -98,9 +161,152 @@ impl FilterParams {
         }
         Ok(None)
     }
+    /// Any filter restricting the matching features
+    pub fn is_filtered(&self) -> bool {
+        self.bbox.is_some()
+            || self.datetime.is_some()
+            || !self.filters.is_empty()
+            || self.q.is_some()
+            || self.filter.is_some()
+    }
     pub fn other_params(&self) -> Result<&HashMap<String, String>, Box<dyn std::error::Error>> {
         Ok(&self.filters)
     }
+    /// Parsed CQL2 filter expression
+    pub fn cql2_filter(&self) -> cql2::Result<Option<cql2::Expr>> {
+        let Some(filter) = &self.filter else {
+            return Ok(None);
+        };
+        let expr = match self.filter_lang.as_deref() {
+            None | Some("cql2-text") => cql2::parse_text(filter)?,
+            Some("cql2-json") => cql2::parse_json(filter)?,
+            Some(lang) => return Err(cql2::Error::UnsupportedLanguage(lang.to_string())),
+        };
+        Ok(Some(expr))
+    }
+    /// SRID of geometries in filter expressions (Default: WGS84)
+    pub fn filter_srid(&self) -> cql2::Result<i32> {
+        if let Some(crs) = &self.filter_crs {
+            srid_from_crs_uri(crs).ok_or(cql2::Error::InvalidValue(crs.clone()))
+        } else {
+            Ok(4326)
+        }
+    }
+    /// SRID of requested response CRS
+    pub fn crs_srid(&self) -> error::Result<Option<i32>> {
+        parse_crs(self.crs.as_deref())
+    }
+    /// Sort key values of the last feature of the previous page
+    pub fn cursor(&self) -> error::Result<Option<Vec<serde_json::Value>>> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(None);
+        };
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(Error::InvalidCursor)?;
+        let values = serde_json::from_slice(&bytes).map_err(|_| Error::InvalidCursor)?;
+        Ok(Some(values))
+    }
+    /// SRID of `bbox` coordinates (Default: WGS84)
+    pub fn bbox_srid(&self) -> error::Result<i32> {
+        Ok(parse_crs(self.bbox_crs.as_deref())?.unwrap_or(4326))
+    }
+    /// Requested properties (`None` for all properties)
+    pub fn properties(&self) -> Option<Vec<&str>> {
+        self.properties.as_ref().map(|props| {
+            props
+                .split(',')
+                .map(str::trim)
+                .filter(|prop| !prop.is_empty())
+                .collect()
+        })
+    }
+    pub fn skip_geometry(&self) -> bool {
+        self.skip_geometry.unwrap_or(false)
+    }
+    /// Parsed `sortby` parameter
+    pub fn sortby(&self) -> error::Result<Vec<SortKey>> {
+        let Some(sortby) = &self.sortby else {
+            return Ok(Vec::new());
+        };
+        sortby
+            .split(',')
+            .map(|key| {
+                // An unescaped `+` is decoded as space
+                let key = key.trim();
+                let (property, descending) = match key.strip_prefix('-') {
+                    Some(property) => (property, true),
+                    None => (key.strip_prefix('+').unwrap_or(key), false),
+                };
+                if property.is_empty() {
+                    return Err(Error::InvalidSortby(format!(
+                        "empty property in `{sortby}`"
+                    )));
+                }
+                Ok(SortKey {
+                    property: property.to_string(),
+                    descending,
+                })
+            })
+            .collect()
+    }
+    /// Alternatives of the `q` parameter separated by commas, each with its lowercase
+    /// search terms. All terms of an alternative have to match.
+    pub fn search_terms(&self) -> Vec<Vec<String>> {
+        let Some(q) = &self.q else {
+            return Vec::new();
+        };
+        q.split(',')
+            .map(|alternative| {
+                alternative
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|term| !term.is_empty())
+                    .map(str::to_lowercase)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|terms| !terms.is_empty())
+            .collect()
+    }
+}
+
+fn parse_crs(crs: Option<&str>) -> error::Result<Option<i32>> {
+    crs.map(|uri| srid_from_crs_uri(uri).ok_or(Error::UnsupportedCrs(uri.to_string())))
+        .transpose()
+}
+
+/// Opaque `cursor` token of sort key values
+pub fn encode_cursor(values: &[serde_json::Value]) -> String {
+    serde_json::to_vec(values)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// CRS URI of EPSG code. WGS84 is returned as `CRS84` with longitude/latitude axis order.
+pub fn crs_uri(srid: i32) -> String {
+    if srid == 4326 {
+        "http://www.opengis.net/def/crs/OGC/1.3/CRS84".to_string()
+    } else {
+        format!("http://www.opengis.net/def/crs/EPSG/0/{srid}")
+    }
+}
+
+/// EPSG code from CRS URI like `http://www.opengis.net/def/crs/EPSG/0/2056`
+pub fn srid_from_crs_uri(uri: &str) -> Option<i32> {
+    let uri = uri.trim_start_matches('[').trim_end_matches(']');
+    if uri == "http://www.opengis.net/def/crs/OGC/1.3/CRS84" || uri == "OGC:CRS84" {
+        return Some(4326);
+    }
+    uri.strip_prefix("http://www.opengis.net/def/crs/EPSG/0/")
+        .or(uri.strip_prefix("EPSG:"))
+        .and_then(|code| code.parse().ok())
 }
 
 #[cfg(test)]
@@ -140,6 +346,178 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(filter.as_args(), "?ArbitraryField=Something");
+
+        let filter = FilterParams {
+            filter: Some("name = 'Rhein'".to_string()),
+            filter_lang: Some("cql2-text".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.as_args(),
+            "?filter=name+%3D+%27Rhein%27&filter-lang=cql2-text"
+        );
+    }
+
+    #[test]
+    fn cursor_pagination() {
+        let values = vec![serde_json::json!("Bern"), serde_json::json!(42)];
+        let filter = FilterParams {
+            limit: Some(10),
+            offset: Some(20),
+            ..Default::default()
+        };
+        let next = filter.with_cursor(encode_cursor(&values));
+        assert_eq!(next.offset, None);
+        assert_eq!(next.cursor().unwrap(), Some(values));
+        assert!(next.prev().is_none());
+        assert!(next.as_args().starts_with("?limit=10&cursor="));
+
+        let filter = FilterParams {
+            cursor: Some("5b3".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.cursor().is_err());
+        let filter = FilterParams {
+            cursor: Some("zz".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.cursor().is_err());
+    }
+
+    #[test]
+    fn cql2_filter_parse() {
+        let filter = FilterParams {
+            filter: Some("name = 'Rhein'".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.cql2_filter().unwrap().is_some());
+
+        let filter = FilterParams {
+            filter: Some(r#"{"op":"=","args":[{"property":"name"},"Rhein"]}"#.to_string()),
+            filter_lang: Some("cql2-json".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.cql2_filter().unwrap().is_some());
+
+        let filter = FilterParams {
+            filter: Some("name = 'Rhein'".to_string()),
+            filter_lang: Some("ecql".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.cql2_filter().is_err());
+
+        assert!(FilterParams::default().cql2_filter().unwrap().is_none());
+    }
+
+    #[test]
+    fn sortby_parse() {
+        let filter = FilterParams {
+            sortby: Some("+name,-scalerank, labelrank".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.sortby().unwrap(),
+            vec![
+                SortKey {
+                    property: "name".to_string(),
+                    descending: false
+                },
+                SortKey {
+                    property: "scalerank".to_string(),
+                    descending: true
+                },
+                SortKey {
+                    property: "labelrank".to_string(),
+                    descending: false
+                },
+            ]
+        );
+        assert_eq!(
+            filter.as_args(),
+            "?sortby=%2Bname%2C-scalerank%2C+labelrank"
+        );
+
+        let filter = FilterParams {
+            sortby: Some("name,-".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.sortby().is_err());
+
+        assert!(FilterParams::default().sortby().unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_terms_parse() {
+        let filter = FilterParams {
+            q: Some("Bahnhofstr. 12, St.Gallen,".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.search_terms(),
+            vec![vec!["bahnhofstr", "12"], vec!["st", "gallen"]]
+        );
+        assert_eq!(filter.as_args(), "?q=Bahnhofstr.+12%2C+St.Gallen%2C");
+
+        assert!(FilterParams::default().search_terms().is_empty());
+    }
+
+    #[test]
+    fn properties_parse() {
+        let filter = FilterParams {
+            properties: Some("name, scalerank,".to_string()),
+            skip_geometry: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(filter.properties(), Some(vec!["name", "scalerank"]));
+        assert!(filter.skip_geometry());
+        assert_eq!(
+            filter.as_args(),
+            "?properties=name%2C+scalerank%2C&skipGeometry=true"
+        );
+
+        let filter = FilterParams {
+            properties: Some("".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.properties(), Some(vec![]));
+        assert_eq!(FilterParams::default().properties(), None);
+        assert!(!FilterParams::default().skip_geometry());
+    }
+
+    #[test]
+    fn crs_uri_parse() {
+        assert_eq!(
+            srid_from_crs_uri("http://www.opengis.net/def/crs/OGC/1.3/CRS84"),
+            Some(4326)
+        );
+        assert_eq!(
+            srid_from_crs_uri("http://www.opengis.net/def/crs/EPSG/0/2056"),
+            Some(2056)
+        );
+        assert_eq!(srid_from_crs_uri("[EPSG:3857]"), Some(3857));
+        assert_eq!(srid_from_crs_uri("http://example.com/crs/2056"), None);
+
+        assert_eq!(
+            crs_uri(4326),
+            "http://www.opengis.net/def/crs/OGC/1.3/CRS84"
+        );
+        assert_eq!(crs_uri(2056), "http://www.opengis.net/def/crs/EPSG/0/2056");
+
+        let filter = FilterParams {
+            crs: Some("http://www.opengis.net/def/crs/EPSG/0/2056".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.crs_srid().unwrap(), Some(2056));
+        assert_eq!(filter.bbox_srid().unwrap(), 4326);
+        assert_eq!(
+            filter.as_args(),
+            "?crs=http://www.opengis.net/def/crs/EPSG/0/2056"
+        );
+        let filter = FilterParams {
+            bbox_crs: Some("urn:ogc:def:crs:EPSG::2056".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.bbox_srid().is_err());
     }
 
     #[test]
@@ -1,11 +1,23 @@
-use crate::config::CollectionsCfg;
-use crate::datasource::{gpkg::SqliteDatasource, AutoscanCollectionDatasource, CollectionSource};
+use crate::config::{AccessCfg, CollectionsCfg};
+use crate::datasource::{
+    gpkg::{geojson_bbox, SqliteDatasource},
+    AutoscanCollectionDatasource, ChangeOp, CollectionSource, EditFeature, FeatureChange,
+    ItemsResult, ITEMS_CHANNEL_SIZE,
+};
+use crate::error::{Error, Result};
 use crate::filter_params::FilterParams;
+use crate::output::ItemsFormat;
+use actix_web::web::Bytes;
+use bbox_core::auth::Identity;
 use bbox_core::file_search;
 use bbox_core::ogcapi::*;
 use bbox_core::pg_ds::PgDatasource;
+use futures::channel::{mpsc, oneshot};
+use futures::{future, stream, FutureExt, Stream, StreamExt};
 use log::{info, warn};
+use serde::Serialize;
 use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
 
 // ┌──────────────┐      ┌─────────────┐
 // │              │1    n│             │
@@ -25,6 +37,8 @@ use std::collections::HashMap;
 pub struct Inventory {
     // Key: collection_id
     feat_collections: HashMap<String, FeatureCollection>,
+    // Key: collection_id
+    access: HashMap<String, AccessCfg>,
     base_url: String,
 }
 
@@ -46,6 +60,7 @@ impl Inventory {
         );
         Inventory {
             feat_collections: HashMap::new(),
+            access: HashMap::new(),
             base_url,
         }
     }
@@ -84,6 +99,36 @@ impl Inventory {
                 }
             }
         }
+        #[cfg(feature = "flatgeobuf")]
+        for dir_ds in &config.directory {
+            for path in file_search::search(&dir_ds.dir, "*.fgb") {
+                match crate::datasource::fgb::file_collection(&path, inventory.href_prefix()) {
+                    Ok(collection) => inventory.add_collection(collection),
+                    Err(e) => warn!("Failed to open '{}': {e}", path.display()),
+                }
+            }
+        }
+        #[cfg(feature = "geoparquet")]
+        for dir_ds in &config.directory {
+            for path in file_search::search(&dir_ds.dir, "*.parquet") {
+                match crate::datasource::geoparquet::file_collection(&path, inventory.href_prefix())
+                {
+                    Ok(collection) => inventory.add_collection(collection),
+                    Err(e) => warn!("Failed to open '{}': {e}", path.display()),
+                }
+            }
+        }
+        for dir_ds in &config.directory {
+            let files = file_search::search(&dir_ds.dir, "*.geojson");
+            #[cfg(feature = "shapefile")]
+            let files = [files, file_search::search(&dir_ds.dir, "*.shp")].concat();
+            for path in files {
+                match crate::datasource::memstore::file_collection(&path, inventory.href_prefix()) {
+                    Ok(collection) => inventory.add_collection(collection),
+                    Err(e) => warn!("Failed to open '{}': {e}", path.display()),
+                }
+            }
+        }
         for cfg in &config.postgis {
             match PgDatasource::from_config(cfg, None).await {
                 Ok(mut ds) => {
@@ -118,10 +163,32 @@ impl Inventory {
         }
     }
 
-    /// Return all collections as vector
-    pub fn collections(&self) -> Vec<CoreCollection> {
+    /// Restrict access to collection
+    pub fn set_access(&mut self, collection_id: &str, access: AccessCfg) {
+        self.access.insert(collection_id.to_string(), access);
+    }
+
+    /// Read access to collection. Collections without access rules are public.
+    pub fn can_read(&self, collection_id: &str, identity: Option<&Identity>) -> bool {
+        match self.access.get(collection_id) {
+            Some(access) => access.can_read(identity),
+            None => true,
+        }
+    }
+
+    /// Write access to collection. Collections without access rules are writable by everyone.
+    pub fn can_write(&self, collection_id: &str, identity: Option<&Identity>) -> bool {
+        match self.access.get(collection_id) {
+            Some(access) => access.can_write(identity),
+            None => true,
+        }
+    }
+
+    /// Return all collections readable by `identity` as vector
+    pub fn collections(&self, identity: Option<&Identity>) -> Vec<CoreCollection> {
         self.feat_collections
             .values()
+            .filter(|fc| self.can_read(&fc.collection.id, identity))
             .map(|fc| fc.collection.clone())
             .collect()
     }
@@ -140,65 +207,195 @@ impl Inventory {
         &self,
         collection_id: &str,
         filter: &FilterParams,
-    ) -> Option<CoreFeatures> {
+    ) -> Result<CoreFeatures> {
         let Some(fc) = self.collection(collection_id) else {
-            warn!("Ignoring error getting collection {collection_id}");
-            return None;
+            return Err(Error::CollectionNotFound(collection_id.to_string()));
         };
-        let items = match fc.source.items(filter).await {
-            Ok(items) => items,
-            Err(e) => {
-                warn!("Ignoring error getting collection items for {collection_id}: {e}");
-                return None;
+        let items = fc.source.items(filter).await.map_err(|e| {
+            warn!("Error getting collection items for {collection_id}: {e}");
+            e
+        })?;
+        Ok(items_response(
+            self.href_prefix(),
+            collection_id,
+            filter,
+            items,
+        ))
+    }
+
+    /// Items response streamed while features are read from the datasource
+    pub async fn collection_items_stream(
+        &self,
+        collection_id: &str,
+        filter: &FilterParams,
+        format: ItemsFormat,
+    ) -> Result<impl Stream<Item = Result<Bytes>>> {
+        let Some(fc) = self.collection(collection_id) else {
+            return Err(Error::CollectionNotFound(collection_id.to_string()));
+        };
+        let Some(mut encoder) = format.encoder() else {
+            return Err(Error::OutputFormatError(format!(
+                "{format:?} does not support streaming"
+            )));
+        };
+        let (sender, receiver) = mpsc::channel(ITEMS_CHANNEL_SIZE);
+        let (result_sender, result_receiver) = oneshot::channel();
+        let source = fc.source.clone();
+        let source_filter = filter.clone();
+        actix_web::rt::spawn(async move {
+            let result = source.send_items(&source_filter, sender).await;
+            result_sender.send(result).ok();
+        });
+        let done = result_receiver
+            .map(|result| ItemsEvent::Done(result.unwrap_or(Err(Error::ItemsStreamClosed))));
+        let mut events = receiver
+            .map(|feature| ItemsEvent::Feature(Box::new(feature)))
+            .chain(stream::once(done));
+        // Errors before the first feature are reported like in non-streaming requests
+        let first = match events.next().await {
+            Some(ItemsEvent::Done(Err(e))) => {
+                warn!("Error getting collection items for {collection_id}: {e}");
+                return Err(e);
             }
+            Some(first) => first,
+            None => return Err(Error::ItemsStreamClosed),
         };
-        let base_url = self.href_prefix();
-        let mut features = CoreFeatures {
-            type_: "FeatureCollection".to_string(),
-            links: vec![
-                ApiLink {
-                    href: format!("{base_url}/collections/{collection_id}/items"),
-                    rel: Some("self".to_string()),
-                    type_: Some("text/html".to_string()),
-                    title: Some("this document".to_string()),
-                    hreflang: None,
-                    length: None,
-                },
-                ApiLink {
-                    href: format!("{base_url}/collections/{collection_id}/items.json"),
-                    rel: Some("self".to_string()),
-                    type_: Some("application/geo+json".to_string()),
-                    title: Some("this document".to_string()),
-                    hreflang: None,
-                    length: None,
-                },
-            ],
-            time_stamp: None, // time when the response was generated
-            number_matched: Some(items.number_matched),
-            number_returned: Some(items.number_returned),
-            features: items.features,
-        };
-        if items.number_matched > items.number_returned {
-            let mut add_link = |link: FilterParams, rel: &str| {
-                let params = link.as_args();
-                features.links.push(ApiLink {
-                    href: format!("{base_url}/collections/{collection_id}/items{params}"),
-                    rel: Some(rel.to_string()),
-                    type_: Some("text/html".to_string()),
-                    title: Some(rel.to_string()),
-                    hreflang: None,
-                    length: None,
-                });
-            };
+        let base_url = self.href_prefix().to_string();
+        let collection_id = collection_id.to_string();
+        let filter = filter.clone();
+        let body = stream::once(future::ready(first))
+            .chain(events)
+            .map(move |event| {
+                let mut buf = Vec::new();
+                match event {
+                    ItemsEvent::Feature(feature) => encoder.feature(&feature, &mut buf)?,
+                    ItemsEvent::Done(result) => {
+                        let response = items_response(&base_url, &collection_id, &filter, result?);
+                        encoder.finish(response, &mut buf)?;
+                    }
+                }
+                Ok(Bytes::from(buf))
+            });
+        Ok(body)
+    }
 
+    /// Features of multiple collections, paged over the results concatenated in collection order.
+    /// Collections without queryables for all attribute filters or with invalid query parameters are skipped.
+    /// Errors of the datasources fail the search.
+    pub async fn search_items(
+        &self,
+        collection_ids: &[String],
+        filter: &FilterParams,
+    ) -> Result<CoreFeatures> {
+        let limit = filter.limit_or_default() as usize;
+        let mut offset = filter.offset.unwrap_or(0) as u64;
+        let mut features = Vec::new();
+        let mut number_matched = Some(0);
+        for collection_id in collection_ids {
+            let Some(fc) = self.collection(collection_id) else {
+                warn!("Ignoring error getting collection {collection_id}");
+                continue;
+            };
+            if filter.q.is_some() && !fc.source.text_search() {
+                continue;
+            }
+            if fc.source.check_parameters(filter).is_err() {
+                continue;
+            }
+            if !filter.filters.is_empty() {
+                let queryables = self.collection_queryables(collection_id).await;
+                if !filter.filters.keys().all(|key| {
+                    matches!(&queryables, Some(queryables) if queryables.properties.contains_key(key))
+                }) {
+                    continue;
+                }
+            }
+            let page_full = limit > 0 && features.len() >= limit;
+            let collection_filter = FilterParams {
+                offset: Some(offset as u32),
+                // Query a single feature of remaining collections for numberMatched
+                limit: Some(if page_full {
+                    1
+                } else if limit == 0 {
+                    0
+                } else {
+                    (limit - features.len()) as u32
+                }),
+                ..filter.clone()
+            };
+            let items = fc.source.items(&collection_filter).await.map_err(|e| {
+                warn!("Error getting collection items for {collection_id}: {e}");
+                e
+            })?;
+            // Sources report no reliable count for an offset behind their last feature
+            let collection_matched = if offset > 0 && items.number_returned == 0 {
+                Some(count_matched(fc.source.as_ref(), filter).await?)
+            } else {
+                items.number_matched
+            };
+            match collection_matched {
+                Some(matched) => {
+                    offset = offset.saturating_sub(matched);
+                    number_matched = number_matched.map(|n| n + matched);
+                }
+                None => {
+                    if items.number_returned > 0 {
+                        offset = 0;
+                    }
+                    number_matched = None;
+                }
+            }
+            if !page_full {
+                features.extend(items.features.into_iter().map(|mut feature| {
+                    let properties = feature
+                        .properties
+                        .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+                    if let Some(properties) = properties.as_object_mut() {
+                        properties.insert(
+                            "collection".to_string(),
+                            serde_json::Value::String(collection_id.clone()),
+                        );
+                    }
+                    feature
+                }));
+            }
+        }
+        let href = format!("{}/search", self.href_prefix());
+        let collections = collection_ids.join(",");
+        let link = |filter: FilterParams, rel: &str| {
+            let args = filter.as_args();
+            let sep = if args.is_empty() { '?' } else { '&' };
+            ApiLink {
+                href: format!("{href}{args}{sep}collections={collections}"),
+                rel: Some(rel.to_string()),
+                type_: Some("application/geo+json".to_string()),
+                title: Some(rel.to_string()),
+                hreflang: None,
+                length: None,
+            }
+        };
+        let mut links = vec![link(filter.clone(), "self")];
+        if limit > 0 {
             if let Some(prev) = filter.prev() {
-                add_link(prev, "prev");
+                links.push(link(prev, "prev"));
             }
-            if let Some(next) = filter.next(items.number_matched) {
-                add_link(next, "next");
+            let max = match number_matched {
+                Some(number_matched) => number_matched,
+                None if features.len() == limit => u64::MAX,
+                None => 0,
+            };
+            if let Some(next) = filter.next(max) {
+                links.push(link(next, "next"));
             }
         }
-        Some(features)
+        Ok(CoreFeatures {
+            type_: "FeatureCollection".to_string(),
+            links,
+            time_stamp: None,
+            number_matched,
+            number_returned: Some(features.len() as u64),
+            features,
+        })
     }
 
     pub async fn collection_item(
@@ -206,12 +403,17 @@ impl Inventory {
         base_url: &str,
         collection_id: &str,
         feature_id: &str,
+        filter: &FilterParams,
     ) -> Option<CoreFeature> {
         let Some(fc) = self.collection(collection_id) else {
             warn!("Ignoring error getting collection {collection_id}");
             return None;
         };
-        match fc.source.item(base_url, collection_id, feature_id).await {
+        match fc
+            .source
+            .item(base_url, collection_id, feature_id, filter)
+            .await
+        {
             Ok(item) => item,
             Err(e) => {
                 warn!("Ignoring error getting collection item for {collection_id}: {e}");
@@ -220,6 +422,203 @@ impl Inventory {
         }
     }
 
+    /// Server-Sent Events with feature changes within the WGS84 `bbox` of `filter`.
+    /// Changed features are only reported, if they are readable with `filter`.
+    /// Deleted features are checked with their values before deletion,
+    /// and withheld from subscribers with `bbox`, if their extent is unknown.
+    pub fn collection_changes_stream(
+        &self,
+        collection_id: &str,
+        filter: &FilterParams,
+        with_features: bool,
+    ) -> Option<impl Stream<Item = Result<Bytes>>> {
+        let fc = self.collection(collection_id)?;
+        let receiver = fc.source.changes()?;
+        let bbox = filter.bbox().ok().flatten().map(|bbox| match bbox[..] {
+            [minx, miny, _, maxx, maxy, _] => [minx, miny, maxx, maxy],
+            _ => [bbox[0], bbox[1], bbox[2], bbox[3]],
+        });
+        let source = fc.source.clone();
+        let base_url = self.href_prefix().to_string();
+        let collection_id = collection_id.to_string();
+        let filter = filter.clone();
+        let changes = stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) => return Some((change, receiver)),
+                    Err(RecvError::Lagged(n)) => warn!("Skipped {n} change notifications"),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        let events = changes.filter_map(move |change| {
+            let source = source.clone();
+            let base_url = base_url.clone();
+            let collection_id = collection_id.clone();
+            let filter = filter.clone();
+            async move {
+                let feature = if change.op == ChangeOp::Delete {
+                    match source.deleted_item_readable(&change, &filter).await {
+                        Ok(true) => None,
+                        Ok(false) => return None,
+                        Err(e) => {
+                            warn!("Ignoring error checking deleted item for {collection_id}: {e}");
+                            return None;
+                        }
+                    }
+                } else {
+                    match source
+                        .item(&base_url, &collection_id, &change.id, &filter)
+                        .await
+                    {
+                        Ok(Some(feature)) => Some(feature),
+                        Ok(None) => return None,
+                        Err(e) => {
+                            warn!("Ignoring error getting changed item for {collection_id}: {e}");
+                            return None;
+                        }
+                    }
+                };
+                if let Some(bbox) = bbox {
+                    // Deleted features are checked with their extent before deletion
+                    let extent = match &feature {
+                        Some(feature) => Some(geojson_bbox(&feature.geometry)?),
+                        None => change.bbox,
+                    };
+                    let b = extent?;
+                    if b[0] > bbox[2] || b[2] < bbox[0] || b[1] > bbox[3] || b[3] < bbox[1] {
+                        return None;
+                    }
+                }
+                let event = ChangeEvent {
+                    change,
+                    feature: feature.filter(|_| with_features),
+                };
+                Some(
+                    serde_json::to_string(&event)
+                        .map(|data| Bytes::from(format!("data: {data}\n\n")))
+                        .map_err(Error::from),
+                )
+            }
+        });
+        Some(events)
+    }
+
+    pub async fn collection_srs_definition(
+        &self,
+        collection_id: &str,
+        srid: i32,
+    ) -> Option<String> {
+        let fc = self.collection(collection_id)?;
+        match fc.source.srs_definition(srid).await {
+            Ok(definition) => definition,
+            Err(e) => {
+                warn!("Ignoring error getting SRS definition for {collection_id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Any collection supporting transactions
+    pub fn has_transactions(&self) -> bool {
+        self.feat_collections
+            .values()
+            .any(|fc| fc.source.transactions())
+    }
+
+    /// Whether the collection supports the `q` parameter
+    pub fn collection_text_search(&self, collection_id: &str) -> bool {
+        matches!(self.collection(collection_id), Some(fc) if fc.source.text_search())
+    }
+
+    /// Validate request parameters bound to the collection query
+    pub fn check_collection_parameters(
+        &self,
+        collection_id: &str,
+        filter: &FilterParams,
+    ) -> Result<()> {
+        match self.collection(collection_id) {
+            Some(fc) => fc.source.check_parameters(filter),
+            None => Ok(()),
+        }
+    }
+
+    /// Any collection with STAC metadata
+    pub fn has_stac(&self) -> bool {
+        self.feat_collections
+            .values()
+            .any(|fc| fc.collection.foreign_members.contains_key("stac_version"))
+    }
+
+    pub async fn collection_item_etag(
+        &self,
+        collection_id: &str,
+        feature_id: &str,
+    ) -> Option<String> {
+        let fc = self.collection(collection_id)?;
+        match fc.source.item_etag(feature_id).await {
+            Ok(etag) => etag,
+            Err(e) => {
+                warn!("Ignoring error getting item etag for {collection_id}: {e}");
+                None
+            }
+        }
+    }
+
+    fn transaction_source(&self, collection_id: &str) -> Result<&dyn CollectionSource> {
+        self.collection(collection_id)
+            .map(|fc| fc.source.as_ref())
+            .ok_or(Error::CollectionNotFound(collection_id.to_string()))
+    }
+
+    pub async fn create_collection_item(
+        &self,
+        collection_id: &str,
+        feature: &EditFeature,
+    ) -> Result<String> {
+        let source = self.transaction_source(collection_id)?;
+        source.create_item(feature).await
+    }
+
+    pub async fn replace_collection_item(
+        &self,
+        collection_id: &str,
+        feature_id: &str,
+        feature: &EditFeature,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let source = self.transaction_source(collection_id)?;
+        source
+            .replace_item(feature_id, feature, etag, username)
+            .await
+    }
+
+    pub async fn update_collection_item(
+        &self,
+        collection_id: &str,
+        feature_id: &str,
+        feature: &EditFeature,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let source = self.transaction_source(collection_id)?;
+        source
+            .update_item(feature_id, feature, etag, username)
+            .await
+    }
+
+    pub async fn delete_collection_item(
+        &self,
+        collection_id: &str,
+        feature_id: &str,
+        etag: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<()> {
+        let source = self.transaction_source(collection_id)?;
+        source.delete_item(feature_id, etag, username).await
+    }
+
     pub async fn collection_queryables(&self, collection_id: &str) -> Option<Queryables> {
         let Some(fc) = self.collection(collection_id) else {
             warn!("Ignoring error getting collection {collection_id}");
@@ -233,17 +632,144 @@ impl Inventory {
             }
         }
     }
+
+    pub async fn collection_sortables(&self, collection_id: &str) -> Option<Queryables> {
+        let Some(fc) = self.collection(collection_id) else {
+            warn!("Ignoring error getting collection {collection_id}");
+            return None;
+        };
+        match fc.source.sortables(collection_id).await {
+            Ok(sortables) => sortables,
+            Err(e) => {
+                warn!("Ignoring error getting collection sortables for {collection_id}: {e}");
+                None
+            }
+        }
+    }
+
+    pub async fn collection_schema(&self, collection_id: &str) -> Option<FeatureSchema> {
+        let Some(fc) = self.collection(collection_id) else {
+            warn!("Ignoring error getting collection {collection_id}");
+            return None;
+        };
+        match fc.source.schema(collection_id).await {
+            Ok(schema) => schema,
+            Err(e) => {
+                warn!("Ignoring error getting collection schema for {collection_id}: {e}");
+                None
+            }
+        }
+    }
+}
+
+enum ItemsEvent {
+    Feature(Box<CoreFeature>),
+    Done(Result<ItemsResult>),
+}
+
+/// Server-Sent Event data of a feature change
+#[derive(Serialize)]
+struct ChangeEvent {
+    #[serde(flatten)]
+    change: FeatureChange,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feature: Option<CoreFeature>,
+}
+
+/// Exact number of features matching `filter`, independent of its offset
+async fn count_matched(source: &dyn CollectionSource, filter: &FilterParams) -> Result<u64> {
+    let first = FilterParams {
+        offset: None,
+        limit: Some(1),
+        ..filter.clone()
+    };
+    if let Some(matched) = source.items(&first).await?.number_matched {
+        return Ok(matched);
+    }
+    // Count all features of sources without numberMatched
+    let all = FilterParams {
+        offset: None,
+        limit: Some(0),
+        skip_geometry: Some(true),
+        ..filter.clone()
+    };
+    Ok(source.items(&all).await?.number_returned)
+}
+
+/// FeatureCollection with paging links of an items request
+fn items_response(
+    base_url: &str,
+    collection_id: &str,
+    filter: &FilterParams,
+    items: ItemsResult,
+) -> CoreFeatures {
+    let mut features = CoreFeatures {
+        type_: "FeatureCollection".to_string(),
+        links: vec![
+            ApiLink {
+                href: format!("{base_url}/collections/{collection_id}/items"),
+                rel: Some("self".to_string()),
+                type_: Some("text/html".to_string()),
+                title: Some("this document".to_string()),
+                hreflang: None,
+                length: None,
+            },
+            ApiLink {
+                href: format!("{base_url}/collections/{collection_id}/items.json"),
+                rel: Some("self".to_string()),
+                type_: Some("application/geo+json".to_string()),
+                title: Some("this document".to_string()),
+                hreflang: None,
+                length: None,
+            },
+        ],
+        time_stamp: None, // time when the response was generated
+        number_matched: items.number_matched,
+        number_returned: Some(items.number_returned),
+        features: items.features,
+    };
+    let mut add_link = |link: FilterParams, rel: &str| {
+        let params = link.as_args();
+        features.links.push(ApiLink {
+            href: format!("{base_url}/collections/{collection_id}/items{params}"),
+            rel: Some(rel.to_string()),
+            type_: Some("text/html".to_string()),
+            title: Some(rel.to_string()),
+            hreflang: None,
+            length: None,
+        });
+    };
+    let limit = filter.limit_or_default() as u64;
+    if let Some(cursor) = items.next_cursor {
+        add_link(filter.with_cursor(cursor), "next");
+    } else if limit > 0 {
+        if let Some(prev) = filter.prev() {
+            add_link(prev, "prev");
+        }
+        // Without numberMatched, a full page indicates more features
+        let max = match items.number_matched {
+            Some(number_matched) => number_matched,
+            None if items.number_returned == limit => u64::MAX,
+            None => 0,
+        };
+        if let Some(next) = filter.next(max) {
+            add_link(next, "next");
+        }
+    }
+    features
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::FeatureServerCfg;
+    use futures::TryStreamExt;
 
     #[tokio::test]
     async fn inventory_scan() {
         let inventory = Inventory::scan(&CollectionsCfg::from_path("../assets"), None).await;
         // assert_eq!(inventory.collections().len(), 3);
-        assert!(inventory.collections().len() >= 3);
+        assert!(inventory.collections(None).len() >= 3);
         assert_eq!(
             inventory
                 .core_collection("ne_10m_lakes")
@@ -251,4 +777,295 @@ mod tests {
             Some("ne_10m_lakes".to_string())
         );
     }
+
+    #[tokio::test]
+    async fn collection_access() {
+        let mut inventory = Inventory::scan(&CollectionsCfg::from_path("../assets"), None).await;
+        let all = inventory.collections(None).len();
+        inventory.set_access(
+            "ne_10m_lakes",
+            AccessCfg {
+                read: vec!["staff".to_string()],
+                write: vec!["editors".to_string()],
+                ..Default::default()
+            },
+        );
+        let user = |groups: &[&str]| Identity {
+            username: "jane".to_string(),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+        };
+        assert_eq!(inventory.collections(None).len(), all - 1);
+        assert!(!inventory.can_read("ne_10m_lakes", Some(&user(&[]))));
+        assert!(inventory.can_read("ne_10m_lakes", Some(&user(&["staff"]))));
+        assert!(!inventory.can_write("ne_10m_lakes", Some(&user(&["staff"]))));
+        assert!(inventory.can_read("ne_10m_lakes", Some(&user(&["editors"]))));
+        assert!(inventory.can_write("ne_10m_lakes", Some(&user(&["editors"]))));
+        assert_eq!(inventory.collections(Some(&user(&["staff"]))).len(), all);
+        // Collections without access rules
+        assert!(inventory.can_read("ne_10m_populated_places", None));
+        assert!(inventory.can_write("ne_10m_populated_places", None));
+    }
+
+    #[tokio::test]
+    async fn search_items() {
+        let inventory = Inventory::scan(&CollectionsCfg::from_path("../assets"), None).await;
+        let ids = vec![
+            "ne_10m_lakes".to_string(),
+            "ne_10m_populated_places".to_string(),
+        ];
+        let all = FilterParams {
+            limit: Some(0),
+            ..Default::default()
+        };
+        let lakes = inventory
+            .collection_items("ne_10m_lakes", &all)
+            .await
+            .unwrap()
+            .number_matched
+            .unwrap();
+        let items = inventory.search_items(&ids, &all).await.unwrap();
+        let total = items.number_matched.unwrap();
+        assert!(total > lakes);
+        assert_eq!(items.number_returned, Some(total));
+
+        // Page spanning both collections
+        let filter = FilterParams {
+            limit: Some(2),
+            offset: Some(lakes as u32 - 1),
+            ..Default::default()
+        };
+        let items = inventory.search_items(&ids, &filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(total));
+        let collections: Vec<&str> = items
+            .features
+            .iter()
+            .map(|f| {
+                f.properties.as_ref().unwrap()["collection"]
+                    .as_str()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(collections, ["ne_10m_lakes", "ne_10m_populated_places"]);
+        assert!(items
+            .links
+            .iter()
+            .any(|link| link.rel.as_deref() == Some("next")
+                && link
+                    .href
+                    .ends_with("collections=ne_10m_lakes,ne_10m_populated_places")));
+
+        // Page behind the last feature of the first collection
+        let places = inventory
+            .collection_items(
+                "ne_10m_populated_places",
+                &FilterParams {
+                    limit: Some(2),
+                    offset: Some(1),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let filter = FilterParams {
+            limit: Some(2),
+            offset: Some(lakes as u32 + 1),
+            ..Default::default()
+        };
+        let items = inventory.search_items(&ids, &filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(total));
+        let feature_ids =
+            |features: &[CoreFeature]| features.iter().map(|f| f.id.clone()).collect::<Vec<_>>();
+        assert_eq!(feature_ids(&items.features), feature_ids(&places.features));
+
+        // Sorting is not a property filter
+        let filter = FilterParams {
+            limit: Some(2),
+            sortby: Some("-name".to_string()),
+            ..Default::default()
+        };
+        let items = inventory.search_items(&ids, &filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(total));
+
+        // Collections without matching queryables are skipped
+        let filter = FilterParams {
+            filters: HashMap::from([("unknown".to_string(), "1".to_string())]),
+            ..Default::default()
+        };
+        let items = inventory.search_items(&ids, &filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(0));
+
+        // Datasource errors are not skipped
+        let filter = FilterParams {
+            sortby: Some("unknown".to_string()),
+            ..Default::default()
+        };
+        assert!(inventory.search_items(&ids, &filter).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn items_stream() {
+        let inventory = Inventory::scan(&CollectionsCfg::from_path("../assets"), None).await;
+        // Unlimited by default
+        assert_eq!(FeatureServerCfg::default().limit(Some(0)), Some(0));
+        let server_cfg = FeatureServerCfg { max_limit: 10 };
+        let filter = FilterParams {
+            limit: server_cfg.limit(Some(0)),
+            ..Default::default()
+        };
+        assert_eq!(filter.limit, Some(10));
+        let body = inventory
+            .collection_items_stream("ne_10m_populated_places", &filter, ItemsFormat::GeoJson)
+            .await
+            .unwrap()
+            .try_fold(Vec::new(), |mut body, bytes| async move {
+                body.extend_from_slice(&bytes);
+                Ok(body)
+            })
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["type"], "FeatureCollection");
+        assert_eq!(json["features"].as_array().unwrap().len(), 10);
+        assert_eq!(json["numberReturned"], 10);
+        assert!(json["numberMatched"].as_u64().unwrap() > 10);
+        assert_eq!(
+            json["links"][2]["href"],
+            "/collections/ne_10m_populated_places/items?limit=10&offset=10"
+        );
+
+        let items = inventory
+            .collection_items("ne_10m_populated_places", &filter)
+            .await
+            .unwrap();
+        assert_eq!(serde_json::to_value(items).unwrap(), json);
+
+        let body = inventory
+            .collection_items_stream("ne_10m_populated_places", &filter, ItemsFormat::GeoJsonSeq)
+            .await
+            .unwrap()
+            .try_fold(Vec::new(), |mut body, bytes| async move {
+                body.extend_from_slice(&bytes);
+                Ok(body)
+            })
+            .await
+            .unwrap();
+        let lines = String::from_utf8(body).unwrap();
+        assert_eq!(lines.lines().count(), 10);
+        assert!(lines.lines().all(|line| line.starts_with('\x1e')));
+        let feature: serde_json::Value =
+            serde_json::from_str(&lines.lines().next().unwrap()[1..]).unwrap();
+        assert_eq!(feature, json["features"][0]);
+
+        assert!(inventory
+            .collection_items_stream("unknown", &filter, ItemsFormat::GeoJson)
+            .await
+            .is_err());
+
+        // Filter on a property which is not queryable
+        let filter = FilterParams {
+            filters: HashMap::from([("unknown".to_string(), "1".to_string())]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            inventory
+                .collection_items("ne_10m_populated_places", &filter)
+                .await,
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    /// Source emitting change notifications for rows owned by `owner`
+    #[derive(Clone)]
+    struct ChangesSource {
+        changes: tokio::sync::broadcast::Sender<FeatureChange>,
+    }
+
+    #[async_trait::async_trait]
+    impl CollectionSource for ChangesSource {
+        async fn send_items(
+            &self,
+            _filter: &FilterParams,
+            _sender: crate::datasource::FeatureSender,
+        ) -> Result<ItemsResult> {
+            Ok(ItemsResult {
+                features: Vec::new(),
+                number_matched: Some(0),
+                number_returned: 0,
+                next_cursor: None,
+            })
+        }
+        async fn item(
+            &self,
+            _base_url: &str,
+            _collection_id: &str,
+            _feature_id: &str,
+            _filter: &FilterParams,
+        ) -> Result<Option<CoreFeature>> {
+            Ok(None)
+        }
+        async fn queryables(&self, _collection_id: &str) -> Result<Option<Queryables>> {
+            Ok(None)
+        }
+        fn changes(&self) -> Option<crate::datasource::ChangeReceiver> {
+            Some(self.changes.subscribe())
+        }
+        async fn deleted_item_readable(
+            &self,
+            change: &FeatureChange,
+            filter: &FilterParams,
+        ) -> Result<bool> {
+            Ok(
+                matches!(&change.row, Some(row) if row["owner"].as_str() == filter.username.as_deref()),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn deleted_items_changes() {
+        let mut inventory = Inventory::scan(&CollectionsCfg::from_path("../assets"), None).await;
+        let mut collection = inventory.core_collection("ne_10m_lakes").unwrap().clone();
+        collection.id = "owned".to_string();
+        let (sender, _) = tokio::sync::broadcast::channel(16);
+        inventory.add_collection(FeatureCollection {
+            collection,
+            source: Box::new(ChangesSource {
+                changes: sender.clone(),
+            }),
+        });
+        let filter = FilterParams {
+            bbox: Some("7,46,8,47".to_string()),
+            username: Some("jane".to_string()),
+            ..Default::default()
+        };
+        let events = inventory
+            .collection_changes_stream("owned", &filter, false)
+            .unwrap();
+        let mut events = Box::pin(events);
+
+        let delete = |id: &str, owner: &str, bbox: Option<[f64; 4]>| FeatureChange {
+            op: ChangeOp::Delete,
+            id: id.to_string(),
+            bbox,
+            row: Some(serde_json::json!({ "owner": owner })),
+        };
+        // Hidden by row filter
+        sender
+            .send(delete("1", "joe", Some([7.4, 46.9, 7.5, 47.0])))
+            .unwrap();
+        // Unknown extent with bbox filter
+        sender.send(delete("2", "jane", None)).unwrap();
+        // Outside of bbox
+        sender
+            .send(delete("3", "jane", Some([8.5, 47.3, 8.6, 47.4])))
+            .unwrap();
+        sender
+            .send(delete("4", "jane", Some([7.4, 46.9, 7.5, 47.0])))
+            .unwrap();
+
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(
+            event,
+            Bytes::from("data: {\"op\":\"delete\",\"id\":\"4\"}\n\n")
+        );
+    }
 }
@@ -1,9 +1,11 @@
 pub mod config;
+mod cql2;
 pub mod datasource;
 mod endpoints;
 mod error;
 mod filter_params;
 mod inventory;
+mod output;
 pub mod service;
 
 pub use service::*;
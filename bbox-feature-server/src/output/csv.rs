@@ -0,0 +1,176 @@
+//! CSV output.
+
+use crate::error::{Error, Result};
+use crate::output::FeatureEncoder;
+use bbox_core::ogcapi::{CoreFeature, CoreFeatures};
+use geozero::{geojson::GeoJson, ToWkt};
+use serde_json::{Map, Value};
+
+/// CSV with feature id, properties and geometry as `wkt` column
+///
+/// Property columns are taken from the first feature.
+#[derive(Default)]
+pub struct CsvEncoder {
+    columns: Option<Vec<String>>,
+}
+
+impl CsvEncoder {
+    fn write_header(&mut self, properties: Option<&Map<String, Value>>, buf: &mut Vec<u8>) {
+        let columns: Vec<String> = properties
+            .map(|props| props.keys().cloned().collect())
+            .unwrap_or_default();
+        let header = ["id"]
+            .into_iter()
+            .chain(columns.iter().map(String::as_str))
+            .chain(["wkt"]);
+        write_record(buf, header);
+        self.columns = Some(columns);
+    }
+}
+
+impl FeatureEncoder for CsvEncoder {
+    fn feature(&mut self, feature: &CoreFeature, buf: &mut Vec<u8>) -> Result<()> {
+        let properties = feature.properties.as_ref().and_then(Value::as_object);
+        if self.columns.is_none() {
+            self.write_header(properties, buf);
+        }
+        let mut record = vec![feature.id.clone().unwrap_or_default()];
+        for column in self.columns.iter().flatten() {
+            let value = properties.and_then(|props| props.get(column));
+            record.push(value.map(cell_value).unwrap_or_default());
+        }
+        if feature.geometry.is_null() {
+            record.push(String::new());
+        } else {
+            let wkt = GeoJson(&feature.geometry.to_string())
+                .to_wkt()
+                .map_err(|e| Error::OutputFormatError(e.to_string()))?;
+            record.push(wkt);
+        }
+        write_record(buf, record.iter().map(String::as_str));
+        Ok(())
+    }
+
+    fn finish(&mut self, _response: CoreFeatures, buf: &mut Vec<u8>) -> Result<()> {
+        // Header of empty results
+        if self.columns.is_none() {
+            self.write_header(None, buf);
+        }
+        Ok(())
+    }
+}
+
+fn cell_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn write_record<'a>(buf: &mut Vec<u8>, cells: impl Iterator<Item = &'a str>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            buf.push(b',');
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            buf.push(b'"');
+            buf.extend_from_slice(cell.replace('"', "\"\"").as_bytes());
+            buf.push(b'"');
+        } else {
+            buf.extend_from_slice(cell.as_bytes());
+        }
+    }
+    buf.extend_from_slice(b"\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn feature(id: &str, geometry: Value, properties: Value) -> CoreFeature {
+        CoreFeature {
+            type_: "Feature".to_string(),
+            geometry,
+            properties: Some(properties),
+            id: Some(id.to_string()),
+            links: Vec::new(),
+            foreign_members: serde_json::Map::new(),
+        }
+    }
+
+    fn encode(features: &[CoreFeature]) -> String {
+        let mut encoder = CsvEncoder::default();
+        let mut buf = Vec::new();
+        for feature in features {
+            encoder.feature(feature, &mut buf).unwrap();
+        }
+        let response = CoreFeatures {
+            type_: "FeatureCollection".to_string(),
+            links: Vec::new(),
+            time_stamp: None,
+            number_matched: None,
+            number_returned: None,
+            features: Vec::new(),
+        };
+        encoder.finish(response, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn csv_points() {
+        let csv = encode(&[
+            feature(
+                "1",
+                json!({"type": "Point", "coordinates": [7.5, 47.0]}),
+                json!({"name": "Bern, \"capital\"", "pop": 133115}),
+            ),
+            feature(
+                "2",
+                json!({"type": "Point", "coordinates": [8.5, 47.4]}),
+                json!({"name": "Zürich", "pop": null}),
+            ),
+        ]);
+        assert_eq!(
+            csv,
+            "id,name,pop,wkt\r\n1,\"Bern, \"\"capital\"\"\",133115,POINT(7.5 47)\r\n2,Zürich,,POINT(8.5 47.4)\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_wkt() {
+        let csv = encode(&[feature(
+            "1",
+            json!({"type": "LineString", "coordinates": [[0, 0], [1, 1]]}),
+            json!({"name": "line"}),
+        )]);
+        assert_eq!(csv, "id,name,wkt\r\n1,line,\"LINESTRING(0 0,1 1)\"\r\n");
+    }
+
+    #[test]
+    fn csv_empty() {
+        assert_eq!(encode(&[]), "id,wkt\r\n");
+    }
+
+    #[test]
+    fn csv_mixed_geometries() {
+        let csv = encode(&[
+            feature(
+                "1",
+                json!({"type": "Point", "coordinates": [0, 0]}),
+                json!({"name": "point"}),
+            ),
+            feature("2", Value::Null, json!({"name": "none"})),
+            feature(
+                "3",
+                json!({"type": "LineString", "coordinates": [[0, 0], [1, 1]]}),
+                json!({"name": "line"}),
+            ),
+        ]);
+        assert_eq!(
+            csv,
+            "id,name,wkt\r\n1,point,POINT(0 0)\r\n2,none,\r\n3,line,\"LINESTRING(0 0,1 1)\"\r\n"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! FlatGeobuf output.
+
+use crate::error::{Error, Result};
+use bbox_core::ogcapi::{CoreFeature, CoreFeatures};
+use flatgeobuf::{ColumnType, FgbCrs, FgbWriter, FgbWriterOptions, GeometryType};
+use geozero::geojson::GeoJson;
+use geozero::{ColumnValue, FeatureProcessor, GeozeroDatasource, GeozeroGeometry};
+use serde_json::Value;
+
+/// Encode features as FlatGeobuf, optionally with a packed Hilbert R-tree index
+pub fn features_to_fgb(
+    name: &str,
+    features: &CoreFeatures,
+    srid: i32,
+    write_index: bool,
+) -> Result<Vec<u8>> {
+    let options = FgbWriterOptions {
+        write_index,
+        crs: FgbCrs {
+            code: srid,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut fgb = FgbWriter::create_with_options(name, GeometryType::Unknown, options)
+        .map_err(|e| Error::OutputFormatError(e.to_string()))?;
+    let columns = property_columns(features);
+    for (name, column_type) in &columns {
+        fgb.add_column(name, *column_type, |_, col| col.nullable = true);
+    }
+    for feature in &features.features {
+        fgb.add_feature(FeatureSource {
+            columns: &columns,
+            feature,
+        })
+        .map_err(|e| Error::OutputFormatError(e.to_string()))?;
+    }
+    let mut buf = Vec::new();
+    fgb.write(&mut buf)
+        .map_err(|e| Error::OutputFormatError(e.to_string()))?;
+    Ok(buf)
+}
+
+/// Property columns in order of appearance with a type matching all values
+fn property_columns(features: &CoreFeatures) -> Vec<(String, ColumnType)> {
+    let mut columns: Vec<(String, Option<ColumnType>)> = Vec::new();
+    for properties in features
+        .features
+        .iter()
+        .filter_map(|feature| feature.properties.as_ref()?.as_object())
+    {
+        for (name, value) in properties {
+            let idx = match columns.iter().position(|(col, _)| col == name) {
+                Some(idx) => idx,
+                None => {
+                    columns.push((name.clone(), None));
+                    columns.len() - 1
+                }
+            };
+            let value_type = match value {
+                Value::Null => continue,
+                Value::Bool(_) => ColumnType::Bool,
+                Value::Number(n) if n.is_i64() => ColumnType::Long,
+                Value::Number(_) => ColumnType::Double,
+                Value::String(_) => ColumnType::String,
+                Value::Array(_) | Value::Object(_) => ColumnType::Json,
+            };
+            let column_type = &mut columns[idx].1;
+            *column_type = Some(match (*column_type, value_type) {
+                (None, t) => t,
+                (Some(a), b) if a == b => a,
+                (Some(ColumnType::Long), ColumnType::Double)
+                | (Some(ColumnType::Double), ColumnType::Long) => ColumnType::Double,
+                _ => ColumnType::String,
+            });
+        }
+    }
+    columns
+        .into_iter()
+        .map(|(name, column_type)| (name, column_type.unwrap_or(ColumnType::String)))
+        .collect()
+}
+
+/// Feature with properties in column order and of column type
+struct FeatureSource<'a> {
+    columns: &'a [(String, ColumnType)],
+    feature: &'a CoreFeature,
+}
+
+impl GeozeroDatasource for FeatureSource<'_> {
+    fn process<P: FeatureProcessor>(&mut self, processor: &mut P) -> geozero::error::Result<()> {
+        processor.dataset_begin(None)?;
+        processor.feature_begin(0)?;
+        processor.properties_begin()?;
+        if let Some(properties) = &self.feature.properties {
+            for (i, (name, column_type)) in self.columns.iter().enumerate() {
+                let Some(value) = properties.get(name) else {
+                    continue;
+                };
+                match (value, *column_type) {
+                    (Value::Null, _) => false,
+                    (Value::Bool(v), ColumnType::Bool) => {
+                        processor.property(i, name, &ColumnValue::Bool(*v))?
+                    }
+                    (Value::Number(n), ColumnType::Long) => {
+                        let v = n.as_i64().unwrap_or_default();
+                        processor.property(i, name, &ColumnValue::Long(v))?
+                    }
+                    (Value::Number(n), ColumnType::Double) => {
+                        let v = n.as_f64().unwrap_or(f64::NAN);
+                        processor.property(i, name, &ColumnValue::Double(v))?
+                    }
+                    (Value::String(v), ColumnType::String) => {
+                        processor.property(i, name, &ColumnValue::String(v))?
+                    }
+                    (v, ColumnType::Json) => {
+                        processor.property(i, name, &ColumnValue::Json(&v.to_string()))?
+                    }
+                    (v, _) => processor.property(i, name, &ColumnValue::String(&v.to_string()))?,
+                };
+            }
+        }
+        processor.properties_end()?;
+        if !self.feature.geometry.is_null() {
+            processor.geometry_begin()?;
+            GeoJson(&self.feature.geometry.to_string()).process_geom(processor)?;
+            processor.geometry_end()?;
+        }
+        processor.feature_end(0)?;
+        processor.dataset_end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+    use geozero::FeatureProperties;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+
+    #[test]
+    fn fgb_properties() {
+        let feature = |properties: Value| CoreFeature {
+            type_: "Feature".to_string(),
+            geometry: json!({"type": "Point", "coordinates": [7.44, 46.95]}),
+            properties: Some(properties),
+            id: None,
+            links: Vec::new(),
+            foreign_members: serde_json::Map::new(),
+        };
+        let features = CoreFeatures {
+            type_: "FeatureCollection".to_string(),
+            links: Vec::new(),
+            time_stamp: None,
+            number_matched: None,
+            number_returned: None,
+            features: vec![
+                feature(json!({"name": "Bern", "scalerank": 2, "pop": 1, "capital": true})),
+                feature(json!({"scalerank": null, "pop": 2.5, "name": "Zürich", "tags": ["a"]})),
+            ],
+        };
+        let fgb = features_to_fgb("places", &features, 4326, false).unwrap();
+
+        let mut reader = FgbReader::open(Cursor::new(fgb))
+            .unwrap()
+            .select_all_seq()
+            .unwrap();
+        let columns: Vec<_> = reader
+            .header()
+            .columns()
+            .unwrap()
+            .iter()
+            .map(|col| (col.name().to_string(), col.type_()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("name".to_string(), ColumnType::String),
+                ("scalerank".to_string(), ColumnType::Long),
+                ("pop".to_string(), ColumnType::Double),
+                ("capital".to_string(), ColumnType::Bool),
+                ("tags".to_string(), ColumnType::Json),
+            ]
+        );
+
+        let mut properties = Vec::new();
+        while let Some(feature) = reader.next().unwrap() {
+            let props: BTreeMap<String, String> =
+                feature.properties().unwrap().into_iter().collect();
+            properties.push(props);
+        }
+        let props = |values: &[(&str, &str)]| -> BTreeMap<String, String> {
+            values
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            properties,
+            vec![
+                props(&[
+                    ("name", "Bern"),
+                    ("scalerank", "2"),
+                    ("pop", "1"),
+                    ("capital", "true")
+                ]),
+                props(&[("name", "Zürich"), ("pop", "2.5"), ("tags", r#"["a"]"#)]),
+            ]
+        );
+    }
+}
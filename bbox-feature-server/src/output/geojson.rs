@@ -0,0 +1,69 @@
+//! GeoJSON and GeoJSON text sequence output.
+
+use crate::error::Result;
+use crate::output::FeatureEncoder;
+use bbox_core::ogcapi::{CoreFeature, CoreFeatures};
+
+/// GeoJSON FeatureCollection
+#[derive(Default)]
+pub struct GeoJsonEncoder {
+    started: bool,
+}
+
+impl GeoJsonEncoder {
+    fn start(&mut self, buf: &mut Vec<u8>) -> bool {
+        if self.started {
+            return false;
+        }
+        buf.extend_from_slice(br#"{"type":"FeatureCollection","features":["#);
+        self.started = true;
+        true
+    }
+}
+
+impl FeatureEncoder for GeoJsonEncoder {
+    fn feature(&mut self, feature: &CoreFeature, buf: &mut Vec<u8>) -> Result<()> {
+        if !self.start(buf) {
+            buf.push(b',');
+        }
+        serde_json::to_writer(buf, feature)?;
+        Ok(())
+    }
+
+    fn finish(&mut self, response: CoreFeatures, buf: &mut Vec<u8>) -> Result<()> {
+        self.start(buf);
+        buf.push(b']');
+        // Append members except `type` and `features` after the feature array
+        let mut members = serde_json::to_value(response)?;
+        if let Some(members) = members.as_object_mut() {
+            members.remove("type");
+            members.remove("features");
+            for (key, value) in members.iter() {
+                buf.push(b',');
+                serde_json::to_writer(&mut *buf, key)?;
+                buf.push(b':');
+                serde_json::to_writer(&mut *buf, value)?;
+            }
+        }
+        buf.push(b'}');
+        Ok(())
+    }
+}
+
+/// GeoJSON text sequence (RFC 8142) of features
+#[derive(Default)]
+pub struct GeoJsonSeqEncoder;
+
+impl FeatureEncoder for GeoJsonSeqEncoder {
+    fn feature(&mut self, feature: &CoreFeature, buf: &mut Vec<u8>) -> Result<()> {
+        // Record separator
+        buf.push(0x1e);
+        serde_json::to_writer(&mut *buf, feature)?;
+        buf.push(b'\n');
+        Ok(())
+    }
+
+    fn finish(&mut self, _response: CoreFeatures, _buf: &mut Vec<u8>) -> Result<()> {
+        Ok(())
+    }
+}
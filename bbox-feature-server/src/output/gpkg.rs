@@ -0,0 +1,409 @@
+//! GeoPackage output.
+
+use crate::datasource::gpkg::gpkg_geometry;
+use crate::error::{Error, Result};
+use actix_web::web::Bytes;
+use bbox_core::ogcapi::CoreFeatures;
+use futures::{stream, Stream};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection, Executor, QueryBuilder, Sqlite};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const WGS84_DEFINITION: &str = r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]"#;
+
+const GPKG_SCHEMA: &str = r#"
+PRAGMA application_id = 1196444487;
+PRAGMA user_version = 10200;
+CREATE TABLE gpkg_spatial_ref_sys (
+  srs_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL PRIMARY KEY,
+  organization TEXT NOT NULL,
+  organization_coordsys_id INTEGER NOT NULL,
+  definition TEXT NOT NULL,
+  description TEXT
+);
+CREATE TABLE gpkg_contents (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  data_type TEXT NOT NULL,
+  identifier TEXT UNIQUE,
+  description TEXT DEFAULT '',
+  last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+  min_x DOUBLE,
+  min_y DOUBLE,
+  max_x DOUBLE,
+  max_y DOUBLE,
+  srs_id INTEGER,
+  CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE gpkg_geometry_columns (
+  table_name TEXT NOT NULL,
+  column_name TEXT NOT NULL,
+  geometry_type_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL,
+  z TINYINT NOT NULL,
+  m TINYINT NOT NULL,
+  CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+  CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+  CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+  CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+CREATE TABLE gpkg_extensions (
+  table_name TEXT,
+  column_name TEXT,
+  extension_name TEXT NOT NULL,
+  definition TEXT NOT NULL,
+  scope TEXT NOT NULL,
+  CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+);
+INSERT INTO gpkg_spatial_ref_sys VALUES
+  ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+  ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system');
+"#;
+
+/// R-tree maintenance triggers of the GeoPackage specification.
+/// Created after inserting features, since the `ST_*` functions are not available in SQLx.
+const RTREE_TRIGGERS: &str = r#"
+CREATE TRIGGER "rtree_<t>_<c>_insert" AFTER INSERT ON "<t>"
+  WHEN (new."<c>" NOT NULL AND NOT ST_IsEmpty(NEW."<c>"))
+BEGIN
+  INSERT OR REPLACE INTO "rtree_<t>_<c>" VALUES (
+    NEW."<i>", ST_MinX(NEW."<c>"), ST_MaxX(NEW."<c>"), ST_MinY(NEW."<c>"), ST_MaxY(NEW."<c>")
+  );
+END;
+CREATE TRIGGER "rtree_<t>_<c>_update1" AFTER UPDATE OF "<c>" ON "<t>"
+  WHEN OLD."<i>" = NEW."<i>" AND (NEW."<c>" NOTNULL AND NOT ST_IsEmpty(NEW."<c>"))
+BEGIN
+  INSERT OR REPLACE INTO "rtree_<t>_<c>" VALUES (
+    NEW."<i>", ST_MinX(NEW."<c>"), ST_MaxX(NEW."<c>"), ST_MinY(NEW."<c>"), ST_MaxY(NEW."<c>")
+  );
+END;
+CREATE TRIGGER "rtree_<t>_<c>_update2" AFTER UPDATE OF "<c>" ON "<t>"
+  WHEN OLD."<i>" = NEW."<i>" AND (NEW."<c>" ISNULL OR ST_IsEmpty(NEW."<c>"))
+BEGIN
+  DELETE FROM "rtree_<t>_<c>" WHERE id = OLD."<i>";
+END;
+CREATE TRIGGER "rtree_<t>_<c>_update3" AFTER UPDATE ON "<t>"
+  WHEN OLD."<i>" != NEW."<i>" AND (NEW."<c>" NOTNULL AND NOT ST_IsEmpty(NEW."<c>"))
+BEGIN
+  DELETE FROM "rtree_<t>_<c>" WHERE id = OLD."<i>";
+  INSERT OR REPLACE INTO "rtree_<t>_<c>" VALUES (
+    NEW."<i>", ST_MinX(NEW."<c>"), ST_MaxX(NEW."<c>"), ST_MinY(NEW."<c>"), ST_MaxY(NEW."<c>")
+  );
+END;
+CREATE TRIGGER "rtree_<t>_<c>_update4" AFTER UPDATE ON "<t>"
+  WHEN OLD."<i>" != NEW."<i>" AND (NEW."<c>" ISNULL OR ST_IsEmpty(NEW."<c>"))
+BEGIN
+  DELETE FROM "rtree_<t>_<c>" WHERE id IN (OLD."<i>", NEW."<i>");
+END;
+CREATE TRIGGER "rtree_<t>_<c>_delete" AFTER DELETE ON "<t>"
+  WHEN old."<c>" NOT NULL
+BEGIN
+  DELETE FROM "rtree_<t>_<c>" WHERE id = OLD."<i>";
+END;
+"#;
+
+const FID_COLUMN: &str = "fid";
+const GEOMETRY_COLUMN: &str = "geom";
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// GeoPackage file in the temp directory, removed when dropped
+pub struct TempGpkg {
+    path: PathBuf,
+}
+
+impl TempGpkg {
+    fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = std::env::temp_dir().join(format!(
+            "bbox-{}-{}-{name}.gpkg",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        TempGpkg { path }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Stream file content in chunks. The file is removed after streaming.
+    pub fn into_stream(self) -> Result<impl Stream<Item = Result<Bytes>>> {
+        let file = File::open(&self.path).map_err(|e| Error::OutputFormatError(e.to_string()))?;
+        // File is closed before removing it
+        Ok(stream::unfold(Some((file, self)), |state| async move {
+            let (mut file, gpkg) = state?;
+            let mut buf = vec![0; CHUNK_SIZE];
+            match file.read(&mut buf) {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((Ok(Bytes::from(buf)), Some((file, gpkg))))
+                }
+                Err(e) => Some((Err(Error::OutputFormatError(e.to_string())), None)),
+            }
+        }))
+    }
+}
+
+impl Drop for TempGpkg {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Write features into a new GeoPackage table `name` with R-tree spatial index
+pub async fn features_to_gpkg(
+    name: &str,
+    features: &CoreFeatures,
+    srid: i32,
+    srs_definition: Option<String>,
+) -> Result<TempGpkg> {
+    let gpkg = TempGpkg::new(name);
+    let mut conn = SqliteConnectOptions::new()
+        .filename(gpkg.path())
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Off)
+        .connect()
+        .await?;
+    let mut tx = conn.begin().await?;
+    tx.execute(GPKG_SCHEMA).await?;
+    sqlx::query("INSERT INTO gpkg_spatial_ref_sys VALUES ('WGS 84 geodetic', 4326, 'EPSG', 4326, ?, 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid')")
+        .bind(WGS84_DEFINITION)
+        .execute(&mut *tx)
+        .await?;
+    if ![-1, 0, 4326].contains(&srid) {
+        sqlx::query("INSERT INTO gpkg_spatial_ref_sys VALUES (?, ?, 'EPSG', ?, ?, NULL)")
+            .bind(format!("EPSG:{srid}"))
+            .bind(srid)
+            .bind(srid)
+            .bind(srs_definition.as_deref().unwrap_or("undefined"))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let columns = property_columns(features);
+    let mut ddl = format!(
+        r#"CREATE TABLE "{name}" ("{FID_COLUMN}" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, "{GEOMETRY_COLUMN}" {}"#,
+        geometry_type(features)
+    );
+    for (column, column_type) in &columns {
+        ddl.push_str(&format!(r#", "{column}" {column_type}"#));
+    }
+    ddl.push(')');
+    tx.execute(ddl.as_str()).await?;
+    tx.execute(
+        format!(
+            r#"CREATE VIRTUAL TABLE "rtree_{name}_{GEOMETRY_COLUMN}" USING rtree(id, minx, maxx, miny, maxy)"#
+        )
+        .as_str(),
+    )
+    .await?;
+
+    let mut extent: Option<[f64; 4]> = None;
+    for feature in &features.features {
+        let (wkb, bbox) = gpkg_geometry(Some(&feature.geometry), srid)?;
+        let fid = feature.id.as_deref().and_then(|id| id.parse::<i64>().ok());
+        let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+            r#"INSERT INTO "{name}" ("{FID_COLUMN}", "{GEOMETRY_COLUMN}""#
+        ));
+        for (column, _) in &columns {
+            builder.push(format!(r#", "{column}""#));
+        }
+        builder.push(") VALUES (");
+        let mut separated = builder.separated(", ");
+        separated.push_bind(fid);
+        separated.push_bind(wkb);
+        let properties = feature.properties.as_ref().and_then(Value::as_object);
+        for (column, _) in &columns {
+            match properties.and_then(|props| props.get(column)) {
+                None | Some(Value::Null) => separated.push_bind(None::<String>),
+                Some(Value::Bool(v)) => separated.push_bind(*v),
+                Some(Value::Number(v)) if v.is_f64() => separated.push_bind(v.as_f64()),
+                Some(Value::Number(v)) => separated.push_bind(v.as_i64()),
+                Some(Value::String(v)) => separated.push_bind(v.clone()),
+                Some(v) => separated.push_bind(v.to_string()),
+            };
+        }
+        builder.push(")");
+        let rowid = builder.build().execute(&mut *tx).await?.last_insert_rowid();
+        if let Some(bbox) = bbox {
+            sqlx::query(&format!(
+                r#"INSERT INTO "rtree_{name}_{GEOMETRY_COLUMN}" VALUES (?, ?, ?, ?, ?)"#
+            ))
+            .bind(rowid)
+            .bind(bbox[0])
+            .bind(bbox[2])
+            .bind(bbox[1])
+            .bind(bbox[3])
+            .execute(&mut *tx)
+            .await?;
+            let e = extent.get_or_insert(bbox);
+            *e = [
+                e[0].min(bbox[0]),
+                e[1].min(bbox[1]),
+                e[2].max(bbox[2]),
+                e[3].max(bbox[3]),
+            ];
+        }
+    }
+
+    sqlx::query("INSERT INTO gpkg_contents (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id) VALUES (?, 'features', ?, ?, ?, ?, ?, ?)")
+        .bind(name)
+        .bind(name)
+        .bind(extent.map(|e| e[0]))
+        .bind(extent.map(|e| e[1]))
+        .bind(extent.map(|e| e[2]))
+        .bind(extent.map(|e| e[3]))
+        .bind(srid)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO gpkg_geometry_columns VALUES (?, ?, ?, ?, 0, 0)")
+        .bind(name)
+        .bind(GEOMETRY_COLUMN)
+        .bind(geometry_type(features))
+        .bind(srid)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO gpkg_extensions VALUES (?, ?, 'gpkg_rtree_index', 'http://www.geopackage.org/spec120/#extension_rtree', 'write-only')")
+        .bind(name)
+        .bind(GEOMETRY_COLUMN)
+        .execute(&mut *tx)
+        .await?;
+    let triggers = RTREE_TRIGGERS
+        .replace("<t>", name)
+        .replace("<c>", GEOMETRY_COLUMN)
+        .replace("<i>", FID_COLUMN);
+    tx.execute(triggers.as_str()).await?;
+    tx.commit().await?;
+    conn.close().await?;
+    Ok(gpkg)
+}
+
+/// Property columns with SQLite type of the first non-null value
+fn property_columns(features: &CoreFeatures) -> Vec<(String, &'static str)> {
+    let mut columns: Vec<(String, &'static str)> = Vec::new();
+    for feature in &features.features {
+        let Some(properties) = feature.properties.as_ref().and_then(Value::as_object) else {
+            continue;
+        };
+        for (key, value) in properties {
+            if key == FID_COLUMN || key == GEOMETRY_COLUMN {
+                continue;
+            }
+            let column_type = match value {
+                Value::Null => continue,
+                Value::Bool(_) => "BOOLEAN",
+                Value::Number(v) if v.is_f64() => "DOUBLE",
+                Value::Number(_) => "INTEGER",
+                _ => "TEXT",
+            };
+            if !columns.iter().any(|(column, _)| column == key) {
+                columns.push((key.clone(), column_type));
+            }
+        }
+    }
+    columns
+}
+
+/// GeoPackage geometry type name of all features
+fn geometry_type(features: &CoreFeatures) -> String {
+    let mut types = features
+        .features
+        .iter()
+        .filter_map(|feature| feature.geometry["type"].as_str());
+    match types.next() {
+        Some(first) if types.all(|geom_type| geom_type == first) => first.to_uppercase(),
+        _ => "GEOMETRY".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::gpkg::SqliteDatasource;
+    use crate::datasource::AutoscanCollectionDatasource;
+    use crate::filter_params::FilterParams;
+    use bbox_core::ogcapi::CoreFeature;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    fn features() -> CoreFeatures {
+        let feature = |id: &str, x: f64, y: f64, name: &str| CoreFeature {
+            type_: "Feature".to_string(),
+            geometry: json!({"type": "Point", "coordinates": [x, y]}),
+            properties: Some(json!({"name": name, "scalerank": 2, "pop": 1.5e5})),
+            id: Some(id.to_string()),
+            links: Vec::new(),
+            foreign_members: serde_json::Map::new(),
+        };
+        CoreFeatures {
+            type_: "FeatureCollection".to_string(),
+            links: Vec::new(),
+            time_stamp: None,
+            number_matched: None,
+            number_returned: None,
+            features: vec![
+                feature("3", 7.44, 46.95, "Bern"),
+                feature("7", 8.54, 47.37, "Zürich"),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn gpkg_download() {
+        let gpkg = features_to_gpkg("places", &features(), 4326, None)
+            .await
+            .unwrap();
+        let path = gpkg.path().clone();
+        let mut ds = SqliteDatasource::new_pool(&path.to_string_lossy())
+            .await
+            .unwrap();
+        let collections = ds.collections("").await.unwrap();
+        assert_eq!(collections.len(), 1);
+        let collection = &collections[0].collection;
+        assert_eq!(collection.id, "places");
+        assert_eq!(
+            collection
+                .extent
+                .as_ref()
+                .unwrap()
+                .spatial
+                .as_ref()
+                .unwrap()
+                .bbox,
+            vec![vec![7.44, 46.95, 8.54, 47.37]]
+        );
+
+        // Filter with R-tree index
+        let filter = FilterParams {
+            bbox: Some("8,47,9,48".to_string()),
+            ..Default::default()
+        };
+        let items = collections[0].source.items(&filter).await.unwrap();
+        assert_eq!(items.number_matched, Some(1));
+        let feature = &items.features[0];
+        assert_eq!(feature.id.as_deref(), Some("7"));
+        assert_eq!(
+            feature.properties,
+            Some(json!({"name": "Zürich", "scalerank": 2, "pop": 1.5e5}))
+        );
+        drop(ds);
+
+        let body = gpkg.into_stream().unwrap().collect::<Vec<_>>().await;
+        assert!(
+            body.iter()
+                .map(|chunk| chunk.as_ref().unwrap().len())
+                .sum::<usize>()
+                > 0
+        );
+        assert!(!path.exists());
+    }
+}
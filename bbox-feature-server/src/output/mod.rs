@@ -0,0 +1,114 @@
+//! Output formats of feature items.
+
+pub mod csv;
+#[cfg(feature = "flatgeobuf")]
+pub mod fgb;
+pub mod geojson;
+pub mod gpkg;
+
+use crate::error::Result;
+use bbox_core::ogcapi::{CoreFeature, CoreFeatures};
+
+/// Response format of an items request
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemsFormat {
+    Html,
+    GeoJson,
+    GeoJsonSeq,
+    Csv,
+    GeoPackage,
+    #[cfg(feature = "flatgeobuf")]
+    FlatGeobuf,
+}
+
+impl ItemsFormat {
+    /// Format of `f` query parameter
+    pub fn from_param(f: &str) -> Option<Self> {
+        match f {
+            "html" if cfg!(feature = "html") => Some(ItemsFormat::Html),
+            "json" | "geojson" => Some(ItemsFormat::GeoJson),
+            "geojsonseq" => Some(ItemsFormat::GeoJsonSeq),
+            "csv" => Some(ItemsFormat::Csv),
+            "gpkg" | "geopackage" => Some(ItemsFormat::GeoPackage),
+            #[cfg(feature = "flatgeobuf")]
+            "fgb" | "flatgeobuf" => Some(ItemsFormat::FlatGeobuf),
+            _ => None,
+        }
+    }
+
+    /// Format of preferred media type in `Accept` header
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "text/html" if cfg!(feature = "html") => Some(ItemsFormat::Html),
+            "application/geo+json" | "application/json" => Some(ItemsFormat::GeoJson),
+            "application/geo+json-seq" => Some(ItemsFormat::GeoJsonSeq),
+            "text/csv" => Some(ItemsFormat::Csv),
+            "application/geopackage+sqlite3" => Some(ItemsFormat::GeoPackage),
+            #[cfg(feature = "flatgeobuf")]
+            "application/flatgeobuf" => Some(ItemsFormat::FlatGeobuf),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ItemsFormat::Html => "text/html",
+            ItemsFormat::GeoJson => "application/geo+json",
+            ItemsFormat::GeoJsonSeq => "application/geo+json-seq",
+            ItemsFormat::Csv => "text/csv",
+            ItemsFormat::GeoPackage => "application/geopackage+sqlite3",
+            #[cfg(feature = "flatgeobuf")]
+            ItemsFormat::FlatGeobuf => "application/flatgeobuf",
+        }
+    }
+
+    /// Encoder for streamed responses
+    pub fn encoder(&self) -> Option<Box<dyn FeatureEncoder>> {
+        match self {
+            ItemsFormat::GeoJson => Some(Box::<geojson::GeoJsonEncoder>::default()),
+            ItemsFormat::GeoJsonSeq => Some(Box::new(geojson::GeoJsonSeqEncoder)),
+            ItemsFormat::Csv => Some(Box::<csv::CsvEncoder>::default()),
+            _ => None,
+        }
+    }
+}
+
+/// Streaming encoder of items responses
+pub trait FeatureEncoder {
+    /// Encode a feature, including the response header for the first feature
+    fn feature(&mut self, feature: &CoreFeature, buf: &mut Vec<u8>) -> Result<()>;
+    /// Complete the response with the collection members of `response`
+    fn finish(&mut self, response: CoreFeatures, buf: &mut Vec<u8>) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_format() {
+        assert_eq!(ItemsFormat::from_param("json"), Some(ItemsFormat::GeoJson));
+        assert_eq!(ItemsFormat::from_param("csv"), Some(ItemsFormat::Csv));
+        assert_eq!(ItemsFormat::from_param("xml"), None);
+        assert_eq!(
+            ItemsFormat::from_media_type("application/geo+json"),
+            Some(ItemsFormat::GeoJson)
+        );
+        assert_eq!(
+            ItemsFormat::from_media_type("application/geo+json-seq"),
+            Some(ItemsFormat::GeoJsonSeq)
+        );
+        assert_eq!(ItemsFormat::from_media_type("*/*"), None);
+        #[cfg(feature = "flatgeobuf")]
+        {
+            assert_eq!(
+                ItemsFormat::from_param("fgb"),
+                Some(ItemsFormat::FlatGeobuf)
+            );
+            assert_eq!(
+                ItemsFormat::from_media_type("application/flatgeobuf"),
+                Some(ItemsFormat::FlatGeobuf)
+            );
+        }
+    }
+}
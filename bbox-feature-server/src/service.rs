@@ -1,4 +1,5 @@
-use crate::config::FeatureServiceCfg;
+use crate::config::{FeatureServerCfg, FeatureServiceCfg};
+use crate::datasource::stac::{STAC_CONFORMANCE_CLASSES, STAC_VERSION};
 use crate::datasource::Datasources;
 use crate::inventory::Inventory;
 use async_trait::async_trait;
@@ -7,10 +8,12 @@ use bbox_core::config::{error_exit, CoreServiceCfg};
 use bbox_core::metrics::{no_metrics, NoMetrics};
 use bbox_core::ogcapi::{ApiLink, CoreCollection};
 use bbox_core::service::OgcApiService;
+use serde_json::json;
 
 #[derive(Clone)]
 pub struct FeatureService {
     pub inventory: Inventory,
+    pub server_cfg: FeatureServerCfg,
 }
 #[async_trait]
 impl OgcApiService for FeatureService {
@@ -32,8 +35,14 @@ impl OgcApiService for FeatureService {
                 .await
                 .unwrap_or_else(error_exit);
             inventory.add_collection(collection);
+            if let Some(access) = &cfg.access {
+                inventory.set_access(&cfg.name, access.clone());
+            }
+        }
+        FeatureService {
+            inventory,
+            server_cfg: config.server.clone(),
         }
-        FeatureService { inventory }
     }
     fn conformance_classes(&self) -> Vec<String> {
         let mut classes = vec![
@@ -41,8 +50,34 @@ impl OgcApiService for FeatureService {
             "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/core".to_string(),
             "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/geojson".to_string(),
             "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/oas30".to_string(),
-            // "http://www.opengis.net/spec/ogcapi-features-2/1.0/conf/crs".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-2/1.0/conf/crs".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/filter".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/features-filter".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/queryables".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-5/1.0/conf/schemas".to_string(),
+            "http://www.opengis.net/spec/ogcapi-features-5/1.0/conf/sortables".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/cql2-text".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/cql2-json".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/basic-cql2".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/advanced-comparison-operators".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/basic-spatial-functions".to_string(),
+            "http://www.opengis.net/spec/cql2/1.0/conf/temporal-functions".to_string(),
         ];
+        if self.inventory.has_transactions() {
+            classes.extend(vec![
+                "http://www.opengis.net/spec/ogcapi-features-4/1.0/conf/create-replace-delete"
+                    .to_string(),
+                "http://www.opengis.net/spec/ogcapi-features-4/1.0/conf/update".to_string(),
+                "http://www.opengis.net/spec/ogcapi-features-4/1.0/conf/features".to_string(),
+            ]);
+        }
+        if self.inventory.has_stac() {
+            classes.extend(
+                STAC_CONFORMANCE_CLASSES
+                    .iter()
+                    .map(|class| class.to_string()),
+            );
+        }
         if cfg!(feature = "html") {
             classes.extend(vec![
                 "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/html".to_string(),
@@ -51,17 +86,40 @@ impl OgcApiService for FeatureService {
         classes
     }
     fn landing_page_links(&self, api_base: &str) -> Vec<ApiLink> {
-        vec![ApiLink {
+        let mut links = vec![ApiLink {
             href: format!("{api_base}/collections"),
             rel: Some("data".to_string()),
             type_: Some("application/json".to_string()),
             title: Some("Information about the feature collections".to_string()),
             hreflang: None,
             length: None,
-        }]
+        }];
+        if self.inventory.has_stac() {
+            links.push(ApiLink {
+                href: format!("{api_base}/search"),
+                rel: Some("search".to_string()),
+                type_: Some("application/geo+json".to_string()),
+                title: Some("STAC item search".to_string()),
+                hreflang: None,
+                length: None,
+            });
+        }
+        links
+    }
+    fn landing_page_members(&self) -> serde_json::Map<String, serde_json::Value> {
+        if !self.inventory.has_stac() {
+            return serde_json::Map::new();
+        }
+        // Landing page as STAC catalog
+        let mut members = serde_json::Map::new();
+        members.insert("type".to_string(), json!("Catalog"));
+        members.insert("id".to_string(), json!("bbox"));
+        members.insert("stac_version".to_string(), json!(STAC_VERSION));
+        members
     }
     fn collections(&self) -> Vec<CoreCollection> {
-        self.inventory.collections()
+        // Public collections only
+        self.inventory.collections(None)
     }
     fn openapi_yaml(&self) -> Option<&str> {
         Some(include_str!("openapi.yaml"))